@@ -0,0 +1,762 @@
+#![allow(dead_code)]
+
+/*
+ * Solidity ABI（Application Binary Interface）
+ *      调用合约时的 calldata = 4 字节函数选择器 + 参数编码。
+ *      选择器是规范函数签名（如 "transfer(address,uint256)"）的 Keccak-256 哈希的前 4 字节。
+ *
+ * 参数编码分为 "头部" 和 "尾部":
+ *      - 静态类型（uintN、address、bool、bytesN 以及由它们组成的定长数组、元组）直接放在头部，每个占 32 字节的整数倍
+ *      - 动态类型（bytes、string、T[] 以及包含动态类型的数组、元组）在头部只放一个偏移量，真正的内容追加在尾部
+ *      偏移量是相对于当前这一层元组编码起始位置的字节数。
+ */
+
+use crate::crypto::hash::keccak256;
+use crate::evm::word::Word;
+use std::fmt;
+use std::iter;
+
+/// 定长数组 T[k] 允许的最大长度
+// NOTE 类型字符串可能来自不可信的输入，k 过大时计算头部大小会溢出，解码时也会循环很多次
+pub const MAX_FIXED_ARRAY_LEN: usize = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    /// bytes1 .. bytes32
+    FixedBytes(usize),
+    Bytes,
+    String,
+    /// T[]
+    Array(Box<ParamType>),
+    /// T[k]
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Uint(Word),
+    /// 二进制补码表示的有符号整数
+    Int(Word),
+    Address([u8; 20]),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    /// 类型字符串或函数签名写错了
+    InvalidSignature(String),
+    /// 传入的值和声明的类型对不上
+    TypeMismatch {
+        expected: ParamType,
+        found: Token,
+    },
+    /// 解码时读到了数据之外
+    OutOfBounds {
+        offset: usize,
+    },
+    /// 数据可以读出来，但不是规范编码（例如 bool 的值是 2，地址的高 12 字节不为 0）
+    InvalidData(&'static str),
+    SelectorMismatch,
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiError::InvalidSignature(s) => write!(f, "invalid signature: {}", s),
+            AbiError::TypeMismatch { expected, found } => {
+                write!(f, "expected {}, found {:?}", expected, found)
+            }
+            AbiError::OutOfBounds { offset } => write!(f, "read past end of data at {}", offset),
+            AbiError::InvalidData(why) => write!(f, "invalid data: {}", why),
+            AbiError::SelectorMismatch => write!(f, "function selector does not match"),
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+impl fmt::Display for ParamType {
+    /// 规范类型名，例如 uint 要写成 uint256，元组写成 (a,b)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::Address => write!(f, "address"),
+            ParamType::Bool => write!(f, "bool"),
+            ParamType::FixedBytes(n) => write!(f, "bytes{}", n),
+            ParamType::Bytes => write!(f, "bytes"),
+            ParamType::String => write!(f, "string"),
+            ParamType::Array(inner) => write!(f, "{}[]", inner),
+            ParamType::FixedArray(inner, n) => write!(f, "{}[{}]", inner, n),
+            ParamType::Tuple(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// 解析类型和函数签名
+
+/// 在最外层的逗号处切分，括号里的逗号属于嵌套的元组
+fn split_top_level(s: &str) -> Result<Vec<&str>, AbiError> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        if depth < 0 {
+            return Err(AbiError::InvalidSignature(s.to_string()));
+        }
+    }
+    if depth != 0 {
+        return Err(AbiError::InvalidSignature(s.to_string()));
+    }
+    parts.push(&s[start..]);
+    Ok(parts)
+}
+
+fn parse_sized(s: &str, prefix: &str, default: usize) -> Option<usize> {
+    let rest = s.strip_prefix(prefix)?;
+    if rest.is_empty() {
+        return Some(default);
+    }
+    rest.parse().ok()
+}
+
+impl ParamType {
+    pub fn parse(s: &str) -> Result<ParamType, AbiError> {
+        let s = s.trim();
+        let invalid = || AbiError::InvalidSignature(s.to_string());
+
+        // 数组后缀总是在最后: 先剥掉最外层的 [] 或 [k]
+        if let Some(body) = s.strip_suffix(']') {
+            let open = body.rfind('[').ok_or_else(invalid)?;
+            let inner = ParamType::parse(&body[..open])?;
+            let size = &body[open + 1..];
+            return if size.is_empty() {
+                Ok(ParamType::Array(Box::new(inner)))
+            } else {
+                match size.parse() {
+                    Ok(n @ 1..=MAX_FIXED_ARRAY_LEN) => {
+                        Ok(ParamType::FixedArray(Box::new(inner), n))
+                    }
+                    _ => Err(invalid()),
+                }
+            };
+        }
+
+        if let Some(body) = s.strip_prefix('(').and_then(|b| b.strip_suffix(')')) {
+            if body.trim().is_empty() {
+                return Ok(ParamType::Tuple(Vec::new()));
+            }
+            let items = split_top_level(body)?
+                .into_iter()
+                .map(ParamType::parse)
+                .collect::<Result<_, _>>()?;
+            return Ok(ParamType::Tuple(items));
+        }
+
+        match s {
+            "address" => return Ok(ParamType::Address),
+            "bool" => return Ok(ParamType::Bool),
+            "bytes" => return Ok(ParamType::Bytes),
+            "string" => return Ok(ParamType::String),
+            _ => {}
+        }
+        if let Some(bits) = parse_sized(s, "uint", 256) {
+            return match bits {
+                8..=256 if bits % 8 == 0 => Ok(ParamType::Uint(bits)),
+                _ => Err(invalid()),
+            };
+        }
+        if let Some(bits) = parse_sized(s, "int", 256) {
+            return match bits {
+                8..=256 if bits % 8 == 0 => Ok(ParamType::Int(bits)),
+                _ => Err(invalid()),
+            };
+        }
+        if let Some(n) = s.strip_prefix("bytes").and_then(|n| n.parse().ok()) {
+            return match n {
+                1..=32 => Ok(ParamType::FixedBytes(n)),
+                _ => Err(invalid()),
+            };
+        }
+        Err(invalid())
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, _) => inner.is_dynamic(),
+            ParamType::Tuple(items) => items.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// 在头部占用的字节数: 动态类型只占一个偏移量
+    fn head_size(&self) -> Result<usize, AbiError> {
+        if self.is_dynamic() {
+            return Ok(32);
+        }
+        let too_large = AbiError::InvalidData("static type is too large");
+        match self {
+            ParamType::FixedArray(inner, n) => inner.head_size()?.checked_mul(*n).ok_or(too_large),
+            ParamType::Tuple(items) => items.iter().try_fold(0usize, |sum, item| {
+                sum.checked_add(item.head_size()?).ok_or(too_large.clone())
+            }),
+            _ => Ok(32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<ParamType>,
+}
+
+impl Function {
+    /// 解析 "transfer(address,uint256)"，也接受带参数名的写法 "transfer(address to, uint256 amount)"
+    pub fn parse(signature: &str) -> Result<Function, AbiError> {
+        let invalid = || AbiError::InvalidSignature(signature.to_string());
+        let signature = signature.trim();
+        let open = signature.find('(').ok_or_else(invalid)?;
+        let name = signature[..open].trim();
+        let body = signature[open..]
+            .strip_prefix('(')
+            .and_then(|b| b.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+
+        let mut inputs = Vec::new();
+        if !body.trim().is_empty() {
+            for param in split_top_level(body)? {
+                // 去掉参数名: 类型之后第一个空白后面的部分
+                let param = param.trim();
+                let ty = match param.rfind(')') {
+                    Some(close) => {
+                        let (ty, rest) = param.split_at(close + 1);
+                        // 元组类型之后可能还有数组后缀
+                        let suffix = rest.split_whitespace().next().unwrap_or("");
+                        let suffix = if suffix.starts_with('[') { suffix } else { "" };
+                        format!("{}{}", ty, suffix)
+                    }
+                    None => param.split_whitespace().next().unwrap_or("").to_string(),
+                };
+                inputs.push(ParamType::parse(&ty)?);
+            }
+        }
+        Ok(Function {
+            name: name.to_string(),
+            inputs,
+        })
+    }
+
+    pub fn signature(&self) -> String {
+        let types: Vec<String> = self.inputs.iter().map(|t| t.to_string()).collect();
+        format!("{}({})", self.name, types.join(","))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    /// 生成完整的 calldata
+    pub fn encode_call(&self, args: &[Token]) -> Result<Vec<u8>, AbiError> {
+        let mut out = self.selector().to_vec();
+        out.extend(encode(&self.inputs, args)?);
+        Ok(out)
+    }
+
+    /// 从 calldata 中还原参数
+    pub fn decode_call(&self, calldata: &[u8]) -> Result<Vec<Token>, AbiError> {
+        if calldata.len() < 4 || calldata[..4] != self.selector() {
+            return Err(AbiError::SelectorMismatch);
+        }
+        decode(&self.inputs, &calldata[4..])
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// 编码
+
+fn pad_right(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(32) * 32, 0);
+    out
+}
+
+fn check_type(ty: &ParamType, token: &Token) -> Result<(), AbiError> {
+    let mismatch = || AbiError::TypeMismatch {
+        expected: ty.clone(),
+        found: token.clone(),
+    };
+    let ok = match (ty, token) {
+        (ParamType::Uint(bits), Token::Uint(v)) => v.bits() <= *bits,
+        (ParamType::Int(bits), Token::Int(v)) => {
+            v.sign_extend(Word::from_u64(*bits as u64 / 8 - 1)) == *v
+        }
+        (ParamType::Address, Token::Address(_)) | (ParamType::Bool, Token::Bool(_)) => true,
+        (ParamType::FixedBytes(n), Token::FixedBytes(b)) => b.len() == *n,
+        (ParamType::Bytes, Token::Bytes(_)) | (ParamType::String, Token::String(_)) => true,
+        (ParamType::Array(_), Token::Array(_)) => true,
+        (ParamType::FixedArray(_, n), Token::FixedArray(items)) => items.len() == *n,
+        (ParamType::Tuple(types), Token::Tuple(items)) => types.len() == items.len(),
+        _ => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(mismatch())
+    }
+}
+
+fn encode_value(ty: &ParamType, token: &Token) -> Result<Vec<u8>, AbiError> {
+    check_type(ty, token)?;
+    Ok(match (ty, token) {
+        (_, Token::Uint(v)) | (_, Token::Int(v)) => v.to_be_bytes().to_vec(),
+        (_, Token::Address(addr)) => {
+            let mut out = vec![0u8; 12];
+            out.extend_from_slice(addr);
+            out
+        }
+        (_, Token::Bool(b)) => Word::from(*b).to_be_bytes().to_vec(),
+        (_, Token::FixedBytes(bytes)) => pad_right(bytes),
+        (_, Token::Bytes(bytes)) => encode_bytes(bytes),
+        (_, Token::String(s)) => encode_bytes(s.as_bytes()),
+        (ParamType::Array(inner), Token::Array(items)) => {
+            let mut out = Word::from_u64(items.len() as u64).to_be_bytes().to_vec();
+            out.extend(encode_seq(iter::repeat(&**inner).zip(items))?);
+            out
+        }
+        // NOTE check_type 已经保证了 items 正好有 n 个
+        (ParamType::FixedArray(inner, _), Token::FixedArray(items)) => {
+            encode_seq(iter::repeat(&**inner).zip(items))?
+        }
+        (ParamType::Tuple(types), Token::Tuple(items)) => encode(types, items)?,
+        _ => unreachable!("check_type has rejected other combinations"),
+    })
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Word::from_u64(bytes.len() as u64).to_be_bytes().to_vec();
+    out.extend(pad_right(bytes));
+    out
+}
+
+/// 按元组规则编码一组值: 先写所有头部，再依次追加动态值的尾部
+pub fn encode(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>, AbiError> {
+    if types.len() != tokens.len() {
+        return Err(AbiError::TypeMismatch {
+            expected: ParamType::Tuple(types.to_vec()),
+            found: Token::Tuple(tokens.to_vec()),
+        });
+    }
+    encode_seq(types.iter().zip(tokens))
+}
+
+/// 数组的元素类型都相同，用 iter::repeat 重复同一个类型，不必为每个元素复制一份 ParamType
+fn encode_seq<'a, I>(pairs: I) -> Result<Vec<u8>, AbiError>
+where
+    I: Iterator<Item = (&'a ParamType, &'a Token)> + Clone,
+{
+    let head_len = pairs.clone().try_fold(0usize, |sum, (ty, _)| {
+        sum.checked_add(ty.head_size()?)
+            .ok_or(AbiError::InvalidData("static type is too large"))
+    })?;
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+    for (ty, token) in pairs {
+        let encoded = encode_value(ty, token)?;
+        if ty.is_dynamic() {
+            head.extend(Word::from_u64((head_len + tail.len()) as u64).to_be_bytes());
+            tail.extend(encoded);
+        } else {
+            head.extend(encoded);
+        }
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+// ---------------------------------------------------------------------------------------------
+// 解码
+
+fn read_word(data: &[u8], offset: usize) -> Result<Word, AbiError> {
+    let bytes = offset
+        .checked_add(32)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(AbiError::OutOfBounds { offset })?;
+    Ok(Word::from_be_slice(bytes))
+}
+
+fn read_usize(data: &[u8], offset: usize) -> Result<usize, AbiError> {
+    read_word(data, offset)?
+        .to_u64()
+        .filter(|v| *v <= data.len() as u64)
+        .map(|v| v as usize)
+        .ok_or(AbiError::OutOfBounds { offset })
+}
+
+fn read_bytes(data: &[u8], offset: usize) -> Result<Vec<u8>, AbiError> {
+    let len = read_usize(data, offset)?;
+    let start = offset + 32;
+    let bytes = data
+        .get(start..start + len)
+        .ok_or(AbiError::OutOfBounds { offset: start })?;
+    // NOTE 严格模式: 填充部分必须是 0
+    let padded_end = start + len.div_ceil(32) * 32;
+    let padding = data
+        .get(start + len..padded_end)
+        .ok_or(AbiError::OutOfBounds { offset: start })?;
+    if padding.iter().any(|b| *b != 0) {
+        return Err(AbiError::InvalidData("non-zero padding"));
+    }
+    Ok(bytes.to_vec())
+}
+
+/// 解码单个值，data 是当前元组编码的起始位置，offset 是该值在头部的位置
+fn decode_value(ty: &ParamType, data: &[u8], offset: usize) -> Result<Token, AbiError> {
+    // 动态类型: 头部是一个指向内容的偏移量
+    let at = if ty.is_dynamic() {
+        read_usize(data, offset)?
+    } else {
+        offset
+    };
+    let rest = || data.get(at..).ok_or(AbiError::OutOfBounds { offset: at });
+
+    match ty {
+        ParamType::Uint(bits) => {
+            let v = read_word(data, at)?;
+            if v.bits() > *bits {
+                return Err(AbiError::InvalidData("uint out of range"));
+            }
+            Ok(Token::Uint(v))
+        }
+        ParamType::Int(bits) => {
+            let v = read_word(data, at)?;
+            if v.sign_extend(Word::from_u64(*bits as u64 / 8 - 1)) != v {
+                return Err(AbiError::InvalidData("int is not sign-extended"));
+            }
+            Ok(Token::Int(v))
+        }
+        ParamType::Address => {
+            let bytes = read_word(data, at)?.to_be_bytes();
+            if bytes[..12].iter().any(|b| *b != 0) {
+                return Err(AbiError::InvalidData("address has dirty high bytes"));
+            }
+            Ok(Token::Address(bytes[12..].try_into().unwrap()))
+        }
+        ParamType::Bool => match read_word(data, at)?.to_u64() {
+            Some(0) => Ok(Token::Bool(false)),
+            Some(1) => Ok(Token::Bool(true)),
+            _ => Err(AbiError::InvalidData("bool must be 0 or 1")),
+        },
+        ParamType::FixedBytes(n) => {
+            let bytes = read_word(data, at)?.to_be_bytes();
+            if bytes[*n..].iter().any(|b| *b != 0) {
+                return Err(AbiError::InvalidData("non-zero padding"));
+            }
+            Ok(Token::FixedBytes(bytes[..*n].to_vec()))
+        }
+        ParamType::Bytes => Ok(Token::Bytes(read_bytes(data, at)?)),
+        ParamType::String => String::from_utf8(read_bytes(data, at)?)
+            .map(Token::String)
+            .map_err(|_| AbiError::InvalidData("string is not utf-8")),
+        ParamType::Array(inner) => {
+            let len = read_usize(data, at)?;
+            // NOTE 元素的偏移量相对于长度字段之后的位置
+            let items = iter::repeat_n(&**inner, len);
+            Ok(Token::Array(decode_seq(items, &rest()?[32..])?))
+        }
+        ParamType::FixedArray(inner, n) => {
+            let items = iter::repeat_n(&**inner, *n);
+            Ok(Token::FixedArray(decode_seq(items, rest()?)?))
+        }
+        ParamType::Tuple(types) => Ok(Token::Tuple(decode(types, rest()?)?)),
+    }
+}
+
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, AbiError> {
+    decode_seq(types.iter(), data)
+}
+
+fn decode_seq<'a>(
+    types: impl Iterator<Item = &'a ParamType>,
+    data: &[u8],
+) -> Result<Vec<Token>, AbiError> {
+    let mut tokens = Vec::new();
+    let mut offset = 0usize;
+    for ty in types {
+        let size = ty.head_size()?;
+        tokens.push(decode_value(ty, data, offset)?);
+        offset = offset
+            .checked_add(size)
+            .ok_or(AbiError::OutOfBounds { offset })?;
+    }
+    Ok(tokens)
+}
+
+// ---------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+fn unhex(s: &str) -> Vec<u8> {
+    let s: String = s.split_whitespace().collect();
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_parse_signatures() {
+    let f = Function::parse("transfer(address,uint256)").unwrap();
+    assert_eq!(f.inputs, vec![ParamType::Address, ParamType::Uint(256)]);
+    assert_eq!(f.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+
+    // uint 是 uint256 的别名，选择器用规范写法计算
+    let f = Function::parse("transfer(address to, uint amount)").unwrap();
+    assert_eq!(f.signature(), "transfer(address,uint256)");
+
+    let f = Function::parse("f((uint8,bytes)[2],string[],bool)").unwrap();
+    assert_eq!(
+        f.inputs[0],
+        ParamType::FixedArray(
+            Box::new(ParamType::Tuple(vec![ParamType::Uint(8), ParamType::Bytes])),
+            2
+        )
+    );
+    assert_eq!(f.signature(), "f((uint8,bytes)[2],string[],bool)");
+    assert!(f.inputs[0].is_dynamic());
+    assert!(!ParamType::parse("(uint8,bool)[3]").unwrap().is_dynamic());
+
+    assert_eq!(Function::parse("noargs()").unwrap().inputs, vec![]);
+    for bad in [
+        "f(uint7)",
+        "f(bytes33)",
+        "f(foo)",
+        "f(uint256",
+        "(uint256)",
+        "f((a,b)",
+        "f(uint256[0])",
+        "f(uint256[65537])",
+        "f(uint256[99999999999999999999999])",
+    ] {
+        assert!(Function::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_solidity_doc_examples() {
+    // NOTE Solidity 文档 "Contract ABI Specification" 中的例子
+    let baz = Function::parse("baz(uint32,bool)").unwrap();
+    let calldata = baz
+        .encode_call(&[Token::Uint(Word::from_u64(69)), Token::Bool(true)])
+        .unwrap();
+    assert_eq!(
+        calldata,
+        unhex(
+            "cdcd77c0
+             0000000000000000000000000000000000000000000000000000000000000045
+             0000000000000000000000000000000000000000000000000000000000000001"
+        )
+    );
+
+    let sam = Function::parse("sam(bytes,bool,uint256[])").unwrap();
+    let args = vec![
+        Token::Bytes(b"dave".to_vec()),
+        Token::Bool(true),
+        Token::Array((1..=3).map(|i| Token::Uint(Word::from_u64(i))).collect()),
+    ];
+    let calldata = sam.encode_call(&args).unwrap();
+    assert_eq!(
+        calldata,
+        unhex(
+            "a5643bf2
+             0000000000000000000000000000000000000000000000000000000000000060
+             0000000000000000000000000000000000000000000000000000000000000001
+             00000000000000000000000000000000000000000000000000000000000000a0
+             0000000000000000000000000000000000000000000000000000000000000004
+             6461766500000000000000000000000000000000000000000000000000000000
+             0000000000000000000000000000000000000000000000000000000000000003
+             0000000000000000000000000000000000000000000000000000000000000001
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000003"
+        )
+    );
+    assert_eq!(sam.decode_call(&calldata), Ok(args));
+
+    let f = Function::parse("f(uint256,uint32[],bytes10,bytes)").unwrap();
+    let args = vec![
+        Token::Uint(Word::from_u64(0x123)),
+        Token::Array(vec![
+            Token::Uint(Word::from_u64(0x456)),
+            Token::Uint(Word::from_u64(0x789)),
+        ]),
+        Token::FixedBytes(b"1234567890".to_vec()),
+        Token::Bytes(b"Hello, world!".to_vec()),
+    ];
+    let calldata = f.encode_call(&args).unwrap();
+    assert_eq!(
+        calldata,
+        unhex(
+            "8be65246
+             0000000000000000000000000000000000000000000000000000000000000123
+             0000000000000000000000000000000000000000000000000000000000000080
+             3132333435363738393000000000000000000000000000000000000000000000
+             00000000000000000000000000000000000000000000000000000000000000e0
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000456
+             0000000000000000000000000000000000000000000000000000000000000789
+             000000000000000000000000000000000000000000000000000000000000000d
+             48656c6c6f2c20776f726c642100000000000000000000000000000000000000"
+        )
+    );
+    assert_eq!(f.decode_call(&calldata), Ok(args));
+}
+
+#[test]
+fn test_nested_roundtrip_and_errors() {
+    let types = vec![
+        ParamType::parse("(string,int8)[]").unwrap(),
+        ParamType::parse("address").unwrap(),
+        ParamType::parse("string[2]").unwrap(),
+    ];
+    let tokens = vec![
+        Token::Array(vec![
+            Token::Tuple(vec![
+                Token::String(String::from("你好")),
+                Token::Int(Word::from_u64(5).wrapping_neg()),
+            ]),
+            Token::Tuple(vec![
+                Token::String(String::new()),
+                Token::Int(Word::from_u64(127)),
+            ]),
+        ]),
+        Token::Address([0xab; 20]),
+        Token::FixedArray(vec![
+            Token::String(String::from("a")),
+            Token::String(String::from("b")),
+        ]),
+    ];
+    let encoded = encode(&types, &tokens).unwrap();
+    assert_eq!(decode(&types, &encoded), Ok(tokens));
+
+    // 值超出类型范围
+    assert!(matches!(
+        encode(&[ParamType::Uint(8)], &[Token::Uint(Word::from_u64(256))]),
+        Err(AbiError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        encode(&[ParamType::Int(8)], &[Token::Int(Word::from_u64(128))]),
+        Err(AbiError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        encode(&[ParamType::Bool], &[Token::Address([0; 20])]),
+        Err(AbiError::TypeMismatch { .. })
+    ));
+
+    // 非规范或被截断的数据
+    let two = Word::from_u64(2).to_be_bytes();
+    assert_eq!(
+        decode(&[ParamType::Bool], &two),
+        Err(AbiError::InvalidData("bool must be 0 or 1"))
+    );
+    assert_eq!(
+        decode(&[ParamType::Address], &Word::MAX.to_be_bytes()),
+        Err(AbiError::InvalidData("address has dirty high bytes"))
+    );
+    assert!(matches!(
+        decode(&[ParamType::Bytes], &[0u8; 31]),
+        Err(AbiError::OutOfBounds { .. })
+    ));
+    // 偏移量指向数据之外
+    let mut bad = Word::from_u64(0x1000).to_be_bytes().to_vec();
+    bad.extend([0u8; 32]);
+    assert!(matches!(
+        decode(&[ParamType::String], &bad),
+        Err(AbiError::OutOfBounds { .. })
+    ));
+    assert!(matches!(
+        decode(
+            &[ParamType::Uint(8), ParamType::parse("(bool,bool)").unwrap()],
+            &[0u8; 32]
+        ),
+        Err(AbiError::OutOfBounds { .. })
+    ));
+
+    let f = Function::parse("f(uint256)").unwrap();
+    assert_eq!(f.decode_call(&[0, 1, 2]), Err(AbiError::SelectorMismatch));
+
+    // NOTE 每一层都合法，但嵌套之后头部大小超出了 usize
+    let huge = [ParamType::parse("uint256[65536][65536][65536][65536]").unwrap()];
+    assert_eq!(
+        decode(&huge, &[0u8; 64]),
+        Err(AbiError::InvalidData("static type is too large"))
+    );
+    assert_eq!(
+        encode(&huge, &[Token::FixedArray(vec![])]).unwrap_err(),
+        AbiError::InvalidData("static type is too large")
+    );
+}
+
+#[test]
+fn test_drive_evm_contract() {
+    use crate::evm::opcode::assemble;
+    use crate::evm::vm::{execute, Context, Storage};
+
+    // add(uint256,uint256): 忽略选择器，直接读取两个参数并返回它们的和
+    let code = assemble(
+        "PUSH1 0x04 CALLDATALOAD PUSH1 0x24 CALLDATALOAD ADD \
+         PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN",
+    )
+    .unwrap();
+    let add = Function::parse("add(uint256,uint256)").unwrap();
+    let calldata = add
+        .encode_call(&[
+            Token::Uint(Word::from_u64(40)),
+            Token::Uint(Word::from_u64(2)),
+        ])
+        .unwrap();
+
+    let ctx = Context {
+        calldata,
+        gas_limit: 100_000,
+        ..Context::default()
+    };
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert_eq!(
+        decode(&[ParamType::Uint(256)], &outcome.output),
+        Ok(vec![Token::Uint(Word::from_u64(42))])
+    );
+}
@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+
+/*
+ * 有符号定点小数
+ *      finance::fixed::Fixed 是 u128 上的 18 位小数，只能表示非负数，范围也只有 3.4e20。
+ *      Decimal 把同样的 "真实值 * 10^18" 存进 I256，可以表示负数（盈亏、价格变化），
+ *      整数部分大约有 58 位十进制数字。
+ *
+ * NOTE 两个类型分工不同，所以都保留:
+ *      Fixed     协议内部的记账: 余额、储备、利率都不会是负数，每次乘除都要指定向上或向下取整（对协议有利）
+ *      Decimal   通用的有符号计算: 报表、盈亏、差值，统一向 0 取整，不关心舍入对谁有利
+ *      两者的精度相同，Fixed 可以无损地转换成 Decimal（见 finance::fixed）。
+ *
+ * 乘除法的中间结果 a * b 可能超过 256 位，所以用 512 位的乘积再做除法（I256::checked_mul_div），
+ * 只要最终结果放得下就能算出来；结果本身溢出时返回 None 而不是悄悄截断。
+ * 所有结果都向 0 取整，和整数除法一致。
+ */
+
+use super::int::I256;
+use super::uint::{ParseBigIntError, U256};
+use std::fmt;
+use std::str::FromStr;
+
+pub const DECIMALS: usize = 18;
+
+fn scale() -> I256 {
+    I256::from(1_000_000_000_000_000_000i64)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Decimal(I256);
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(I256::ZERO);
+
+    pub fn one() -> Decimal {
+        Decimal(scale())
+    }
+
+    pub const fn from_raw(raw: I256) -> Decimal {
+        Decimal(raw)
+    }
+
+    pub const fn raw(self) -> I256 {
+        self.0
+    }
+
+    pub fn from_int(n: i128) -> Option<Decimal> {
+        I256::from(n).checked_mul(scale()).map(Decimal)
+    }
+
+    /// 去掉小数部分，向 0 取整
+    pub fn trunc(self) -> I256 {
+        self.0.checked_div(scale()).unwrap()
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn checked_neg(self) -> Option<Decimal> {
+        self.0.checked_neg().map(Decimal)
+    }
+
+    pub fn checked_add(self, rhs: Decimal) -> Option<Decimal> {
+        self.0.checked_add(rhs.0).map(Decimal)
+    }
+
+    pub fn checked_sub(self, rhs: Decimal) -> Option<Decimal> {
+        self.0.checked_sub(rhs.0).map(Decimal)
+    }
+
+    /// (a * b) / 10^18
+    pub fn checked_mul(self, rhs: Decimal) -> Option<Decimal> {
+        self.0.checked_mul_div(rhs.0, scale()).map(Decimal)
+    }
+
+    /// (a * 10^18) / b，除数为 0 返回 None
+    pub fn checked_div(self, rhs: Decimal) -> Option<Decimal> {
+        self.0.checked_mul_div(scale(), rhs.0).map(Decimal)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseBigIntError;
+
+    /// "-1.25"、"+3"、"0.000000000000000001"，小数位超过 18 位视为错误而不是悄悄截断
+    fn from_str(s: &str) -> Result<Decimal, ParseBigIntError> {
+        let (negative, body) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+        if int.is_empty() {
+            return Err(ParseBigIntError::Empty);
+        }
+        if let Some(c) = int
+            .chars()
+            .chain(frac.chars())
+            .find(|c| !c.is_ascii_digit())
+        {
+            return Err(ParseBigIntError::InvalidDigit(c));
+        }
+        if frac.len() > DECIMALS {
+            return Err(ParseBigIntError::Overflow);
+        }
+        let digits = format!("{}{:0<width$}", int, frac, width = DECIMALS);
+        let magnitude = U256::from_str_radix(&digits, 10)?;
+        let sign = if negative { "-" } else { "" };
+        // NOTE 借用 I256 的解析来处理 -2^255 这个边界，不必在这里重复判断范围
+        format!("{}{}", sign, magnitude).parse().map(Decimal)
+    }
+}
+
+impl fmt::Display for Decimal {
+    /// 去掉小数部分末尾的 0，整数不带小数点
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!("{:0>width$}", self.0.unsigned_abs(), width = DECIMALS + 1);
+        let (int, frac) = digits.split_at(digits.len() - DECIMALS);
+        let frac = frac.trim_end_matches('0');
+        let sign = if self.is_negative() { "-" } else { "" };
+        if frac.is_empty() {
+            write!(f, "{}{}", sign, int)
+        } else {
+            write!(f, "{}{}.{}", sign, int, frac)
+        }
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decimal({})", self)
+    }
+}
+
+#[test]
+fn test_decimal() {
+    let d = |s: &str| s.parse::<Decimal>().unwrap();
+    assert_eq!(d("0.1").checked_add(d("0.2")), Some(d("0.3")));
+    assert_eq!(d("1").checked_sub(d("1.25")).unwrap().to_string(), "-0.25");
+    assert_eq!(d("-1.5").checked_mul(d("2")), Some(d("-3")));
+    assert_eq!(d("-1.5").checked_mul(d("-1.5")), Some(d("2.25")));
+    assert_eq!(
+        d("1").checked_div(d("-3")).unwrap().to_string(),
+        "-0.333333333333333333"
+    );
+    assert_eq!(d("1").checked_div(Decimal::ZERO), None);
+    assert_eq!(d("-7.9").trunc(), I256::from(-7i64));
+    assert_eq!(Decimal::from_int(-42).unwrap().to_string(), "-42");
+    assert_eq!(d("+0.000000000000000001").raw(), I256::ONE);
+    assert_eq!(d("-0").to_string(), "0");
+
+    assert_eq!("".parse::<Decimal>(), Err(ParseBigIntError::Empty));
+    assert_eq!(".5".parse::<Decimal>(), Err(ParseBigIntError::Empty));
+    assert_eq!(
+        "1.2x".parse::<Decimal>(),
+        Err(ParseBigIntError::InvalidDigit('x'))
+    );
+    assert_eq!(
+        "1.0000000000000000001".parse::<Decimal>(),
+        Err(ParseBigIntError::Overflow)
+    );
+
+    // 超过 u128 的金额，Fixed 已经放不下了
+    let big = d("1000000000000000000000000000000.5");
+    assert_eq!(
+        big.checked_mul(d("2")).unwrap().to_string(),
+        "2000000000000000000000000000001"
+    );
+    // 两个 1e29 量级的数相乘，乘积的原始值 a.raw() * b.raw() 超过 256 位，但结果本身放得下
+    let a = d("123456789012345678901234567890.5");
+    let b = d("-300000000000000000000000000000.25");
+    let product = d("-37037036703703703670370370367180864197253086419725308641972.625");
+    assert_eq!(a.checked_mul(b), Some(product));
+    assert_eq!(product.checked_div(b), Some(a));
+    assert_eq!(product.checked_mul(d("2")), None);
+    let max = Decimal::from_raw(I256::max_value());
+    assert_eq!(max.checked_add(Decimal::from_raw(I256::ONE)), None);
+    assert_eq!(max.checked_mul(d("2")), None);
+    assert_eq!(max.to_string().parse::<Decimal>(), Ok(max));
+}
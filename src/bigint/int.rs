@@ -0,0 +1,335 @@
+#![allow(dead_code)]
+
+/*
+ * 256 位有符号整数（二进制补码）
+ *      和 i8..i128 一样，负数 -x 存成 2^256 - x，最高位是符号位。
+ *      取值范围是 [-2^255, 2^255 - 1]，MIN 没有对应的正数，所以 -MIN、MIN / -1、MIN.abs() 都会溢出。
+ *
+ * 加减法的比特运算和无符号完全相同，区别只在于怎么判断溢出:
+ *      两个同号数相加，结果的符号变了，就是溢出。
+ */
+
+use super::uint::{ParseBigIntError, U256};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct I256(U256);
+
+impl I256 {
+    pub const ZERO: I256 = I256(U256::ZERO);
+    pub const ONE: I256 = I256(U256::ONE);
+    pub const MINUS_ONE: I256 = I256(U256::MAX);
+
+    pub fn max_value() -> I256 {
+        I256(U256::MAX.shr(1))
+    }
+
+    pub fn min_value() -> I256 {
+        I256(U256::ONE.shl(255))
+    }
+
+    /// 直接使用补码比特
+    pub const fn from_bits(bits: U256) -> I256 {
+        I256(bits)
+    }
+
+    pub const fn to_bits(self) -> U256 {
+        self.0
+    }
+
+    pub fn from_i128(v: i128) -> I256 {
+        let magnitude = U256::from(v.unsigned_abs());
+        if v < 0 {
+            I256(magnitude.wrapping_neg())
+        } else {
+            I256(magnitude)
+        }
+    }
+
+    pub fn to_i128(self) -> Option<i128> {
+        let magnitude = self.unsigned_abs().to_u128()?;
+        if self.is_negative() {
+            // NOTE i128::MIN 的绝对值刚好是 2^127，不能先转成 i128 再取负
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// 非负数转成 U256，负数返回 None
+    pub fn to_u256(self) -> Option<U256> {
+        if self.is_negative() {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn signum(self) -> I256 {
+        if self.is_negative() {
+            I256::MINUS_ONE
+        } else if self.is_zero() {
+            I256::ZERO
+        } else {
+            I256::ONE
+        }
+    }
+
+    /// 绝对值，MIN 的绝对值 2^255 仍然能用 U256 表示
+    pub fn unsigned_abs(self) -> U256 {
+        if self.is_negative() {
+            self.0.wrapping_neg()
+        } else {
+            self.0
+        }
+    }
+
+    pub fn wrapping_neg(self) -> I256 {
+        I256(self.0.wrapping_neg())
+    }
+
+    pub fn wrapping_add(self, rhs: I256) -> I256 {
+        I256(self.0.wrapping_add(rhs.0))
+    }
+
+    pub fn wrapping_sub(self, rhs: I256) -> I256 {
+        I256(self.0.wrapping_sub(rhs.0))
+    }
+
+    pub fn wrapping_mul(self, rhs: I256) -> I256 {
+        I256(self.0.wrapping_mul(rhs.0))
+    }
+
+    pub fn checked_neg(self) -> Option<I256> {
+        if self == I256::min_value() {
+            None
+        } else {
+            Some(self.wrapping_neg())
+        }
+    }
+
+    pub fn checked_abs(self) -> Option<I256> {
+        if self.is_negative() {
+            self.checked_neg()
+        } else {
+            Some(self)
+        }
+    }
+
+    pub fn checked_add(self, rhs: I256) -> Option<I256> {
+        let sum = self.wrapping_add(rhs);
+        if self.is_negative() == rhs.is_negative() && sum.is_negative() != self.is_negative() {
+            None
+        } else {
+            Some(sum)
+        }
+    }
+
+    pub fn checked_sub(self, rhs: I256) -> Option<I256> {
+        let diff = self.wrapping_sub(rhs);
+        if self.is_negative() != rhs.is_negative() && diff.is_negative() != self.is_negative() {
+            None
+        } else {
+            Some(diff)
+        }
+    }
+
+    /// 先按绝对值做无符号乘法，再看结果能否放进有符号的范围
+    pub fn checked_mul(self, rhs: I256) -> Option<I256> {
+        let magnitude = self.unsigned_abs().checked_mul(rhs.unsigned_abs())?;
+        I256::from_sign_and_magnitude(self.is_negative() != rhs.is_negative(), magnitude)
+    }
+
+    /// 向 0 取整，和 Rust 原生整数的 / 一致
+    pub fn checked_div(self, rhs: I256) -> Option<I256> {
+        let q = self.unsigned_abs().checked_div(rhs.unsigned_abs())?;
+        I256::from_sign_and_magnitude(self.is_negative() != rhs.is_negative(), q)
+    }
+
+    /// self * rhs / denom，向 0 取整，中间的乘积不会溢出（见 U256::checked_mul_div）
+    pub fn checked_mul_div(self, rhs: I256, denom: I256) -> Option<I256> {
+        let q = self
+            .unsigned_abs()
+            .checked_mul_div(rhs.unsigned_abs(), denom.unsigned_abs())?;
+        let negative = self.is_negative() ^ rhs.is_negative() ^ denom.is_negative();
+        // NOTE 商为 0 时不能带负号，from_sign_and_magnitude 对 0 两种符号都得到 0
+        I256::from_sign_and_magnitude(negative, q)
+    }
+
+    /// 余数的符号和被除数相同
+    pub fn checked_rem(self, rhs: I256) -> Option<I256> {
+        let r = self.unsigned_abs().checked_rem(rhs.unsigned_abs())?;
+        I256::from_sign_and_magnitude(self.is_negative(), r)
+    }
+
+    pub fn saturating_add(self, rhs: I256) -> I256 {
+        self.checked_add(rhs)
+            .unwrap_or_else(|| self.saturate(rhs.is_negative()))
+    }
+
+    pub fn saturating_sub(self, rhs: I256) -> I256 {
+        self.checked_sub(rhs)
+            .unwrap_or_else(|| self.saturate(!rhs.is_negative()))
+    }
+
+    pub fn saturating_mul(self, rhs: I256) -> I256 {
+        self.checked_mul(rhs)
+            .unwrap_or_else(|| self.saturate(self.is_negative() != rhs.is_negative()))
+    }
+
+    fn saturate(self, negative: bool) -> I256 {
+        if negative {
+            I256::min_value()
+        } else {
+            I256::max_value()
+        }
+    }
+
+    fn from_sign_and_magnitude(negative: bool, magnitude: U256) -> Option<I256> {
+        let limit = U256::ONE.shl(255);
+        if negative {
+            // 负数可以多一个: -2^255
+            if magnitude > limit {
+                return None;
+            }
+            Some(I256(magnitude.wrapping_neg()))
+        } else {
+            if magnitude >= limit {
+                return None;
+            }
+            Some(I256(magnitude))
+        }
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.signed_cmp(other.0)
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<i64> for I256 {
+    fn from(v: i64) -> I256 {
+        I256::from_i128(v as i128)
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(v: i128) -> I256 {
+        I256::from_i128(v)
+    }
+}
+
+impl FromStr for I256 {
+    type Err = ParseBigIntError;
+
+    /// 可选的 + / - 号，后面是十进制或 0x 开头的十六进制
+    fn from_str(s: &str) -> Result<I256, ParseBigIntError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let magnitude: U256 = digits.parse()?;
+        I256::from_sign_and_magnitude(negative, magnitude).ok_or(ParseBigIntError::Overflow)
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(!self.is_negative(), "", &self.unsigned_abs().to_string())
+    }
+}
+
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[test]
+fn test_i256_arithmetic() {
+    let a = I256::from(-7i64);
+    let b = I256::from(2i64);
+    assert_eq!(a.checked_div(b), Some(I256::from(-3i64)));
+    assert_eq!(a.checked_rem(b), Some(I256::from(-1i64)));
+    assert_eq!(a.checked_mul(b), Some(I256::from(-14i64)));
+    assert_eq!(a.checked_sub(b).and_then(|v| v.to_i128()), Some(-9));
+    assert!(a < b && a < I256::ZERO && I256::MINUS_ONE < I256::ZERO);
+    assert_eq!(a.signum(), I256::MINUS_ONE);
+    assert_eq!(b.checked_div(I256::ZERO), None);
+
+    // 和 i128 的行为对照
+    for (x, y) in [
+        (i128::MAX, 3),
+        (-1000, 7),
+        (i128::MIN + 1, -1),
+        (12345, -678),
+    ] {
+        let (bx, by) = (I256::from(x), I256::from(y));
+        assert_eq!(bx.checked_div(by).unwrap().to_i128(), Some(x / y));
+        assert_eq!(bx.checked_rem(by).unwrap().to_i128(), Some(x % y));
+        assert_eq!(bx.checked_add(by).and_then(I256::to_i128), x.checked_add(y));
+    }
+    assert_eq!(I256::from(i128::MIN).to_i128(), Some(i128::MIN));
+    assert_eq!(
+        I256::from(i128::MAX).wrapping_add(I256::ONE).to_i128(),
+        None
+    );
+}
+
+#[test]
+fn test_i256_overflow() {
+    let (min, max) = (I256::min_value(), I256::max_value());
+    assert_eq!(max.checked_add(I256::ONE), None);
+    assert_eq!(min.checked_sub(I256::ONE), None);
+    assert_eq!(max.wrapping_add(I256::ONE), min);
+    assert_eq!(min.checked_neg(), None);
+    assert_eq!(min.checked_abs(), None);
+    assert_eq!(min.unsigned_abs(), U256::ONE.shl(255));
+    assert_eq!(min.checked_div(I256::MINUS_ONE), None);
+    assert_eq!(min.checked_mul(I256::MINUS_ONE), None);
+    assert_eq!(min.checked_mul(I256::ONE), Some(min));
+
+    assert_eq!(max.saturating_add(max), max);
+    assert_eq!(min.saturating_sub(max), min);
+    assert_eq!(min.saturating_mul(I256::MINUS_ONE), max);
+    assert_eq!(min.saturating_mul(I256::from(2i64)), min);
+    // 一正一负相加永远不会溢出
+    assert_eq!(max.checked_add(min), Some(I256::MINUS_ONE));
+}
+
+#[test]
+fn test_i256_parse_and_display() {
+    let min: I256 =
+        "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+            .parse()
+            .unwrap();
+    assert_eq!(min, I256::min_value());
+    assert_eq!(
+        "57896044618658097711785492504343953926634992332820282019728792003956564819968"
+            .parse::<I256>(),
+        Err(ParseBigIntError::Overflow)
+    );
+    assert_eq!("-0x10".parse::<I256>(), Ok(I256::from(-16i64)));
+    assert_eq!("+42".parse::<I256>(), Ok(I256::from(42i64)));
+    assert_eq!(I256::from(-42i64).to_string(), "-42");
+    assert_eq!(format!("{:+}", I256::from(42i64)), "+42");
+    assert_eq!(format!("{:>5}", I256::from(-42i64)), "  -42");
+    assert_eq!(min.to_string().parse::<I256>(), Ok(min));
+}
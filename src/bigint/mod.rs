@@ -0,0 +1,5 @@
+pub(crate) mod decimal;
+pub(crate) mod int;
+pub(crate) mod uint;
+
+// NOTE test_base.rs 里的 i8..i128、u8..u128 不够用时，就需要自己用多个机器字拼出更大的整数
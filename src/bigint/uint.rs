@@ -0,0 +1,773 @@
+#![allow(dead_code)]
+
+/*
+ * 256 位无符号整数
+ *      Rust 最大的原生整数是 u128，这里用 4 个 u64 拼成一个 256 位整数，低位在前（小端排列）。
+ *      以太坊上的余额、价格都用 uint256 表示，EVM 的字长也是 256 位（evm 模块直接用它作为 Word），
+ *      finance 模块用它计算两个 u128 相乘的中间结果。
+ *
+ * 和原生整数一样，溢出的处理方式要在调用时显式选择:
+ *      wrapping_*      模 2^256，EVM 的算术就是这种语义
+ *      checked_*       溢出时返回 None
+ *      saturating_*    溢出时停在 0 或 MAX
+ *      overflowing_*   返回结果和是否溢出
+ * 有符号运算（SDIV、SLT、SAR 等）把同样的比特按二进制补码解释，更完整的有符号类型见 I256。
+ */
+
+use crate::error::DomainError;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    pub const fn from_u64(v: u64) -> U256 {
+        U256([v, 0, 0, 0])
+    }
+
+    /// 按大端解释最多 32 个字节，不足 32 字节时高位补 0（PUSH1..PUSH32 就是这么做的）
+    pub fn from_be_slice(bytes: &[u8]) -> U256 {
+        assert!(bytes.len() <= 32, "a word holds at most 32 bytes");
+        let mut buf = [0u8; 32];
+        buf[32 - bytes.len()..].copy_from_slice(bytes);
+        U256::from_be_bytes(buf)
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> U256 {
+        let mut limbs = [0u64; 4];
+        for (i, chunk) in bytes.chunks(8).enumerate() {
+            limbs[3 - i] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, chunk) in out.chunks_mut(8).enumerate() {
+            chunk.copy_from_slice(&self.0[3 - i].to_be_bytes());
+        }
+        out
+    }
+
+    pub fn low_u64(self) -> u64 {
+        self.0[0]
+    }
+
+    /// 值能放进 u64 时返回它，用于内存偏移、跳转目标等
+    pub fn to_u64(self) -> Option<u64> {
+        if self.0[1] == 0 && self.0[2] == 0 && self.0[3] == 0 {
+            Some(self.0[0])
+        } else {
+            None
+        }
+    }
+
+    pub fn to_u128(self) -> Option<u128> {
+        if self.0[2] == 0 && self.0[3] == 0 {
+            Some(((self.0[1] as u128) << 64) | self.0[0] as u128)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self == U256::ZERO
+    }
+
+    pub fn bit(self, i: usize) -> bool {
+        i < 256 && (self.0[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    /// 有效比特数，0 的有效比特数为 0
+    pub fn bits(self) -> usize {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return i * 64 + 64 - self.0[i].leading_zeros() as usize;
+            }
+        }
+        0
+    }
+
+    pub fn overflowing_add(self, rhs: U256) -> (U256, bool) {
+        let mut out = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (s1, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (s2, c2) = s1.overflowing_add(carry as u64);
+            *limb = s2;
+            carry = c1 || c2;
+        }
+        (U256(out), carry)
+    }
+
+    pub fn overflowing_sub(self, rhs: U256) -> (U256, bool) {
+        let mut out = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (d1, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (d2, b2) = d1.overflowing_sub(borrow as u64);
+            *limb = d2;
+            borrow = b1 || b2;
+        }
+        (U256(out), borrow)
+    }
+
+    pub fn wrapping_add(self, rhs: U256) -> U256 {
+        self.overflowing_add(rhs).0
+    }
+
+    pub fn wrapping_sub(self, rhs: U256) -> U256 {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// 完整的 512 位乘积，低位在前
+    fn mul_wide(self, rhs: U256) -> [u64; 8] {
+        let mut out = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let t = self.0[i] as u128 * rhs.0[j] as u128 + out[i + j] as u128 + carry;
+                out[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            out[i + 4] = carry as u64;
+        }
+        out
+    }
+
+    pub fn wrapping_mul(self, rhs: U256) -> U256 {
+        let wide = self.mul_wide(rhs);
+        U256([wide[0], wide[1], wide[2], wide[3]])
+    }
+
+    /// 无符号除法，返回 (商, 余数)；除数为 0 时 EVM 约定结果为 0
+    pub fn div_rem(self, rhs: U256) -> (U256, U256) {
+        if rhs.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        if self < rhs {
+            return (U256::ZERO, self);
+        }
+        // 二进制长除法
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for i in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder.0[0] |= 1;
+            }
+            if remainder >= rhs {
+                remainder = remainder.wrapping_sub(rhs);
+                quotient.0[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (quotient, remainder)
+    }
+
+    /// 512 位数除以 256 位数，返回 512 位的商和余数，除数不能为 0
+    fn wide_div_rem(wide: &[u64; 8], divisor: U256) -> ([u64; 8], U256) {
+        let mut quotient = [0u64; 8];
+        let mut remainder = U256::ZERO;
+        for i in (0..512).rev() {
+            // NOTE remainder < divisor，左移之后可能超过 256 位，用 overflow 标志补上
+            let overflow = remainder.bit(255);
+            remainder = remainder.shl(1);
+            if (wide[i / 64] >> (i % 64)) & 1 == 1 {
+                remainder.0[0] |= 1;
+            }
+            if overflow || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (quotient, remainder)
+    }
+
+    /// 512 位数对 modulus 取模，ADDMOD 和 MULMOD 的中间结果不能先截断成 256 位
+    fn wide_rem(wide: &[u64; 8], modulus: U256) -> U256 {
+        U256::wide_div_rem(wide, modulus).1
+    }
+
+    pub fn add_mod(self, rhs: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let (sum, carry) = self.overflowing_add(rhs);
+        let wide = [
+            sum.0[0],
+            sum.0[1],
+            sum.0[2],
+            sum.0[3],
+            carry as u64,
+            0,
+            0,
+            0,
+        ];
+        U256::wide_rem(&wide, modulus)
+    }
+
+    pub fn mul_mod(self, rhs: U256, modulus: U256) -> U256 {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        U256::wide_rem(&self.mul_wide(rhs), modulus)
+    }
+
+    /// 模 2^256 的快速幂
+    pub fn wrapping_pow(self, exp: U256) -> U256 {
+        let mut result = U256::ONE;
+        for i in (0..exp.bits()).rev() {
+            result = result.wrapping_mul(result);
+            if exp.bit(i) {
+                result = result.wrapping_mul(self);
+            }
+        }
+        result
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // checked / saturating
+
+    pub fn checked_add(self, rhs: U256) -> Option<U256> {
+        match self.overflowing_add(rhs) {
+            (v, false) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn checked_sub(self, rhs: U256) -> Option<U256> {
+        match self.overflowing_sub(rhs) {
+            (v, false) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// 512 位乘积的高 256 位不为 0 就是溢出
+    pub fn checked_mul(self, rhs: U256) -> Option<U256> {
+        let wide = self.mul_wide(rhs);
+        if wide[4..].iter().any(|limb| *limb != 0) {
+            return None;
+        }
+        Some(U256([wide[0], wide[1], wide[2], wide[3]]))
+    }
+
+    /// self * rhs / denom（向下取整），乘积保留完整的 512 位，只要最终的商放得下就不会溢出。
+    /// 除数为 0 或商超过 256 位时返回 None
+    pub fn checked_mul_div(self, rhs: U256, denom: U256) -> Option<U256> {
+        if denom.is_zero() {
+            return None;
+        }
+        let (q, _) = U256::wide_div_rem(&self.mul_wide(rhs), denom);
+        if q[4..].iter().any(|limb| *limb != 0) {
+            return None;
+        }
+        Some(U256([q[0], q[1], q[2], q[3]]))
+    }
+
+    /// 和原生整数一致: 除数为 0 时返回 None，而不是 EVM 的 0
+    pub fn checked_div(self, rhs: U256) -> Option<U256> {
+        if rhs.is_zero() {
+            None
+        } else {
+            Some(self.div_rem(rhs).0)
+        }
+    }
+
+    pub fn checked_rem(self, rhs: U256) -> Option<U256> {
+        if rhs.is_zero() {
+            None
+        } else {
+            Some(self.div_rem(rhs).1)
+        }
+    }
+
+    pub fn checked_pow(self, mut exp: u32) -> Option<U256> {
+        let mut base = self;
+        let mut acc = U256::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = acc.checked_mul(base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.checked_mul(base)?;
+            }
+        }
+        Some(acc)
+    }
+
+    pub fn saturating_add(self, rhs: U256) -> U256 {
+        self.checked_add(rhs).unwrap_or(U256::MAX)
+    }
+
+    pub fn saturating_sub(self, rhs: U256) -> U256 {
+        self.checked_sub(rhs).unwrap_or(U256::ZERO)
+    }
+
+    pub fn saturating_mul(self, rhs: U256) -> U256 {
+        self.checked_mul(rhs).unwrap_or(U256::MAX)
+    }
+
+    /// 模幂 self^exp mod modulus，每一步都用 512 位的中间结果取模，不会溢出。modulus 为 0 时返回 None
+    pub fn pow_mod(self, exp: U256, modulus: U256) -> Option<U256> {
+        if modulus.is_zero() {
+            return None;
+        }
+        let mut result = U256::ONE.div_rem(modulus).1;
+        let base = self.div_rem(modulus).1;
+        for i in (0..exp.bits()).rev() {
+            result = result.mul_mod(result, modulus);
+            if exp.bit(i) {
+                result = result.mul_mod(base, modulus);
+            }
+        }
+        Some(result)
+    }
+
+    /// 解析指定进制（2..=36）的数字串，不接受前缀和符号
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<U256, ParseBigIntError> {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+        // 允许用下划线分组，和 Rust 的字面量一样
+        let digits = s.trim_start_matches('_');
+        if digits.is_empty() {
+            return Err(ParseBigIntError::Empty);
+        }
+        let radix_word = U256::from_u64(radix as u64);
+        let mut value = U256::ZERO;
+        for c in digits.chars().filter(|c| *c != '_') {
+            let digit = c.to_digit(radix).ok_or(ParseBigIntError::InvalidDigit(c))?;
+            value = value
+                .checked_mul(radix_word)
+                .and_then(|v| v.checked_add(U256::from_u64(digit as u64)))
+                .ok_or(ParseBigIntError::Overflow)?;
+        }
+        Ok(value)
+    }
+
+    /// 十进制表示: 每次除以 10^19（u64 能放下的最大的 10 的幂），拼接余数
+    fn to_decimal(self) -> String {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        if self.is_zero() {
+            return String::from("0");
+        }
+        let mut chunks = Vec::new();
+        let mut rest = self;
+        while !rest.is_zero() {
+            let (q, r) = rest.div_rem(U256::from_u64(CHUNK));
+            chunks.push(r.low_u64());
+            rest = q;
+        }
+        let mut out = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            out.push_str(&format!("{:019}", chunk));
+        }
+        out
+    }
+
+    pub fn shl(self, shift: usize) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = (shift / 64, shift % 64);
+        let mut out = [0u64; 4];
+        for i in (limbs..4).rev() {
+            out[i] = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                out[i] |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(out)
+    }
+
+    pub fn shr(self, shift: usize) -> U256 {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let (limbs, bits) = (shift / 64, shift % 64);
+        let mut out = [0u64; 4];
+        for (i, limb) in out.iter_mut().enumerate().take(4 - limbs) {
+            *limb = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs < 3 {
+                *limb |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        U256(out)
+    }
+
+    // ---------------------------------------------------------------------------------------------
+    // 有符号解释（二进制补码）
+
+    pub fn is_negative(self) -> bool {
+        self.bit(255)
+    }
+
+    pub fn wrapping_neg(self) -> U256 {
+        (!self).wrapping_add(U256::ONE)
+    }
+
+    fn abs(self) -> U256 {
+        if self.is_negative() {
+            self.wrapping_neg()
+        } else {
+            self
+        }
+    }
+
+    /// 有符号除法，向 0 取整；-2^255 / -1 溢出后仍是 -2^255
+    pub fn signed_div(self, rhs: U256) -> U256 {
+        let (q, _) = self.abs().div_rem(rhs.abs());
+        if self.is_negative() != rhs.is_negative() {
+            q.wrapping_neg()
+        } else {
+            q
+        }
+    }
+
+    /// 有符号取余，结果的符号和被除数相同
+    pub fn signed_rem(self, rhs: U256) -> U256 {
+        let (_, r) = self.abs().div_rem(rhs.abs());
+        if self.is_negative() {
+            r.wrapping_neg()
+        } else {
+            r
+        }
+    }
+
+    pub fn signed_cmp(self, rhs: U256) -> Ordering {
+        match (self.is_negative(), rhs.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // 符号相同时，补码的无符号大小关系就是有符号大小关系
+            _ => self.cmp(&rhs),
+        }
+    }
+
+    /// 算术右移，高位补符号位
+    pub fn sar(self, shift: usize) -> U256 {
+        if !self.is_negative() {
+            return self.shr(shift);
+        }
+        if shift >= 256 {
+            return U256::MAX;
+        }
+        self.shr(shift) | U256::MAX.shl(256 - shift)
+    }
+
+    /// SIGNEXTEND: 把第 byte_index 个字节（从低位数，0 开始）的最高位扩展到更高的位
+    pub fn sign_extend(self, byte_index: U256) -> U256 {
+        let index = match byte_index.to_u64() {
+            Some(i) if i < 31 => i as usize,
+            _ => return self,
+        };
+        let sign_bit = index * 8 + 7;
+        let mask = U256::MAX.shl(sign_bit + 1);
+        if self.bit(sign_bit) {
+            self | mask
+        } else {
+            self & !mask
+        }
+    }
+
+    /// BYTE: 取大端表示下的第 i 个字节
+    pub fn byte(self, i: U256) -> U256 {
+        match i.to_u64() {
+            Some(i) if i < 32 => U256::from_u64(self.to_be_bytes()[i as usize] as u64),
+            _ => U256::ZERO,
+        }
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // NOTE 从最高的 limb 开始比较，数组自带的字典序是从下标 0 开始的，不能直接 derive
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl BitAnd for U256 {
+    type Output = U256;
+    fn bitand(self, rhs: U256) -> U256 {
+        U256([
+            self.0[0] & rhs.0[0],
+            self.0[1] & rhs.0[1],
+            self.0[2] & rhs.0[2],
+            self.0[3] & rhs.0[3],
+        ])
+    }
+}
+
+impl BitOr for U256 {
+    type Output = U256;
+    fn bitor(self, rhs: U256) -> U256 {
+        U256([
+            self.0[0] | rhs.0[0],
+            self.0[1] | rhs.0[1],
+            self.0[2] | rhs.0[2],
+            self.0[3] | rhs.0[3],
+        ])
+    }
+}
+
+impl BitXor for U256 {
+    type Output = U256;
+    fn bitxor(self, rhs: U256) -> U256 {
+        U256([
+            self.0[0] ^ rhs.0[0],
+            self.0[1] ^ rhs.0[1],
+            self.0[2] ^ rhs.0[2],
+            self.0[3] ^ rhs.0[3],
+        ])
+    }
+}
+
+impl Not for U256 {
+    type Output = U256;
+    fn not(self) -> U256 {
+        U256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(v: u64) -> U256 {
+        U256::from_u64(v)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(v: u128) -> U256 {
+        U256([v as u64, (v >> 64) as u64, 0, 0])
+    }
+}
+
+impl From<bool> for U256 {
+    fn from(b: bool) -> U256 {
+        U256::from_u64(b as u64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBigIntError {
+    Empty,
+    InvalidDigit(char),
+    Overflow,
+}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBigIntError::Empty => write!(f, "cannot parse integer from empty string"),
+            ParseBigIntError::InvalidDigit(c) => write!(f, "invalid digit `{}`", c),
+            ParseBigIntError::Overflow => write!(f, "number too large to fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl DomainError for ParseBigIntError {
+    const CODE: u16 = 2003;
+}
+
+impl FromStr for U256 {
+    type Err = ParseBigIntError;
+
+    /// 十进制，或者带 0x 前缀的十六进制
+    fn from_str(s: &str) -> Result<U256, ParseBigIntError> {
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => U256::from_str_radix(hex, 16),
+            None => U256::from_str_radix(s, 10),
+        }
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // NOTE 用 pad_integral 才能支持 {:>80} 这样的宽度和对齐参数
+        f.pad_integral(true, "", &self.to_decimal())
+    }
+}
+
+impl fmt::LowerHex for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = format!(
+            "{:x}{:016x}{:016x}{:016x}",
+            self.0[3], self.0[2], self.0[1], self.0[0]
+        );
+        let digits = hex.trim_start_matches('0');
+        f.pad_integral(true, "0x", if digits.is_empty() { "0" } else { digits })
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        let bytes = self.to_be_bytes();
+        // 去掉前导零，至少保留一个字节
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(31);
+        for b in &bytes[start..] {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_u256_wrapping() {
+    let max = U256::MAX;
+    assert_eq!(max.wrapping_add(U256::ONE), U256::ZERO);
+    assert_eq!(U256::ZERO.wrapping_sub(U256::ONE), max);
+
+    // 2^128 * 2^128 溢出成 0
+    let two_128 = U256::ONE.shl(128);
+    assert_eq!(two_128.wrapping_mul(two_128), U256::ZERO);
+    assert_eq!(two_128.shr(127), U256::from_u64(2));
+
+    let (q, r) = U256::from_u64(1000).div_rem(U256::from_u64(7));
+    assert_eq!((q, r), (U256::from_u64(142), U256::from_u64(6)));
+    let (q, r) = max.div_rem(two_128);
+    assert_eq!(q, U256::MAX.shr(128));
+    assert_eq!(r, U256::MAX.shr(128));
+
+    // NOTE 中间结果超过了 256 位: (2^256 - 1)^2 mod 7 = 1，(2^256 + 1) mod 2^255 = 1
+    assert_eq!(max.mul_mod(max, U256::from_u64(7)), U256::ONE);
+    assert_eq!(
+        max.add_mod(U256::from_u64(2), U256::ONE.shl(255)),
+        U256::ONE
+    );
+
+    assert_eq!(
+        U256::from_u64(3).wrapping_pow(U256::from_u64(5)),
+        U256::from_u64(243)
+    );
+    assert_eq!(
+        U256::from_u64(2).wrapping_pow(U256::from_u64(256)),
+        U256::ZERO
+    );
+}
+
+#[test]
+fn test_u256_signed() {
+    let minus_one = U256::MAX;
+    let minus_eight = U256::from_u64(8).wrapping_neg();
+
+    assert_eq!(
+        minus_eight.signed_div(U256::from_u64(2)),
+        U256::from_u64(4).wrapping_neg()
+    );
+    assert_eq!(
+        minus_eight.signed_rem(U256::from_u64(3)),
+        U256::from_u64(2).wrapping_neg()
+    );
+    assert_eq!(minus_one.signed_cmp(U256::ONE), Ordering::Less);
+    assert_eq!(minus_eight.sar(1), U256::from_u64(4).wrapping_neg());
+    assert_eq!(minus_one.sar(300), minus_one);
+
+    // 0xff 作为 1 字节的有符号数是 -1
+    assert_eq!(U256::from_u64(0xff).sign_extend(U256::ZERO), minus_one);
+    assert_eq!(
+        U256::from_u64(0x7f).sign_extend(U256::ZERO),
+        U256::from_u64(0x7f)
+    );
+    assert_eq!(
+        U256::from_u64(0x1234).byte(U256::from_u64(30)),
+        U256::from_u64(0x12)
+    );
+
+    let bytes = U256::from_u64(0x0102).to_be_bytes();
+    assert_eq!(&bytes[30..], &[1, 2]);
+    assert_eq!(U256::from_be_slice(&[1, 2]), U256::from_u64(0x0102));
+}
+
+#[test]
+fn test_u256_checked_and_saturating() {
+    let max = U256::MAX;
+    assert_eq!(max.checked_add(U256::ONE), None);
+    assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+    assert_eq!(max.saturating_add(U256::ONE), max);
+    assert_eq!(U256::ZERO.saturating_sub(U256::ONE), U256::ZERO);
+
+    let two_128 = U256::ONE.shl(128);
+    assert_eq!(two_128.checked_mul(two_128), None);
+    assert_eq!(two_128.saturating_mul(two_128), max);
+    assert_eq!(
+        two_128.checked_mul(U256::from_u64(3)),
+        Some(U256::from_u64(3).shl(128))
+    );
+    assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+    assert_eq!(U256::ONE.checked_rem(U256::ZERO), None);
+
+    assert_eq!(U256::from_u64(2).checked_pow(255), Some(U256::ONE.shl(255)));
+    assert_eq!(U256::from_u64(2).checked_pow(256), None);
+    assert_eq!(
+        U256::from_u64(10).checked_pow(77).map(|v| v.bits()),
+        Some(256)
+    );
+    assert_eq!(U256::from_u64(10).checked_pow(78), None);
+}
+
+#[test]
+fn test_u256_pow_mod() {
+    // 费马小定理: a^(p-1) = 1 (mod p)，p = 2^255 - 19
+    let p = U256::ONE.shl(255).wrapping_sub(U256::from_u64(19));
+    let a = U256::from_u64(123_456_789);
+    assert_eq!(a.pow_mod(p.wrapping_sub(U256::ONE), p), Some(U256::ONE));
+    // 3^200 mod 1000 = 1
+    assert_eq!(
+        U256::from_u64(3).pow_mod(U256::from_u64(200), U256::from_u64(1000)),
+        Some(U256::ONE)
+    );
+    assert_eq!(
+        U256::from_u64(5).pow_mod(U256::ZERO, U256::ONE),
+        Some(U256::ZERO)
+    );
+    assert_eq!(U256::ONE.pow_mod(U256::ONE, U256::ZERO), None);
+}
+
+#[test]
+fn test_u256_parse_and_display() {
+    let max: U256 =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+            .parse()
+            .unwrap();
+    assert_eq!(max, U256::MAX);
+    assert_eq!(max.to_string().len(), 78);
+    assert_eq!(
+        "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+            .parse::<U256>(),
+        Err(ParseBigIntError::Overflow)
+    );
+    assert_eq!("0x".parse::<U256>(), Err(ParseBigIntError::Empty));
+    assert_eq!(
+        "12a".parse::<U256>(),
+        Err(ParseBigIntError::InvalidDigit('a'))
+    );
+    assert_eq!(
+        "-1".parse::<U256>(),
+        Err(ParseBigIntError::InvalidDigit('-'))
+    );
+
+    let n: U256 = "0xDEAD_beef".parse().unwrap();
+    assert_eq!(n, U256::from_u64(0xdead_beef));
+    assert_eq!(format!("{:x}", n), "deadbeef");
+    assert_eq!(format!("{:#x}", U256::ZERO), "0x0");
+    assert_eq!(U256::ZERO.to_string(), "0");
+
+    // 跨过 10^19 的分块边界时中间的 0 不能丢
+    let ten_19 = U256::from_u64(10).checked_pow(19).unwrap();
+    assert_eq!(ten_19.to_string(), "10000000000000000000");
+    assert_eq!(format!("{:>6}", U256::from_u64(42)), "    42");
+    assert_eq!(U256::from_str_radix("1010", 2), Ok(U256::from_u64(10)));
+}
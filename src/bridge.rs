@@ -0,0 +1,666 @@
+#![allow(dead_code)]
+
+/*
+ * 跨链桥（模拟）
+ *      两条链互相不知道对方的状态，桥要解决的问题是: 链 B 怎么相信 "链 A 上确实发生了某件事"。
+ *
+ * 这里的做法是轻客户端 + Merkle 证明:
+ *      - 每条链把本区块内的桥事件组成 Merkle 树，树根写进区块的 payload
+ *      - 每条链内置一个对方链的轻客户端，只同步区块头并校验工作量证明
+ *      - 中继者（relayer）把区块头和 "事件 + Merkle 证明" 搬到另一条链上，它不需要被信任:
+ *        伪造的事件算不出区块头里的树根；伪造的区块头即使挖够了难度，也只是一条分支，
+ *        轻客户端和 Blockchain::resolve_fork 一样按累计工作量选链，诚实的链挖得更快，伪造的分支会被甩开
+ *      NOTE 这和比特币的安全假设相同: 攻击者的算力超过诚实的矿工时，伪造的分支就能胜出。
+ *      事件还要等 CONFIRMATIONS 个区块才被接受，就是为了让伪造的分支有足够的时间被甩开
+ *
+ * 资产流转:
+ *      锁定-铸造: 用户在原生链 A 上把代币锁进金库，链 B 铸造等量的包装代币
+ *      销毁-释放: 用户在链 B 上销毁包装代币，链 A 从金库中释放原生代币
+ *      任何时刻都应当满足: A 的金库余额 == B 上包装代币的总量
+ *
+ * 重放保护: 每个事件带有源链内递增的 nonce，目标链记录已处理过的事件哈希，同一个事件只能兑现一次。
+ * 确认数: 只有在其后又有 CONFIRMATIONS 个区块的事件才被接受，降低分叉回滚带来的风险。
+ */
+
+use crate::chain::{hash_bytes, Block, Blockchain, ChainError, Hash};
+use crate::merkle::{MerkleProof, MerkleTree};
+use crate::rlp::{rlp_struct, Encodable};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// 事件所在区块之后至少还要有这么多个区块
+pub const CONFIRMATIONS: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// 代币的原生链: 锁定 / 释放
+    Home,
+    /// 对端链: 铸造 / 销毁包装代币
+    Remote,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub source_chain: u64,
+    pub dest_chain: u64,
+    /// 源链内递增，保证两笔内容相同的转账也有不同的事件哈希
+    pub nonce: u64,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: u64,
+}
+
+rlp_struct!(Event {
+    source_chain,
+    dest_chain,
+    nonce,
+    sender,
+    recipient,
+    amount
+});
+
+impl Event {
+    pub fn hash(&self) -> Hash {
+        hash_bytes(&self.rlp_bytes())
+    }
+}
+
+/// 中继者提交给目标链的消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeMessage {
+    pub block_index: u64,
+    pub event: Event,
+    pub proof: MerkleProof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeError {
+    InsufficientBalance {
+        account: String,
+        balance: u64,
+        amount: u64,
+    },
+    /// 轻客户端拒绝了对方链的区块头
+    InvalidHeader(ChainError),
+    UnknownBlock {
+        index: u64,
+    },
+    NotFinalized {
+        index: u64,
+        confirmations: u64,
+    },
+    WrongChain,
+    InvalidProof,
+    Replay {
+        nonce: u64,
+    },
+    /// 金库余额（Remote 链上是包装代币总量）不足，说明桥的账目已经出错
+    VaultDrained,
+    /// 余额或者跨链总量超出 u64
+    ArithmeticOverflow,
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::InsufficientBalance {
+                account,
+                balance,
+                amount,
+            } => write!(f, "{} has {} but needs {}", account, balance, amount),
+            BridgeError::InvalidHeader(err) => write!(f, "invalid header: {}", err),
+            BridgeError::UnknownBlock { index } => write!(f, "unknown block {}", index),
+            BridgeError::NotFinalized {
+                index,
+                confirmations,
+            } => write!(
+                f,
+                "block {} has {} of {} confirmations",
+                index, confirmations, CONFIRMATIONS
+            ),
+            BridgeError::WrongChain => write!(f, "event is not addressed to this chain"),
+            BridgeError::InvalidProof => write!(f, "merkle proof does not match the header"),
+            BridgeError::Replay { nonce } => write!(f, "event {} was already processed", nonce),
+            BridgeError::VaultDrained => write!(f, "vault balance is insufficient"),
+            BridgeError::ArithmeticOverflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+impl From<ChainError> for BridgeError {
+    fn from(err: ChainError) -> BridgeError {
+        BridgeError::InvalidHeader(err)
+    }
+}
+
+pub struct BridgedChain {
+    pub id: u64,
+    pub role: Role,
+    chain: Blockchain,
+    balances: BTreeMap<String, u64>,
+    /// Home: 锁定在金库中的代币；Remote: 包装代币的总量
+    bridged: u64,
+    /// 尚未打包进区块的事件
+    pending: Vec<Event>,
+    /// 每个区块包含的事件，下标是区块高度，中继者从这里构造证明
+    block_events: Vec<Vec<Event>>,
+    next_nonce: u64,
+    /// 对方链的区块头，累计工作量最大的那条分支
+    light_client: Blockchain,
+    /// 其它竞争中的分支，工作量超过 light_client 时互换
+    // NOTE 没有清理，每个分支都是一份完整的拷贝，真实的轻客户端会丢掉分叉点早已确认的分支
+    forks: Vec<Blockchain>,
+    processed: HashSet<Hash>,
+}
+
+impl BridgedChain {
+    pub fn new(id: u64, role: Role, difficulty: u32) -> BridgedChain {
+        BridgedChain {
+            id,
+            role,
+            chain: Blockchain::new(difficulty),
+            balances: BTreeMap::new(),
+            bridged: 0,
+            pending: Vec::new(),
+            // 创世区块没有事件
+            block_events: vec![Vec::new()],
+            next_nonce: 0,
+            light_client: Blockchain::new(difficulty),
+            forks: Vec::new(),
+            processed: HashSet::new(),
+        }
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        self.chain.blocks()
+    }
+
+    pub fn balance(&self, account: &str) -> u64 {
+        self.balances.get(account).copied().unwrap_or(0)
+    }
+
+    /// Home 链上是金库余额，Remote 链上是包装代币的总发行量
+    pub fn bridged(&self) -> u64 {
+        self.bridged
+    }
+
+    pub fn mint_native(&mut self, account: &str, amount: u64) -> Result<(), BridgeError> {
+        let balance = self.credited(account, amount)?;
+        self.balances.insert(account.to_string(), balance);
+        Ok(())
+    }
+
+    /// 只检查不入账，返回入账后的余额
+    fn credited(&self, account: &str, amount: u64) -> Result<u64, BridgeError> {
+        self.balance(account)
+            .checked_add(amount)
+            .ok_or(BridgeError::ArithmeticOverflow)
+    }
+
+    /// 只检查不扣款，返回扣款后的余额
+    fn check_balance(&self, account: &str, amount: u64) -> Result<u64, BridgeError> {
+        let balance = self.balance(account);
+        balance
+            .checked_sub(amount)
+            .ok_or_else(|| BridgeError::InsufficientBalance {
+                account: account.to_string(),
+                balance,
+                amount,
+            })
+    }
+
+    /// 发起跨链转账: Home 链锁定，Remote 链销毁。返回生成的事件
+    pub fn send(
+        &mut self,
+        dest_chain: u64,
+        sender: &str,
+        recipient: &str,
+        amount: u64,
+    ) -> Result<Event, BridgeError> {
+        let balance = self.check_balance(sender, amount)?;
+        // NOTE Remote 链上用 mint_native 发的代币不是包装代币，销毁它们会让总量不够减
+        let bridged = match self.role {
+            Role::Home => self
+                .bridged
+                .checked_add(amount)
+                .ok_or(BridgeError::ArithmeticOverflow)?,
+            Role::Remote => self
+                .bridged
+                .checked_sub(amount)
+                .ok_or(BridgeError::VaultDrained)?,
+        };
+        // 两项检查都通过之后才修改状态
+        self.balances.insert(sender.to_string(), balance);
+        self.bridged = bridged;
+        let event = Event {
+            source_chain: self.id,
+            dest_chain,
+            nonce: self.next_nonce,
+            sender: sender.to_string(),
+            recipient: recipient.to_string(),
+            amount,
+        };
+        self.next_nonce += 1;
+        self.pending.push(event.clone());
+        Ok(event)
+    }
+
+    /// 把待处理的事件打包成一个新区块，payload 是事件 Merkle 树的根
+    pub fn produce_block(&mut self) -> &Block {
+        let events = std::mem::take(&mut self.pending);
+        let leaves: Vec<Hash> = events.iter().map(Event::hash).collect();
+        let root = MerkleTree::from_leaves(&leaves).root();
+        self.block_events.push(events);
+        // NOTE 时间戳是区块高度，离 u64::MAX 还远，nonce 和时间戳不可能一起用完
+        self.chain
+            .mine_block_at(&root, self.block_events.len() as u64)
+            .expect("block height is far below u64::MAX")
+    }
+
+    /// 为某个区块中的第 i 个事件生成证明
+    pub fn prove(&self, block_index: u64, i: usize) -> Option<BridgeMessage> {
+        let events = self.block_events.get(block_index as usize)?;
+        let leaves: Vec<Hash> = events.iter().map(Event::hash).collect();
+        Some(BridgeMessage {
+            block_index,
+            event: events.get(i)?.clone(),
+            proof: MerkleTree::from_leaves(&leaves).proof(i)?,
+        })
+    }
+
+    /// 轻客户端已同步到的对方链高度
+    pub fn counterparty_height(&self) -> u64 {
+        self.light_client.latest().index
+    }
+
+    /// 同步对方链的区块头，每个都要通过链接和工作量校验。
+    /// 区块头可以接在任何一条已知分支的任意位置上，累计工作量最大的分支成为 light_client
+    pub fn submit_header(&mut self, header: Block) -> Result<(), BridgeError> {
+        // 最常见的情况: 接在当前分支的末尾
+        if header.prev_hash == self.light_client.latest().hash {
+            self.light_client.add_block(header)?;
+            return Ok(());
+        }
+        let has = |chain: &Blockchain, index: u64, hash: &Hash| {
+            chain
+                .blocks()
+                .get(index as usize)
+                .is_some_and(|block| block.hash == *hash)
+        };
+        let mut chains = std::iter::once(&self.light_client).chain(&self.forks);
+        // 重复提交的区块头什么也不做
+        if chains
+            .clone()
+            .any(|chain| has(chain, header.index, &header.hash))
+        {
+            return Ok(());
+        }
+        let Some(parent) = header.index.checked_sub(1) else {
+            return Err(ChainError::InvalidGenesis.into());
+        };
+
+        // 接在某个分支的末尾就延长它，接在某条分支的中间就分叉出一条新的分支
+        let fork = match self
+            .forks
+            .iter()
+            .position(|fork| fork.latest().hash == header.prev_hash)
+        {
+            Some(i) => {
+                self.forks[i].add_block(header)?;
+                i
+            }
+            None => {
+                let mut branch = chains
+                    .find(|chain| has(chain, parent, &header.prev_hash))
+                    .ok_or(ChainError::InvalidPrevHash {
+                        index: header.index,
+                    })?
+                    .clone();
+                branch.truncate(parent);
+                branch.add_block(header)?;
+                self.forks.push(branch);
+                self.forks.len() - 1
+            }
+        };
+
+        let previous = self.light_client.clone();
+        if self.light_client.resolve_fork(self.forks[fork].blocks())? {
+            self.forks[fork] = previous;
+        }
+        Ok(())
+    }
+
+    /// 兑现对方链上的事件
+    pub fn receive(&mut self, msg: &BridgeMessage) -> Result<(), BridgeError> {
+        let event = &msg.event;
+        if event.dest_chain != self.id || event.source_chain == self.id {
+            return Err(BridgeError::WrongChain);
+        }
+
+        let header = self
+            .light_client
+            .blocks()
+            .get(msg.block_index as usize)
+            .ok_or(BridgeError::UnknownBlock {
+                index: msg.block_index,
+            })?;
+        let confirmations = self.counterparty_height() - msg.block_index;
+        if confirmations < CONFIRMATIONS {
+            return Err(BridgeError::NotFinalized {
+                index: msg.block_index,
+                confirmations,
+            });
+        }
+
+        let leaf = event.hash();
+        let root: Hash = header
+            .payload
+            .as_slice()
+            .try_into()
+            .map_err(|_| BridgeError::InvalidProof)?;
+        if !msg.proof.verify(&root, &leaf) {
+            return Err(BridgeError::InvalidProof);
+        }
+        // NOTE 先检查再记录，检查失败的消息以后还可以重新提交
+        if self.processed.contains(&leaf) {
+            return Err(BridgeError::Replay { nonce: event.nonce });
+        }
+
+        let bridged = match self.role {
+            Role::Home => self
+                .bridged
+                .checked_sub(event.amount)
+                .ok_or(BridgeError::VaultDrained)?,
+            Role::Remote => self
+                .bridged
+                .checked_add(event.amount)
+                .ok_or(BridgeError::ArithmeticOverflow)?,
+        };
+        let balance = self.credited(&event.recipient, event.amount)?;
+        // 和 send 一样，全部检查通过之后才修改状态
+        self.bridged = bridged;
+        self.balances.insert(event.recipient.clone(), balance);
+        self.processed.insert(leaf);
+        Ok(())
+    }
+}
+
+/// 中继者: 把 from 链的新区块头同步给 to 链，然后提交所有已经足够确认的事件。
+/// 返回每条消息的处理结果（重复提交会得到 Replay 错误，不影响其他消息）
+pub fn relay(from: &BridgedChain, to: &mut BridgedChain) -> Vec<Result<Event, BridgeError>> {
+    let mut results = Vec::new();
+    // 从两边第一个不同的区块开始同步: to 的轻客户端可能选中了一条伪造的分支，
+    // 也可能已经比 from 更高（例如 from 是一个落后的节点），这时没有区块头要同步
+    let synced = from
+        .blocks()
+        .iter()
+        .zip(to.light_client.blocks())
+        .take_while(|(a, b)| a.hash == b.hash)
+        .count();
+    for header in &from.blocks()[synced..] {
+        if let Err(err) = to.submit_header(header.clone()) {
+            results.push(Err(err));
+            return results;
+        }
+    }
+    let height = to.counterparty_height();
+    for index in 1..=height.saturating_sub(CONFIRMATIONS) {
+        let Some(events) = from.block_events.get(index as usize) else {
+            break;
+        };
+        for (i, event) in events.iter().enumerate() {
+            if event.dest_chain != to.id || to.processed.contains(&event.hash()) {
+                continue;
+            }
+            let msg = from.prove(index, i).unwrap();
+            results.push(to.receive(&msg).map(|_| msg.event));
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+fn test_chains() -> (BridgedChain, BridgedChain) {
+    let mut home = BridgedChain::new(1, Role::Home, 4);
+    home.mint_native("alice", 1000).unwrap();
+    (home, BridgedChain::new(2, Role::Remote, 4))
+}
+
+#[cfg(test)]
+fn advance(chain: &mut BridgedChain, blocks: u64) {
+    for _ in 0..blocks {
+        chain.produce_block();
+    }
+}
+
+#[test]
+fn test_lock_mint_burn_release() {
+    let (mut home, mut remote) = test_chains();
+
+    home.send(2, "alice", "bob", 300).unwrap();
+    home.send(2, "alice", "carol", 200).unwrap();
+    advance(&mut home, 1);
+
+    // 确认数不够，中继者只同步区块头
+    assert!(relay(&home, &mut remote).is_empty());
+    assert_eq!(remote.balance("bob"), 0);
+
+    advance(&mut home, CONFIRMATIONS);
+    let done = relay(&home, &mut remote);
+    assert_eq!(done.len(), 2);
+    assert_eq!(remote.balance("bob"), 300);
+    assert_eq!(remote.balance("carol"), 200);
+    assert_eq!(home.balance("alice"), 500);
+    assert_eq!(home.bridged(), remote.bridged());
+
+    // 再次中继不会重复铸造
+    assert!(relay(&home, &mut remote).is_empty());
+
+    // bob 在链 B 上销毁 100，回到链 A 上的 dave
+    remote.send(1, "bob", "dave", 100).unwrap();
+    advance(&mut remote, 1 + CONFIRMATIONS);
+    assert_eq!(relay(&remote, &mut home).len(), 1);
+    assert_eq!(home.balance("dave"), 100);
+    assert_eq!(remote.balance("bob"), 200);
+    assert_eq!(home.bridged(), 400);
+    assert_eq!(home.bridged(), remote.bridged());
+}
+
+#[test]
+fn test_double_spend_and_replay() {
+    let (mut home, mut remote) = test_chains();
+    home.send(2, "alice", "bob", 100).unwrap();
+    advance(&mut home, 1 + CONFIRMATIONS);
+    relay(&home, &mut remote);
+
+    // 同一条消息提交两次
+    let msg = home.prove(1, 0).unwrap();
+    assert_eq!(remote.receive(&msg), Err(BridgeError::Replay { nonce: 0 }));
+    assert_eq!(remote.balance("bob"), 100);
+
+    // 销毁超过余额的包装代币
+    assert_eq!(
+        remote.send(1, "bob", "bob", 101),
+        Err(BridgeError::InsufficientBalance {
+            account: String::from("bob"),
+            balance: 100,
+            amount: 101
+        })
+    );
+    // 把发往 B 的事件拿到 A 上兑现
+    assert_eq!(home.receive(&msg), Err(BridgeError::WrongChain));
+    assert_eq!(home.bridged(), remote.bridged());
+
+    // B 上直接发的代币不是包装代币，不能拿去销毁
+    remote.mint_native("eve", 500).unwrap();
+    assert_eq!(
+        remote.send(1, "eve", "eve", 500),
+        Err(BridgeError::VaultDrained)
+    );
+    assert_eq!(remote.balance("eve"), 500);
+    assert_eq!(home.bridged(), remote.bridged());
+
+    // 从一个落后的 A 链副本中继，B 的轻客户端已经比它高
+    let (stale, _) = test_chains();
+    assert!(relay(&stale, &mut remote).is_empty());
+    assert_eq!(remote.balance("bob"), 100);
+}
+
+#[test]
+fn test_forged_proofs_and_headers() {
+    let (mut home, mut remote) = test_chains();
+    home.send(2, "alice", "mallory", 10).unwrap();
+    home.send(2, "alice", "bob", 20).unwrap();
+    advance(&mut home, 1);
+
+    // 还没有同步区块头
+    let msg = home.prove(1, 0).unwrap();
+    assert_eq!(
+        remote.receive(&msg),
+        Err(BridgeError::UnknownBlock { index: 1 })
+    );
+    relay(&home, &mut remote);
+    assert_eq!(
+        remote.receive(&msg),
+        Err(BridgeError::NotFinalized {
+            index: 1,
+            confirmations: 0
+        })
+    );
+    advance(&mut home, CONFIRMATIONS);
+    relay(&home, &mut remote);
+
+    // 篡改金额后重新提交一个新 nonce 的事件，证明对不上
+    let mut forged = home.prove(1, 0).unwrap();
+    forged.event.amount = 1_000_000;
+    forged.event.nonce = 99;
+    assert_eq!(remote.receive(&forged), Err(BridgeError::InvalidProof));
+    // 拿别的事件的证明来配
+    let mut forged = home.prove(1, 0).unwrap();
+    forged.proof = home.prove(1, 1).unwrap().proof;
+    forged.event.nonce = 99;
+    assert_eq!(remote.receive(&forged), Err(BridgeError::InvalidProof));
+
+    // 恶意中继者伪造一个区块头，声称其中有一笔给 mallory 的巨额转账
+    let fake = Event {
+        source_chain: 1,
+        dest_chain: 2,
+        nonce: 100,
+        sender: String::from("alice"),
+        recipient: String::from("mallory"),
+        amount: 1_000_000,
+    };
+    let mut header = home.blocks().last().unwrap().clone();
+    header.index += 1;
+    header.prev_hash = header.hash;
+    header.payload = MerkleTree::from_leaves(&[fake.hash()]).root().to_vec();
+    header.hash = header.compute_hash();
+    // 没有重新挖矿，工作量不够（难度 4 时偶然满足的概率是 1/16，这里换 nonce 直到不满足）
+    while header.check_work().is_ok() {
+        header.nonce += 1;
+        header.hash = header.compute_hash();
+    }
+    assert!(matches!(
+        remote.submit_header(header),
+        Err(BridgeError::InvalidHeader(
+            ChainError::InsufficientWork { .. }
+        ))
+    ));
+    assert_eq!(remote.balance("mallory"), 10);
+    assert_eq!(home.bridged(), remote.bridged());
+}
+
+#[test]
+fn test_mined_fake_header_loses_to_honest_chain() {
+    use crate::chain::ZERO_HASH;
+
+    let (mut home, mut remote) = test_chains();
+    home.send(2, "alice", "bob", 10).unwrap();
+    advance(&mut home, 1);
+    relay(&home, &mut remote);
+
+    // 恶意中继者抢在诚实的区块之前，挖出一个满足难度的假区块头
+    let fake = Event {
+        source_chain: 1,
+        dest_chain: 2,
+        nonce: 100,
+        sender: String::from("alice"),
+        recipient: String::from("mallory"),
+        amount: 1_000_000,
+    };
+    let tip = home.blocks().last().unwrap();
+    let mut header = Block {
+        index: tip.index + 1,
+        timestamp: 0,
+        prev_hash: tip.hash,
+        payload: MerkleTree::from_leaves(&[fake.hash()]).root().to_vec(),
+        nonce: 0,
+        difficulty: 4,
+        hash: ZERO_HASH,
+    };
+    header.mine().unwrap();
+    remote.submit_header(header.clone()).unwrap();
+    assert_eq!(remote.counterparty_height(), 2);
+    let msg = BridgeMessage {
+        block_index: 2,
+        event: fake.clone(),
+        proof: MerkleTree::from_leaves(&[fake.hash()]).proof(0).unwrap(),
+    };
+    assert_eq!(
+        remote.receive(&msg),
+        Err(BridgeError::NotFinalized {
+            index: 2,
+            confirmations: 0
+        })
+    );
+
+    // 诚实的链继续出块，累计工作量超过假分支后，轻客户端切换回诚实的链
+    home.send(2, "alice", "bob", 20).unwrap();
+    advance(&mut home, 1 + CONFIRMATIONS);
+    let done = relay(&home, &mut remote);
+    assert_eq!(done.len(), 2);
+    assert!(done.iter().all(Result::is_ok));
+    assert_eq!(remote.light_client.blocks(), home.blocks());
+    assert_eq!(remote.receive(&msg), Err(BridgeError::InvalidProof));
+
+    // 再提交一次假区块头，它只是一条工作量更少的分支
+    remote.submit_header(header).unwrap();
+    assert_eq!(remote.light_client.blocks(), home.blocks());
+    assert_eq!(remote.balance("mallory"), 0);
+    assert_eq!(remote.balance("bob"), 30);
+    assert_eq!(home.bridged(), remote.bridged());
+}
+
+#[test]
+fn test_bridge_overflow() {
+    let (mut home, mut remote) = test_chains();
+    assert_eq!(
+        home.mint_native("alice", u64::MAX),
+        Err(BridgeError::ArithmeticOverflow)
+    );
+    assert_eq!(home.balance("alice"), 1000);
+
+    // 金库的总量溢出
+    home.mint_native("whale", u64::MAX).unwrap();
+    home.send(2, "whale", "whale", u64::MAX).unwrap();
+    assert_eq!(
+        home.send(2, "alice", "bob", 1),
+        Err(BridgeError::ArithmeticOverflow)
+    );
+    assert_eq!(home.balance("alice"), 1000);
+    assert_eq!(home.bridged(), u64::MAX);
+
+    // 收款人在 B 上的余额溢出: 事件不算处理过，余额和总量都不变
+    remote.mint_native("whale", 1).unwrap();
+    advance(&mut home, 1 + CONFIRMATIONS);
+    let done = relay(&home, &mut remote);
+    assert_eq!(done, vec![Err(BridgeError::ArithmeticOverflow)]);
+    assert_eq!(remote.balance("whale"), 1);
+    assert_eq!(remote.bridged(), 0);
+    assert_eq!(
+        remote.receive(&home.prove(1, 0).unwrap()),
+        Err(BridgeError::ArithmeticOverflow)
+    );
+}
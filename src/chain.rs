@@ -0,0 +1,457 @@
+#![allow(dead_code)]
+
+/*
+ * 区块链（Blockchain）
+ *      区块链本质上是一个只能追加的链表：每个区块都记录了上一个区块的哈希值，
+ *      只要修改了历史上任意一个区块的内容，它的哈希就会改变，后面所有区块的 prev_hash 都会对不上。
+ *
+ * 工作量证明（Proof of Work）
+ *      矿工不断修改区块中的 nonce，直到区块哈希满足难度要求（这里用 "前导零比特的个数" 表示难度）。
+ *      难度每增加 1，平均需要尝试的次数就翻一倍。
+ *
+ * 分叉（Fork）
+ *      两个矿工可能同时挖出高度相同的区块，网络中就出现了分叉。
+ *      最简单的解决办法是 "最长链原则"：节点只接受比自己更长的合法链。
+ *      NOTE 但 "长" 不能按区块个数算: 难度为 0 的区块不需要任何计算，随手就能造出一条更长的链。
+ *      所以比较的是累计工作量（每个区块约 2^difficulty 次哈希），并且拒绝难度低于本地要求的区块。
+ */
+
+use crate::crypto::hash::{hex, sha256};
+use crate::error::DomainError;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 区块哈希，固定 32 字节
+pub type Hash = [u8; 32];
+
+/// 创世区块的 prev_hash 全为 0
+pub const ZERO_HASH: Hash = [0; 32];
+
+/// 对任意字节计算 32 字节的摘要，和比特币一样使用 SHA-256
+pub fn hash_bytes(data: &[u8]) -> Hash {
+    sha256(data)
+}
+
+/// 哈希的十六进制表示，直接沿用 crypto 模块里的实现
+pub fn to_hex(bytes: &[u8]) -> String {
+    hex(bytes)
+}
+
+/// 哈希前导零比特的个数，用来衡量工作量
+pub fn leading_zero_bits(hash: &Hash) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub index: u64,
+    pub timestamp: u64,
+    pub prev_hash: Hash,
+    pub payload: Vec<u8>,
+    pub nonce: u64,
+    pub difficulty: u32,
+    pub hash: Hash,
+}
+
+impl Block {
+    /// 创世区块的内容是固定的，这样不同节点各自创建的链才能拥有相同的起点
+    pub fn genesis() -> Block {
+        let mut block = Block {
+            index: 0,
+            timestamp: 0,
+            prev_hash: ZERO_HASH,
+            payload: b"genesis".to_vec(),
+            nonce: 0,
+            difficulty: 0,
+            hash: ZERO_HASH,
+        };
+        block.hash = block.compute_hash();
+        block
+    }
+
+    /// 参与哈希计算的区块头字节，hash 字段本身不参与
+    pub fn header_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 * 3 + 4 + 32 + self.payload.len());
+        bytes.extend_from_slice(&self.index.to_be_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.prev_hash);
+        bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes.extend_from_slice(&self.difficulty.to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    pub fn compute_hash(&self) -> Hash {
+        hash_bytes(&self.header_bytes())
+    }
+
+    /// 不断递增 nonce，直到哈希满足难度要求。nonce 和时间戳都用完了还没找到时返回错误
+    pub fn mine(&mut self) -> Result<(), ChainError> {
+        loop {
+            let hash = self.compute_hash();
+            if leading_zero_bits(&hash) >= self.difficulty {
+                self.hash = hash;
+                return Ok(());
+            }
+            // NOTE nonce 用完了就换个时间戳继续，真实的矿工还会修改 coinbase 交易中的 extra nonce
+            self.nonce = match self.nonce.checked_add(1) {
+                Some(n) => n,
+                None => {
+                    self.timestamp = self
+                        .timestamp
+                        .checked_add(1)
+                        .ok_or(ChainError::NonceExhausted { index: self.index })?;
+                    0
+                }
+            };
+        }
+    }
+
+    /// 挖出这个区块平均需要的哈希次数 2^difficulty，难度过大时饱和
+    pub fn work(&self) -> u128 {
+        1u128.checked_shl(self.difficulty).unwrap_or(u128::MAX)
+    }
+
+    /// 区块自身是否合法: 哈希正确且满足自己声明的难度
+    pub fn check_work(&self) -> Result<(), ChainError> {
+        if self.compute_hash() != self.hash {
+            return Err(ChainError::InvalidHash { index: self.index });
+        }
+        if leading_zero_bits(&self.hash) < self.difficulty {
+            return Err(ChainError::InsufficientWork {
+                index: self.index,
+                difficulty: self.difficulty,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    InvalidGenesis,
+    InvalidIndex { expected: u64, found: u64 },
+    InvalidPrevHash { index: u64 },
+    InvalidHash { index: u64 },
+    InsufficientWork { index: u64, difficulty: u32 },
+    DifficultyMismatch { index: u64, expected: u32, found: u32 },
+    /// 挖矿时 nonce 和时间戳都已经用完
+    NonceExhausted { index: u64 },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::InvalidGenesis => write!(f, "genesis block does not match"),
+            ChainError::InvalidIndex { expected, found } => {
+                write!(f, "expected block #{}, found #{}", expected, found)
+            }
+            ChainError::InvalidPrevHash { index } => {
+                write!(f, "block #{} does not link to its parent", index)
+            }
+            ChainError::InvalidHash { index } => write!(f, "block #{} has a wrong hash", index),
+            ChainError::InsufficientWork { index, difficulty } => write!(
+                f,
+                "block #{} does not reach difficulty {}",
+                index, difficulty
+            ),
+            ChainError::DifficultyMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "block #{} claims difficulty {}, chain requires {}",
+                index, found, expected
+            ),
+            ChainError::NonceExhausted { index } => {
+                write!(f, "block #{} ran out of nonces and timestamps", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+impl DomainError for ChainError {
+    const CODE: u16 = 3001;
+}
+
+#[derive(Debug, Clone)]
+pub struct Blockchain {
+    blocks: Vec<Block>,
+    difficulty: u32,
+}
+
+impl Blockchain {
+    pub fn new(difficulty: u32) -> Blockchain {
+        Blockchain {
+            blocks: vec![Block::genesis()],
+            difficulty,
+        }
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn latest(&self) -> &Block {
+        // NOTE new 时已经放入了创世区块，这里的 unwrap 不会失败
+        self.blocks.last().unwrap()
+    }
+
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// 只保留高度不超过 height 的区块，创世区块总是保留
+    pub fn truncate(&mut self, height: u64) {
+        self.blocks.truncate((height as usize).saturating_add(1));
+    }
+
+    /// 调整之后挖出的区块的难度，已经在链上的区块不受影响
+    pub fn set_difficulty(&mut self, difficulty: u32) {
+        self.difficulty = difficulty;
+    }
+
+    /// 以当前时间挖出一个新区块并追加到链尾
+    pub fn mine_block(&mut self, payload: &[u8]) -> Result<&Block, ChainError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.mine_block_at(payload, timestamp)
+    }
+
+    /// 指定时间戳挖矿，测试中用它得到可复现的区块
+    pub fn mine_block_at(&mut self, payload: &[u8], timestamp: u64) -> Result<&Block, ChainError> {
+        let block = self.next_block(payload, timestamp)?;
+        self.blocks.push(block);
+        Ok(self.latest())
+    }
+
+    /// 构造并挖出下一个区块，但不追加到链上
+    pub fn next_block(&self, payload: &[u8], timestamp: u64) -> Result<Block, ChainError> {
+        let prev = self.latest();
+        let mut block = Block {
+            index: prev.index + 1,
+            timestamp,
+            prev_hash: prev.hash,
+            payload: payload.to_vec(),
+            nonce: 0,
+            difficulty: self.difficulty,
+            hash: ZERO_HASH,
+        };
+        block.mine()?;
+        Ok(block)
+    }
+
+    /// 追加一个其他节点挖出的区块，必须正好接在链尾
+    pub fn add_block(&mut self, block: Block) -> Result<(), ChainError> {
+        Self::check_link(self.latest(), &block)?;
+        if block.difficulty < self.difficulty {
+            return Err(ChainError::DifficultyMismatch {
+                index: block.index,
+                expected: self.difficulty,
+                found: block.difficulty,
+            });
+        }
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    fn check_link(prev: &Block, block: &Block) -> Result<(), ChainError> {
+        if block.index != prev.index + 1 {
+            return Err(ChainError::InvalidIndex {
+                expected: prev.index + 1,
+                found: block.index,
+            });
+        }
+        if block.prev_hash != prev.hash {
+            return Err(ChainError::InvalidPrevHash { index: block.index });
+        }
+        block.check_work()
+    }
+
+    /// 校验一段从创世区块开始的区块序列
+    pub fn validate_blocks(blocks: &[Block]) -> Result<(), ChainError> {
+        match blocks.first() {
+            Some(first) if *first == Block::genesis() => {}
+            _ => return Err(ChainError::InvalidGenesis),
+        }
+        for pair in blocks.windows(2) {
+            Self::check_link(&pair[0], &pair[1])?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ChainError> {
+        Self::validate_blocks(&self.blocks)
+    }
+
+    /// 一段区块的累计工作量
+    pub fn total_work(blocks: &[Block]) -> u128 {
+        blocks
+            .iter()
+            .fold(0u128, |sum, block| sum.saturating_add(block.work()))
+    }
+
+    /// 最长链原则: 候选链合法且累计工作量更大时替换本地链，返回是否发生了替换
+    pub fn resolve_fork(&mut self, candidate: &[Block]) -> Result<bool, ChainError> {
+        if Self::total_work(candidate) <= Self::total_work(&self.blocks) {
+            return Ok(false);
+        }
+        Self::validate_blocks(candidate)?;
+        // NOTE 和 add_block 一样，除创世区块外每个区块都必须达到本地要求的难度
+        if let Some(block) = candidate[1..]
+            .iter()
+            .find(|block| block.difficulty < self.difficulty)
+        {
+            return Err(ChainError::DifficultyMismatch {
+                index: block.index,
+                expected: self.difficulty,
+                found: block.difficulty,
+            });
+        }
+        self.blocks = candidate.to_vec();
+        Ok(true)
+    }
+}
+
+#[test]
+fn test_mine_and_validate() {
+    let mut chain = Blockchain::new(8);
+    chain.mine_block_at(b"alice -> bob: 10", 1).unwrap();
+    chain.mine_block_at(b"bob -> carol: 3", 2).unwrap();
+
+    assert_eq!(chain.blocks().len(), 3);
+    assert_eq!(chain.validate(), Ok(()));
+    for block in chain.blocks().iter().skip(1) {
+        assert!(leading_zero_bits(&block.hash) >= 8);
+        println!("#{} nonce={} hash={}", block.index, block.nonce, to_hex(&block.hash));
+    }
+}
+
+#[test]
+fn test_tamper_detected() {
+    let mut chain = Blockchain::new(4);
+    chain.mine_block_at(b"a", 1).unwrap();
+    chain.mine_block_at(b"b", 2).unwrap();
+
+    // NOTE 篡改历史区块的内容，哈希随之失效
+    let mut blocks = chain.blocks().to_vec();
+    blocks[1].payload = b"evil".to_vec();
+    assert_eq!(
+        Blockchain::validate_blocks(&blocks),
+        Err(ChainError::InvalidHash { index: 1 })
+    );
+
+    // NOTE 即使重新挖出了被篡改的区块，下一个区块的 prev_hash 也对不上了
+    blocks[1].mine().unwrap();
+    assert_eq!(
+        Blockchain::validate_blocks(&blocks),
+        Err(ChainError::InvalidPrevHash { index: 2 })
+    );
+
+    // nonce 和时间戳都到了上限，不能再换: 返回错误而不是溢出
+    let mut block = blocks[1].clone();
+    block.difficulty = 64;
+    block.nonce = u64::MAX;
+    block.timestamp = u64::MAX;
+    assert_eq!(block.mine(), Err(ChainError::NonceExhausted { index: 1 }));
+}
+
+#[test]
+fn test_add_block_and_difficulty() {
+    let mut miner = Blockchain::new(4);
+    let mut node = miner.clone();
+
+    let block = miner.mine_block_at(b"tx", 1).unwrap().clone();
+    assert_eq!(node.add_block(block.clone()), Ok(()));
+    assert_eq!(
+        node.add_block(block),
+        Err(ChainError::InvalidIndex {
+            expected: 2,
+            found: 1
+        })
+    );
+
+    // NOTE 节点提高难度后，拒绝难度不够的区块
+    node.set_difficulty(12);
+    let easy = miner.mine_block_at(b"easy", 2).unwrap().clone();
+    assert!(matches!(
+        node.add_block(easy),
+        Err(ChainError::DifficultyMismatch { .. })
+    ));
+
+    miner.set_difficulty(12);
+    let mut hard = node.next_block(b"hard", 3).unwrap();
+    assert!(leading_zero_bits(&hard.hash) >= 12);
+    assert_eq!(node.add_block(hard.clone()), Ok(()));
+
+    hard.nonce += 1;
+    assert!(hard.check_work().is_err());
+}
+
+#[test]
+fn test_resolve_fork() {
+    let mut a = Blockchain::new(4);
+    a.mine_block_at(b"common", 1).unwrap();
+    let mut b = a.clone();
+
+    a.mine_block_at(b"a-1", 2).unwrap();
+    b.mine_block_at(b"b-1", 2).unwrap();
+    b.mine_block_at(b"b-2", 3).unwrap();
+
+    // NOTE 较短的链不会替换本地链
+    assert_eq!(b.resolve_fork(a.blocks()), Ok(false));
+    assert_eq!(a.resolve_fork(b.blocks()), Ok(true));
+    assert_eq!(a.latest(), b.latest());
+
+    // NOTE 更长但不合法的链同样被拒绝
+    let mut forged = b.blocks().to_vec();
+    forged.push(forged[1].clone());
+    let before = b.latest().clone();
+    assert!(b.resolve_fork(&forged).is_err());
+    assert_eq!(*b.latest(), before);
+}
+
+#[test]
+fn test_resolve_fork_requires_work() {
+    let mut honest = Blockchain::new(8);
+    honest.mine_block_at(b"a", 1).unwrap();
+    honest.mine_block_at(b"b", 2).unwrap();
+    let before = honest.latest().clone();
+
+    // NOTE 难度为 0 的区块不需要挖矿，区块个数更多，但累计工作量不够
+    let mut cheap = Blockchain::new(0);
+    for i in 0..100 {
+        cheap.mine_block_at(b"free", i).unwrap();
+    }
+    assert!(cheap.blocks().len() > honest.blocks().len());
+    assert_eq!(honest.resolve_fork(cheap.blocks()), Ok(false));
+
+    // NOTE 即使多到累计工作量超过本地链，难度低于本地要求的区块也会被拒绝
+    for i in 100..1000 {
+        cheap.mine_block_at(b"free", i).unwrap();
+    }
+    assert!(Blockchain::total_work(cheap.blocks()) > Blockchain::total_work(honest.blocks()));
+    assert_eq!(
+        honest.resolve_fork(cheap.blocks()),
+        Err(ChainError::DifficultyMismatch {
+            index: 1,
+            expected: 8,
+            found: 0
+        })
+    );
+    assert_eq!(*honest.latest(), before);
+}
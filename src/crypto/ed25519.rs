@@ -0,0 +1,663 @@
+#![allow(dead_code)]
+
+/*
+ * Ed25519 数字签名（RFC 8032）
+ *
+ * 整个算法由三层组成，从下往上依次是:
+ *      1. 有限域 GF(p)，p = 2^255 - 19: 曲线上点的坐标都是这个域中的元素
+ *      2. 扭曲爱德华曲线 -x^2 + y^2 = 1 + d*x^2*y^2: 点加法、标量乘法
+ *      3. 签名方案: 私钥是 32 字节的种子，用 SHA-512 派生出标量 a，公钥 A = a*B
+ *
+ * 签名 (R, S):
+ *      r = H(prefix || M) mod L,  R = r*B
+ *      k = H(R || A || M) mod L,  S = (r + k*a) mod L
+ * 验证:
+ *      S*B == R + k*A
+ *
+ * NOTE 这份实现追求可读性，没有做常数时间处理，存在侧信道风险，只能用于学习
+ */
+
+use super::hash::Sha512;
+use std::fmt;
+use std::sync::OnceLock;
+
+// ---------------------------------------------------------------------------------------------
+// 256 位无符号整数的辅助函数，4 个 u64 小端排列
+
+type Limbs = [u64; 4];
+
+/// p = 2^255 - 19
+const P: Limbs = [
+    0xffff_ffff_ffff_ffed,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+];
+
+/// 基点的阶 L = 2^252 + 27742317777372353535851937790883648493
+const L: Limbs = [
+    0x5812_631a_5cf5_d3ed,
+    0x14de_f9de_a2f7_9cd6,
+    0x0000_0000_0000_0000,
+    0x1000_0000_0000_0000,
+];
+
+fn limbs_from_le(bytes: &[u8]) -> Limbs {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+        let mut buf = [0u8; 8];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(buf);
+    }
+    limbs
+}
+
+fn limbs_to_le(limbs: &Limbs) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (chunk, limb) in out.chunks_mut(8).zip(limbs.iter()) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    out
+}
+
+fn geq(a: &Limbs, b: &Limbs) -> bool {
+    for i in (0..4).rev() {
+        if a[i] != b[i] {
+            return a[i] > b[i];
+        }
+    }
+    true
+}
+
+/// a + b，返回结果和最高位的进位
+fn add_limbs(a: &Limbs, b: &Limbs) -> (Limbs, bool) {
+    let mut out = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (s1, c1) = a[i].overflowing_add(b[i]);
+        let (s2, c2) = s1.overflowing_add(carry as u64);
+        out[i] = s2;
+        carry = c1 || c2;
+    }
+    (out, carry)
+}
+
+/// a - b，返回结果和是否发生了借位
+fn sub_limbs(a: &Limbs, b: &Limbs) -> (Limbs, bool) {
+    let mut out = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (d1, b1) = a[i].overflowing_sub(b[i]);
+        let (d2, b2) = d1.overflowing_sub(borrow as u64);
+        out[i] = d2;
+        borrow = b1 || b2;
+    }
+    (out, borrow)
+}
+
+/// 256 x 256 -> 512 位的教科书乘法
+fn mul_wide(a: &Limbs, b: &Limbs) -> [u64; 8] {
+    let mut out = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = a[i] as u128 * b[j] as u128 + out[i + j] as u128 + carry;
+            out[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        out[i + 4] = carry as u64;
+    }
+    out
+}
+
+/// 512 位整数对 L 取模: 逐位移入的二进制长除法，慢但一眼就能看懂
+fn reduce_mod_l(wide: &[u64; 8]) -> Limbs {
+    let mut r = [0u64; 4];
+    for bit in (0..512).rev() {
+        // r = r * 2 + bit，r < L < 2^253，左移一位也不会溢出
+        for i in (1..4).rev() {
+            r[i] = (r[i] << 1) | (r[i - 1] >> 63);
+        }
+        r[0] = (r[0] << 1) | ((wide[bit / 64] >> (bit % 64)) & 1);
+        if geq(&r, &L) {
+            r = sub_limbs(&r, &L).0;
+        }
+    }
+    r
+}
+
+// ---------------------------------------------------------------------------------------------
+// 有限域 GF(2^255 - 19)
+
+/// 域元素，内部始终保持 0 <= value < p
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Fe(Limbs);
+
+impl Fe {
+    const ZERO: Fe = Fe([0, 0, 0, 0]);
+    const ONE: Fe = Fe([1, 0, 0, 0]);
+
+    fn from_u64(v: u64) -> Fe {
+        Fe([v, 0, 0, 0])
+    }
+
+    /// 按 RFC 8032 的约定忽略最高位，非规范编码（>= p）在这里被约化
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let mut limbs = limbs_from_le(bytes);
+        limbs[3] &= 0x7fff_ffff_ffff_ffff;
+        Fe::reduce_once(limbs)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        limbs_to_le(&self.0)
+    }
+
+    fn reduce_once(limbs: Limbs) -> Fe {
+        if geq(&limbs, &P) {
+            Fe(sub_limbs(&limbs, &P).0)
+        } else {
+            Fe(limbs)
+        }
+    }
+
+    fn add(self, rhs: Fe) -> Fe {
+        // NOTE 两个数都小于 p < 2^255，和不会超过 2^256
+        let (sum, _) = add_limbs(&self.0, &rhs.0);
+        Fe::reduce_once(sum)
+    }
+
+    fn sub(self, rhs: Fe) -> Fe {
+        let (diff, borrow) = sub_limbs(&self.0, &rhs.0);
+        if borrow {
+            Fe(add_limbs(&diff, &P).0)
+        } else {
+            Fe(diff)
+        }
+    }
+
+    fn neg(self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    fn mul(self, rhs: Fe) -> Fe {
+        let wide = mul_wide(&self.0, &rhs.0);
+
+        // NOTE 2^256 = 2 * (2^255 - 19) + 38 ≡ 38 (mod p)，所以高 256 位乘 38 加回低 256 位即可
+        let mut r = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let t = wide[i] as u128 + wide[i + 4] as u128 * 38 + carry;
+            r[i] = t as u64;
+            carry = t >> 64;
+        }
+        // 剩下的进位很小，再折叠一次；极少数情况下还会再溢出一次
+        while carry > 0 {
+            let mut t = carry * 38;
+            for limb in r.iter_mut() {
+                t += *limb as u128;
+                *limb = t as u64;
+                t >>= 64;
+                if t == 0 {
+                    break;
+                }
+            }
+            carry = t;
+        }
+        // 此时 r < 2^256 = 2p + 38，最多减两次 p
+        let r = Fe::reduce_once(r);
+        Fe::reduce_once(r.0)
+    }
+
+    fn square(self) -> Fe {
+        self.mul(self)
+    }
+
+    /// 平方-乘算法求幂，指数按小端 limbs 给出
+    fn pow(self, exp: &Limbs) -> Fe {
+        let mut result = Fe::ONE;
+        for i in (0..256).rev() {
+            result = result.square();
+            if (exp[i / 64] >> (i % 64)) & 1 == 1 {
+                result = result.mul(self);
+            }
+        }
+        result
+    }
+
+    /// 费马小定理: a^(p-2) = a^-1
+    fn invert(self) -> Fe {
+        let (exp, _) = sub_limbs(&P, &[2, 0, 0, 0]);
+        self.pow(&exp)
+    }
+
+    fn is_negative(self) -> bool {
+        self.0[0] & 1 == 1
+    }
+}
+
+/// 运行时计算的曲线常量，避免手抄一长串十六进制出错
+struct Constants {
+    d: Fe,
+    d2: Fe,
+    sqrt_m1: Fe,
+    base: Point,
+}
+
+fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        // d = -121665 / 121666
+        let d = Fe::from_u64(121665)
+            .neg()
+            .mul(Fe::from_u64(121666).invert());
+        // sqrt(-1) = 2^((p-1)/4)
+        let mut exp = sub_limbs(&P, &[1, 0, 0, 0]).0;
+        exp = shr_limbs(&exp, 2);
+        let sqrt_m1 = Fe::from_u64(2).pow(&exp);
+
+        // 基点 B: y = 4/5，x 取偶数的那个根
+        let y = Fe::from_u64(4).mul(Fe::from_u64(5).invert());
+        let x = recover_x(y, false, d, sqrt_m1).expect("base point must exist");
+        Constants {
+            d,
+            d2: d.add(d),
+            sqrt_m1,
+            base: Point::from_affine(x, y),
+        }
+    })
+}
+
+fn shr_limbs(a: &Limbs, n: u32) -> Limbs {
+    let mut out = [0u64; 4];
+    for i in 0..4 {
+        out[i] = a[i] >> n;
+        if i < 3 {
+            out[i] |= a[i + 1] << (64 - n);
+        }
+    }
+    out
+}
+
+/// 由 y 坐标和 x 的符号位解出 x: x^2 = (y^2 - 1) / (d*y^2 + 1)
+fn recover_x(y: Fe, negative: bool, d: Fe, sqrt_m1: Fe) -> Option<Fe> {
+    let y2 = y.square();
+    let x2 = y2.sub(Fe::ONE).mul(d.mul(y2).add(Fe::ONE).invert());
+    if x2 == Fe::ZERO {
+        return if negative { None } else { Some(Fe::ZERO) };
+    }
+
+    // p ≡ 5 (mod 8)，候选平方根为 x2^((p+3)/8)，不对的话再乘上 sqrt(-1)
+    let exp = shr_limbs(&add_limbs(&P, &[3, 0, 0, 0]).0, 3);
+    let mut x = x2.pow(&exp);
+    if x.square() != x2 {
+        x = x.mul(sqrt_m1);
+    }
+    if x.square() != x2 {
+        return None;
+    }
+    if x.is_negative() != negative {
+        x = x.neg();
+    }
+    Some(x)
+}
+
+// ---------------------------------------------------------------------------------------------
+// 曲线上的点，使用扩展坐标 (X:Y:Z:T)，x = X/Z, y = Y/Z, x*y = T/Z，可以避免每一步都求逆
+
+#[derive(Clone, Copy, Debug)]
+struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+impl Point {
+    fn identity() -> Point {
+        Point {
+            x: Fe::ZERO,
+            y: Fe::ONE,
+            z: Fe::ONE,
+            t: Fe::ZERO,
+        }
+    }
+
+    fn from_affine(x: Fe, y: Fe) -> Point {
+        Point {
+            x,
+            y,
+            z: Fe::ONE,
+            t: x.mul(y),
+        }
+    }
+
+    /// 统一的加法公式（a = -1），P + P 同样适用，所以倍点直接复用它
+    fn add(&self, other: &Point) -> Point {
+        let d2 = constants().d2;
+        let a = self.y.sub(self.x).mul(other.y.sub(other.x));
+        let b = self.y.add(self.x).mul(other.y.add(other.x));
+        let c = self.t.mul(d2).mul(other.t);
+        let d = self.z.add(self.z).mul(other.z);
+        let e = b.sub(a);
+        let f = d.sub(c);
+        let g = d.add(c);
+        let h = b.add(a);
+        Point {
+            x: e.mul(f),
+            y: g.mul(h),
+            z: f.mul(g),
+            t: e.mul(h),
+        }
+    }
+
+    /// 标量乘法: 从高位到低位的 "倍点-加" 算法，标量按 32 字节小端给出
+    fn mul_scalar(&self, scalar: &[u8; 32]) -> Point {
+        let mut result = Point::identity();
+        for i in (0..256).rev() {
+            result = result.add(&result);
+            if (scalar[i / 8] >> (i % 8)) & 1 == 1 {
+                result = result.add(self);
+            }
+        }
+        result
+    }
+
+    /// 压缩编码: y 的 255 位加上 x 的符号位
+    fn compress(&self) -> [u8; 32] {
+        let z_inv = self.z.invert();
+        let x = self.x.mul(z_inv);
+        let y = self.y.mul(z_inv);
+        let mut bytes = y.to_bytes();
+        bytes[31] |= (x.is_negative() as u8) << 7;
+        bytes
+    }
+
+    fn decompress(bytes: &[u8; 32]) -> Option<Point> {
+        let c = constants();
+        let negative = bytes[31] >> 7 == 1;
+        // NOTE 拒绝 y >= p 的非规范编码，否则同一个点会有两种写法
+        let mut raw = limbs_from_le(bytes);
+        raw[3] &= 0x7fff_ffff_ffff_ffff;
+        if geq(&raw, &P) {
+            return None;
+        }
+        let y = Fe::from_bytes(bytes);
+        let x = recover_x(y, negative, c.d, c.sqrt_m1)?;
+        Some(Point::from_affine(x, y))
+    }
+}
+
+fn base_mul(scalar: &[u8; 32]) -> Point {
+    constants().base.mul_scalar(scalar)
+}
+
+/// SHA-512 的 64 字节输出按小端解释后对 L 取模
+fn hash_to_scalar(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    let mut wide = [0u64; 8];
+    for (limb, chunk) in wide.iter_mut().zip(digest.chunks(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    limbs_to_le(&reduce_mod_l(&wide))
+}
+
+/// (r + k * a) mod L
+fn scalar_mul_add(k: &[u8; 32], a: &[u8; 32], r: &[u8; 32]) -> [u8; 32] {
+    let mut wide = mul_wide(&limbs_from_le(k), &limbs_from_le(a));
+    let mut carry = 0u128;
+    for (i, limb) in limbs_from_le(r).iter().enumerate() {
+        let t = wide[i] as u128 + *limb as u128 + carry;
+        wide[i] = t as u64;
+        carry = t >> 64;
+    }
+    for limb in wide.iter_mut().skip(4) {
+        let t = *limb as u128 + carry;
+        *limb = t as u64;
+        carry = t >> 64;
+    }
+    limbs_to_le(&reduce_mod_l(&wide))
+}
+
+// ---------------------------------------------------------------------------------------------
+// 签名方案
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// 公钥不是曲线上的合法点
+    InvalidPublicKey,
+    /// R 不是合法的点或 S 不小于 L
+    MalformedSignature,
+    /// 等式 S*B == R + k*A 不成立
+    VerificationFailed,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::InvalidPublicKey => write!(f, "public key is not a valid curve point"),
+            SignatureError::MalformedSignature => write!(f, "signature is malformed"),
+            SignatureError::VerificationFailed => write!(f, "signature verification failed"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Signature(pub [u8; 64]);
+
+impl Signature {
+    pub fn to_bytes(self) -> [u8; 64] {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PublicKey(pub [u8; 32]);
+
+impl PublicKey {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), SignatureError> {
+        let a = Point::decompress(&self.0).ok_or(SignatureError::InvalidPublicKey)?;
+
+        let r_bytes: [u8; 32] = signature.0[..32].try_into().unwrap();
+        let s_bytes: [u8; 32] = signature.0[32..].try_into().unwrap();
+        let r = Point::decompress(&r_bytes).ok_or(SignatureError::MalformedSignature)?;
+        // NOTE 要求 S < L，否则 S 和 S + L 都能通过验证，签名就具有了延展性
+        if geq(&limbs_from_le(&s_bytes), &L) {
+            return Err(SignatureError::MalformedSignature);
+        }
+
+        let k = hash_to_scalar(&[&r_bytes, &self.0, message]);
+        let left = base_mul(&s_bytes);
+        let right = r.add(&a.mul_scalar(&k));
+        if left.compress() == right.compress() {
+            Ok(())
+        } else {
+            Err(SignatureError::VerificationFailed)
+        }
+    }
+}
+
+/// 私钥，只保存 32 字节的种子，其余的量都由种子派生
+#[derive(Clone)]
+pub struct SecretKey {
+    seed: [u8; 32],
+    scalar: [u8; 32],
+    prefix: [u8; 32],
+    public: PublicKey,
+}
+
+impl SecretKey {
+    pub fn from_seed(seed: &[u8; 32]) -> SecretKey {
+        let mut hasher = Sha512::new();
+        hasher.update(seed);
+        let h = hasher.finalize();
+
+        // "clamp": 清掉低 3 位让标量是 8 的倍数（消除小子群），固定最高位
+        let mut scalar: [u8; 32] = h[..32].try_into().unwrap();
+        scalar[0] &= 248;
+        scalar[31] &= 127;
+        scalar[31] |= 64;
+        let prefix: [u8; 32] = h[32..].try_into().unwrap();
+
+        let public = PublicKey(base_mul(&scalar).compress());
+        SecretKey {
+            seed: *seed,
+            scalar,
+            prefix,
+            public,
+        }
+    }
+
+    pub fn seed(&self) -> &[u8; 32] {
+        &self.seed
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public
+    }
+
+    /// 确定性签名: r 由私钥前缀和消息派生，不依赖随机数发生器
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        let r = hash_to_scalar(&[&self.prefix, message]);
+        let r_point = base_mul(&r).compress();
+        let k = hash_to_scalar(&[&r_point, &self.public.0, message]);
+        let s = scalar_mul_add(&k, &self.scalar, &r);
+
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&r_point);
+        sig[32..].copy_from_slice(&s);
+        Signature(sig)
+    }
+}
+
+// NOTE 不要让 {:?} 把私钥打印到日志里
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+fn unhex<const N: usize>(s: &str) -> [u8; N] {
+    let bytes: Vec<u8> = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect();
+    bytes.try_into().unwrap()
+}
+
+#[test]
+fn test_field_arithmetic() {
+    let c = constants();
+    // sqrt(-1)^2 == -1
+    assert_eq!(c.sqrt_m1.square(), Fe::ONE.neg());
+    // a * a^-1 == 1
+    let a = Fe::from_u64(123456789);
+    assert_eq!(a.mul(a.invert()), Fe::ONE);
+    // (p - 1) + 1 == 0
+    assert_eq!(Fe::ONE.neg().add(Fe::ONE), Fe::ZERO);
+    // NOTE 基点的 y 坐标编码就是 0x58666...66，RFC 中常见的一串 6
+    assert_eq!(
+        super::hash::hex(&c.base.compress()),
+        "5866666666666666666666666666666666666666666666666666666666666666"
+    );
+}
+
+#[test]
+fn test_point_operations() {
+    let base = constants().base;
+    // L * B == 单位元
+    let l = limbs_to_le(&L);
+    assert_eq!(base.mul_scalar(&l).compress(), Point::identity().compress());
+
+    // 2B == B + B，3B == 2B + B
+    let mut two = [0u8; 32];
+    two[0] = 2;
+    let mut three = [0u8; 32];
+    three[0] = 3;
+    let b2 = base.add(&base);
+    assert_eq!(base.mul_scalar(&two).compress(), b2.compress());
+    assert_eq!(base.mul_scalar(&three).compress(), b2.add(&base).compress());
+
+    // 压缩后再解压得到同一个点
+    let p = Point::decompress(&b2.compress()).unwrap();
+    assert_eq!(p.compress(), b2.compress());
+}
+
+#[test]
+fn test_rfc8032_vectors() {
+    // NOTE RFC 8032 第 7.1 节的 TEST 1、TEST 2、TEST 3
+    let vectors = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+    for (seed, public, message, signature) in vectors {
+        let key = SecretKey::from_seed(&unhex(seed));
+        assert_eq!(super::hash::hex(key.public_key().as_bytes()), public);
+
+        let message: Vec<u8> = (0..message.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&message[i..i + 2], 16).unwrap())
+            .collect();
+        let sig = key.sign(&message);
+        assert_eq!(super::hash::hex(&sig.to_bytes()), signature);
+        assert_eq!(key.public_key().verify(&message, &sig), Ok(()));
+    }
+}
+
+#[test]
+fn test_verify_rejects_tampering() {
+    let key = SecretKey::from_seed(&[7u8; 32]);
+    let public = key.public_key();
+    let sig = key.sign(b"alice pays bob 10");
+
+    assert_eq!(public.verify(b"alice pays bob 10", &sig), Ok(()));
+    assert_eq!(
+        public.verify(b"alice pays bob 99", &sig),
+        Err(SignatureError::VerificationFailed)
+    );
+
+    // 换一把公钥
+    let other = SecretKey::from_seed(&[8u8; 32]).public_key();
+    assert_eq!(
+        other.verify(b"alice pays bob 10", &sig),
+        Err(SignatureError::VerificationFailed)
+    );
+
+    // S 加上 L 后数值上仍然满足验证等式，但必须被拒绝
+    let mut malleable = sig;
+    let s = limbs_from_le(&sig.0[32..]);
+    let (s_plus_l, _) = add_limbs(&s, &L);
+    malleable.0[32..].copy_from_slice(&limbs_to_le(&s_plus_l));
+    assert_eq!(
+        public.verify(b"alice pays bob 10", &malleable),
+        Err(SignatureError::MalformedSignature)
+    );
+}
@@ -0,0 +1,113 @@
+#![allow(dead_code)]
+
+/*
+ * 基于口令的密钥派生
+ *
+ * HMAC（RFC 2104）
+ *      直接用 H(key || message) 做消息认证码是不安全的: SHA-256 这种 Merkle–Damgård 结构允许 "长度扩展"，
+ *      知道 H(m) 就能算出 H(m || padding || 任意后缀)。HMAC 套了两层哈希来避免这个问题:
+ *          HMAC(K, m) = H((K ^ opad) || H((K ^ ipad) || m))
+ *
+ * PBKDF2（RFC 8018）
+ *      口令的熵很低，直接哈希一次就当作密钥，攻击者每秒可以试几十亿个口令。
+ *      PBKDF2 把 HMAC 迭代成千上万次，让每一次猜测都变得很慢；盐（salt）让相同的口令得到不同的密钥，
+ *      攻击者无法预先算好一张 "口令 -> 密钥" 的表。
+ *          U1 = HMAC(P, S || i),  Uj = HMAC(P, Uj-1),  Ti = U1 ^ U2 ^ ... ^ Uc
+ *      输出按 32 字节一块依次拼接 T1 || T2 || ...
+ */
+
+use super::hash::Sha256;
+
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC-SHA256
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    HmacSha256::new(key).finalize_with(message)
+}
+
+/// 预先算好内外两层的密钥块，PBKDF2 对同一个口令要算上万次 HMAC
+#[derive(Clone)]
+struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    fn new(key: &[u8]) -> HmacSha256 {
+        // 比块长的密钥先哈希一次，短的补 0
+        let mut block = [0u8; BLOCK_SIZE];
+        if key.len() > BLOCK_SIZE {
+            block[..32].copy_from_slice(&super::hash::sha256(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        let mut outer = Sha256::new();
+        inner.update(&block.map(|b| b ^ 0x36));
+        outer.update(&block.map(|b| b ^ 0x5c));
+        HmacSha256 { inner, outer }
+    }
+
+    fn finalize_with(&self, message: &[u8]) -> [u8; 32] {
+        let mut inner = self.inner.clone();
+        inner.update(message);
+        let mut outer = self.outer.clone();
+        outer.update(&inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// PBKDF2-HMAC-SHA256，把派生出的密钥写满 out
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "PBKDF2 needs at least one iteration");
+    let prf = HmacSha256::new(password);
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let block_index = (i as u32 + 1).to_be_bytes();
+        let mut u = prf.finalize_with(&[salt, &block_index].concat());
+        let mut t = u;
+        for _ in 1..iterations {
+            u = prf.finalize_with(&u);
+            for (t, u) in t.iter_mut().zip(u.iter()) {
+                *t ^= u;
+            }
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+#[test]
+fn test_hmac_sha256() {
+    use super::hash::hex;
+    // NOTE RFC 4231 的 Test Case 2 和 Test Case 6（密钥比块长）
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+#[test]
+fn test_pbkdf2_hmac_sha256() {
+    use super::hash::hex;
+    // NOTE RFC 7914 第 11 节，输出长度 64 字节跨越了两个块
+    let mut out = [0u8; 64];
+    pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut out);
+    assert_eq!(
+        hex(&out),
+        "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+    );
+
+    let mut out = [0u8; 32];
+    pbkdf2_hmac_sha256(b"password", b"salt", 4096, &mut out);
+    assert_eq!(
+        hex(&out),
+        "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"
+    );
+}
@@ -0,0 +1,6 @@
+pub(crate) mod chacha20;
+pub(crate) mod ed25519;
+pub(crate) mod hash;
+pub(crate) mod kdf;
+
+// NOTE 密码学相关的算法都从零实现，不依赖第三方 crate，只用于学习，不要用在生产环境
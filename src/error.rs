@@ -0,0 +1,309 @@
+#![allow(dead_code)]
+
+/*
+ * 应用层的错误类型
+ *      test_error.rs 里的函数要么返回 Result<i32, bool>，要么直接返回 io::Error。
+ *      真实的程序里一个函数往往会遇到好几种错误: 读文件（io::Error）、解析数字（ParseIntError）、
+ *      业务规则（WalletError、ChainError ...），? 要求它们都能转换成同一个错误类型。
+ *
+ * 做法
+ *      1. 一个枚举 AppError 把各种错误都包起来，每一种实现 From，这样 ? 会自动转换
+ *      2. context: 底层错误只知道 "No such file or directory"，不知道是在做什么的时候出的错，
+ *         调用方用 .context("reading config") 包一层说明，形成一条错误链
+ *      3. report: 沿着 source() 把整条链打印出来
+ *      4. code: 给每类错误一个稳定的数字编号，日志、监控、接口返回都用它，而不是去匹配错误信息的文本
+ *
+ * 分层
+ *      纯计算的接口（rlp::decode、Blockchain::add_block ...）仍然返回各模块自己的错误类型，调用方可以直接匹配变体。
+ *      面向应用、要碰文件和网络的接口（Keystore、Node::start/connect、WordFreq::add_file ...）返回 error::Result，
+ *      io::Error 在那里加上路径、地址之类的 context，模块自己的错误用 downcast_ref 取出来匹配。
+ *
+ *      error.rs 不依赖任何业务模块: 模块的错误类型实现 DomainError，给出自己的编号，
+ *      ? 就能把它转换成 AppError::Domain。依赖的方向是业务模块 -> error，而不是反过来。
+ */
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::num::{ParseFloatError, ParseIntError};
+
+pub type Result<T, E = AppError> = std::result::Result<T, E>;
+
+/// 业务模块的错误类型实现它就能用 ? 转换成 AppError
+pub trait DomainError: StdError + Send + Sync + 'static {
+    /// 稳定的错误编号，分段见 AppError::code
+    const CODE: u16;
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    Io(io::Error),
+    ParseInt(ParseIntError),
+    ParseFloat(ParseFloatError),
+    /// 业务模块的错误（WalletError、ChainError ...），code 来自 DomainError::CODE
+    Domain {
+        code: u16,
+        error: Box<dyn StdError + Send + Sync>,
+    },
+    /// 调用方附加的说明，source 是被包起来的错误
+    Context {
+        message: String,
+        source: Box<AppError>,
+    },
+}
+
+impl AppError {
+    /// 去掉所有 context 之后最里面的错误
+    pub fn root(&self) -> &AppError {
+        match self {
+            AppError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// 透明变体包着的那个错误，Context 没有
+    fn inner(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            AppError::Io(err) => Some(err),
+            AppError::ParseInt(err) => Some(err),
+            AppError::ParseFloat(err) => Some(err),
+            AppError::Domain { error, .. } => Some(error.as_ref()),
+            AppError::Context { .. } => None,
+        }
+    }
+
+    /// 最里面的错误是 E 时返回它，用来匹配模块自己的错误变体
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.root().inner()?.downcast_ref()
+    }
+
+    /// 底层是 io::Error 时返回它的 kind，不管中间包了多少层 context，
+    /// 也不管它是不是又被 P2pError::Io 这样的模块错误包了一层
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        chain(self.root().inner()?)
+            .find_map(|err| err.downcast_ref::<io::Error>())
+            .map(io::Error::kind)
+    }
+
+    /**
+     * 错误编号，按类别分段:
+     *      1xxx  I/O，按 io::ErrorKind 细分，可以据此决定是否重试
+     *      2xxx  输入格式错误
+     *      3xxx  业务错误
+     * NOTE 编号一旦对外公布就不能再改，新增的错误只能用新的编号
+     */
+    pub fn code(&self) -> u16 {
+        match self.root() {
+            AppError::Io(_) => match self.io_kind() {
+                Some(io::ErrorKind::NotFound) => 1001,
+                Some(io::ErrorKind::PermissionDenied) => 1002,
+                Some(io::ErrorKind::AlreadyExists) => 1003,
+                Some(
+                    io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::TimedOut,
+                ) => 1004,
+                _ => 1000,
+            },
+            AppError::ParseInt(_) => 2001,
+            AppError::ParseFloat(_) => 2002,
+            AppError::Domain { code, .. } => *code,
+            AppError::Context { .. } => unreachable!("root() never returns a context"),
+        }
+    }
+
+    /**
+     * 多行的错误报告:
+     *      error[1001]: loading wallet
+     *        caused by: reading key file /tmp/a.key
+     *        caused by: No such file or directory (os error 2)
+     */
+    // NOTE 约定每个错误的 Display 只描述自己这一层，被包住的错误只通过 source() 给出，
+    // 否则同一段信息会在报告里出现两次
+    pub fn report(&self) -> String {
+        let mut out = format!("error[{}]", self.code());
+        for (i, err) in chain(self).enumerate() {
+            out.push_str(if i == 0 { ": " } else { "\n  caused by: " });
+            out.push_str(&err.to_string());
+        }
+        out
+    }
+}
+
+/// 从 err 自己开始，沿着 source() 一直走到最底层
+pub fn chain<'a>(
+    err: &'a (dyn StdError + 'static),
+) -> impl Iterator<Item = &'a (dyn StdError + 'static)> {
+    std::iter::successors(Some(err), |&e| e.source())
+}
+
+/*
+ * 包装其它错误的变体是 "透明" 的: Display 直接显示里面的错误，source 也直接转交给里面的错误，
+ * 这样错误链上每一层只出现一次。只有 Context 会在链上新增一层。
+ */
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Io(err) => err.fmt(f),
+            AppError::ParseInt(err) => err.fmt(f),
+            AppError::ParseFloat(err) => err.fmt(f),
+            AppError::Domain { error, .. } => error.fmt(f),
+            AppError::Context { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl StdError for AppError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            AppError::Io(err) => err.source(),
+            AppError::ParseInt(err) => err.source(),
+            AppError::ParseFloat(err) => err.source(),
+            AppError::Domain { error, .. } => error.source(),
+            AppError::Context { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($t:ty)),* $(,)?) => {
+        $(impl From<$t> for AppError {
+            fn from(err: $t) -> AppError {
+                AppError::$variant(err)
+            }
+        })*
+    };
+}
+
+impl_from! {
+    Io(io::Error),
+    ParseInt(ParseIntError),
+    ParseFloat(ParseFloatError),
+}
+
+impl<E: DomainError> From<E> for AppError {
+    fn from(err: E) -> AppError {
+        AppError::Domain {
+            code: E::CODE,
+            error: Box::new(err),
+        }
+    }
+}
+
+/// 给任何能转换成 AppError 的 Result 附加一层说明
+pub trait Context<T> {
+    fn context(self, message: impl Into<String>) -> Result<T>;
+
+    /// 只在出错时才生成说明，适合需要 format! 的场合
+    fn with_context<M: Into<String>>(self, f: impl FnOnce() -> M) -> Result<T>;
+}
+
+impl<T, E: Into<AppError>> Context<T> for std::result::Result<T, E> {
+    fn context(self, message: impl Into<String>) -> Result<T> {
+        self.map_err(|err| AppError::Context {
+            message: message.into(),
+            source: Box::new(err.into()),
+        })
+    }
+
+    fn with_context<M: Into<String>>(self, f: impl FnOnce() -> M) -> Result<T> {
+        self.map_err(|err| AppError::Context {
+            message: f().into(),
+            source: Box::new(err.into()),
+        })
+    }
+}
+
+#[test]
+fn test_error_context_chain() {
+    fn parse_port(text: &str) -> Result<u16> {
+        let value = text
+            .strip_prefix("port=")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing port="))?;
+        // ? 把 ParseIntError 自动转换成 AppError::ParseInt
+        Ok(value.trim().parse::<u16>()?)
+    }
+
+    fn load(path: &std::path::Path) -> Result<u16> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        parse_port(&text).context("parsing port")
+    }
+
+    let dir = std::env::temp_dir().join(format!("app-error-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("server.conf");
+
+    let err = load(&path).context("loading config").unwrap_err();
+    assert_eq!(err.to_string(), "loading config");
+    assert_eq!(err.code(), 1001);
+    assert_eq!(err.io_kind(), Some(io::ErrorKind::NotFound));
+    assert_eq!(chain(&err).count(), 3);
+    let report = err.report();
+    println!("{}", report);
+    assert!(report.starts_with("error[1001]: loading config\n  caused by: reading "));
+
+    std::fs::write(&path, "port=99999").unwrap();
+    let err = load(&path).unwrap_err();
+    assert_eq!(err.code(), 2001);
+    assert_eq!(
+        err.report(),
+        "error[2001]: parsing port\n  caused by: number too large to fit in target type"
+    );
+    assert!(matches!(err.root(), AppError::ParseInt(_)));
+
+    std::fs::write(&path, "port=8080").unwrap();
+    assert_eq!(load(&path).unwrap(), 8080);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_error_from_domain() {
+    use crate::wallet::{Keystore, WalletError};
+
+    let dir = std::env::temp_dir().join(format!("app-error-wallet-{}", std::process::id()));
+    let result: Result<()> = (|| {
+        let keystore = Keystore::open(&dir)?.with_iterations(1000);
+        keystore.unlock(&[7u8; 20], "secret")?;
+        Ok(())
+    })();
+    std::fs::remove_dir_all(&dir).ok();
+
+    let err = result.context("signing withdrawal").unwrap_err();
+    assert_eq!(err.code(), 3002);
+    assert!(matches!(
+        err.downcast_ref::<WalletError>(),
+        Some(WalletError::NotFound(_))
+    ));
+    assert!(err.downcast_ref::<io::Error>().is_none());
+
+    // 模块错误包着 io::Error 时，每一层只在报告里出现一次
+    let err = AppError::from(crate::p2p::message::P2pError::Io(io::Error::new(
+        io::ErrorKind::ConnectionReset,
+        "connection reset by peer",
+    )));
+    assert_eq!(err.io_kind(), Some(io::ErrorKind::ConnectionReset));
+    assert_eq!(
+        err.report(),
+        "error[3003]: network error\n  caused by: connection reset by peer"
+    );
+
+    // 钥匙串目录的位置上已经有一个普通文件: 底层的 io::Error 带着路径作为 context
+    let file = std::env::temp_dir().join(format!("app-error-file-{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    let err = Keystore::open(&file).err().unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert!(err.io_kind().is_some());
+    assert!(err.to_string().contains(&file.display().to_string()));
+
+    // 各模块的错误都能用 ? 转换
+    let err: AppError = "0xzz"
+        .parse::<crate::bigint::uint::U256>()
+        .unwrap_err()
+        .into();
+    assert_eq!(err.code(), 2003);
+    let err: AppError = crate::linalg::ndarray::NdArray::from_vec(&[2, 2], vec![1])
+        .unwrap_err()
+        .into();
+    assert_eq!((err.code(), err.io_kind()), (3004, None));
+}
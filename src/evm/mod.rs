@@ -0,0 +1,5 @@
+pub(crate) mod opcode;
+pub(crate) mod vm;
+pub(crate) mod word;
+
+// NOTE EVM 是一个基于栈的虚拟机，智能合约被编译成字节码后在其中执行
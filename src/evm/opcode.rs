@@ -0,0 +1,303 @@
+#![allow(dead_code)]
+
+/*
+ * 操作码表
+ *      EVM 的指令都是 1 个字节，只有 PUSH1..PUSH32 后面紧跟 1..32 字节的立即数。
+ *      这里只收录解释器实现了的那部分指令。
+ */
+
+pub const STOP: u8 = 0x00;
+pub const ADD: u8 = 0x01;
+pub const MUL: u8 = 0x02;
+pub const SUB: u8 = 0x03;
+pub const DIV: u8 = 0x04;
+pub const SDIV: u8 = 0x05;
+pub const MOD: u8 = 0x06;
+pub const SMOD: u8 = 0x07;
+pub const ADDMOD: u8 = 0x08;
+pub const MULMOD: u8 = 0x09;
+pub const EXP: u8 = 0x0a;
+pub const SIGNEXTEND: u8 = 0x0b;
+
+pub const LT: u8 = 0x10;
+pub const GT: u8 = 0x11;
+pub const SLT: u8 = 0x12;
+pub const SGT: u8 = 0x13;
+pub const EQ: u8 = 0x14;
+pub const ISZERO: u8 = 0x15;
+pub const AND: u8 = 0x16;
+pub const OR: u8 = 0x17;
+pub const XOR: u8 = 0x18;
+pub const NOT: u8 = 0x19;
+pub const BYTE: u8 = 0x1a;
+pub const SHL: u8 = 0x1b;
+pub const SHR: u8 = 0x1c;
+pub const SAR: u8 = 0x1d;
+
+pub const KECCAK256: u8 = 0x20;
+
+pub const CALLER: u8 = 0x33;
+pub const CALLVALUE: u8 = 0x34;
+pub const CALLDATALOAD: u8 = 0x35;
+pub const CALLDATASIZE: u8 = 0x36;
+pub const CALLDATACOPY: u8 = 0x37;
+pub const CODESIZE: u8 = 0x38;
+pub const CODECOPY: u8 = 0x39;
+
+pub const POP: u8 = 0x50;
+pub const MLOAD: u8 = 0x51;
+pub const MSTORE: u8 = 0x52;
+pub const MSTORE8: u8 = 0x53;
+pub const SLOAD: u8 = 0x54;
+pub const SSTORE: u8 = 0x55;
+pub const JUMP: u8 = 0x56;
+pub const JUMPI: u8 = 0x57;
+pub const PC: u8 = 0x58;
+pub const MSIZE: u8 = 0x59;
+pub const GAS: u8 = 0x5a;
+pub const JUMPDEST: u8 = 0x5b;
+pub const PUSH0: u8 = 0x5f;
+pub const PUSH1: u8 = 0x60;
+pub const PUSH32: u8 = 0x7f;
+pub const DUP1: u8 = 0x80;
+pub const DUP16: u8 = 0x8f;
+pub const SWAP1: u8 = 0x90;
+pub const SWAP16: u8 = 0x9f;
+
+pub const RETURN: u8 = 0xf3;
+pub const REVERT: u8 = 0xfd;
+pub const INVALID: u8 = 0xfe;
+
+/// 除 PUSHn、DUPn、SWAPn 以外的指令名
+const NAMES: [(u8, &str); 50] = [
+    (STOP, "STOP"),
+    (ADD, "ADD"),
+    (MUL, "MUL"),
+    (SUB, "SUB"),
+    (DIV, "DIV"),
+    (SDIV, "SDIV"),
+    (MOD, "MOD"),
+    (SMOD, "SMOD"),
+    (ADDMOD, "ADDMOD"),
+    (MULMOD, "MULMOD"),
+    (EXP, "EXP"),
+    (SIGNEXTEND, "SIGNEXTEND"),
+    (LT, "LT"),
+    (GT, "GT"),
+    (SLT, "SLT"),
+    (SGT, "SGT"),
+    (EQ, "EQ"),
+    (ISZERO, "ISZERO"),
+    (AND, "AND"),
+    (OR, "OR"),
+    (XOR, "XOR"),
+    (NOT, "NOT"),
+    (BYTE, "BYTE"),
+    (SHL, "SHL"),
+    (SHR, "SHR"),
+    (SAR, "SAR"),
+    (KECCAK256, "KECCAK256"),
+    (CALLER, "CALLER"),
+    (CALLVALUE, "CALLVALUE"),
+    (CALLDATALOAD, "CALLDATALOAD"),
+    (CALLDATASIZE, "CALLDATASIZE"),
+    (CALLDATACOPY, "CALLDATACOPY"),
+    (CODESIZE, "CODESIZE"),
+    (CODECOPY, "CODECOPY"),
+    (POP, "POP"),
+    (MLOAD, "MLOAD"),
+    (MSTORE, "MSTORE"),
+    (MSTORE8, "MSTORE8"),
+    (SLOAD, "SLOAD"),
+    (SSTORE, "SSTORE"),
+    (JUMP, "JUMP"),
+    (JUMPI, "JUMPI"),
+    (PC, "PC"),
+    (MSIZE, "MSIZE"),
+    (GAS, "GAS"),
+    (JUMPDEST, "JUMPDEST"),
+    (PUSH0, "PUSH0"),
+    (RETURN, "RETURN"),
+    (REVERT, "REVERT"),
+    (INVALID, "INVALID"),
+];
+
+/// PUSHn 后面立即数的字节数，其他指令为 0
+pub fn immediate_size(op: u8) -> usize {
+    if (PUSH1..=PUSH32).contains(&op) {
+        (op - PUSH1 + 1) as usize
+    } else {
+        0
+    }
+}
+
+pub fn mnemonic(op: u8) -> Option<String> {
+    match op {
+        PUSH1..=PUSH32 => Some(format!("PUSH{}", op - PUSH1 + 1)),
+        DUP1..=DUP16 => Some(format!("DUP{}", op - DUP1 + 1)),
+        SWAP1..=SWAP16 => Some(format!("SWAP{}", op - SWAP1 + 1)),
+        _ => NAMES
+            .iter()
+            .find(|(code, _)| *code == op)
+            .map(|(_, name)| name.to_string()),
+    }
+}
+
+pub fn from_mnemonic(name: &str) -> Option<u8> {
+    let name = name.to_ascii_uppercase();
+    let numbered = |prefix: &str, first: u8, max: u8| -> Option<u8> {
+        let n: u8 = name.strip_prefix(prefix)?.parse().ok()?;
+        if (1..=max).contains(&n) {
+            Some(first + n - 1)
+        } else {
+            None
+        }
+    };
+    if name != "PUSH0" {
+        if let Some(op) = numbered("PUSH", PUSH1, 32)
+            .or_else(|| numbered("DUP", DUP1, 16))
+            .or_else(|| numbered("SWAP", SWAP1, 16))
+        {
+            return Some(op);
+        }
+    }
+    NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(code, _)| *code)
+}
+
+/// 固定的 gas 开销，动态部分（内存扩展、存储、EXP 指数长度等）由解释器另外计算
+pub fn static_gas(op: u8) -> u64 {
+    match op {
+        STOP | RETURN | REVERT => 0,
+        JUMPDEST => 1,
+        PC | MSIZE | GAS | POP | CALLER | CALLVALUE | CALLDATASIZE | CODESIZE | PUSH0 => 2,
+        ADD | SUB | LT | GT | SLT | SGT | EQ | ISZERO | AND | OR | XOR | NOT | BYTE | SHL | SHR
+        | SAR | CALLDATALOAD | MLOAD | MSTORE | MSTORE8 | CALLDATACOPY | CODECOPY => 3,
+        PUSH1..=PUSH32 | DUP1..=DUP16 | SWAP1..=SWAP16 => 3,
+        MUL | DIV | SDIV | MOD | SMOD | SIGNEXTEND => 5,
+        ADDMOD | MULMOD | JUMP => 8,
+        EXP | JUMPI => 10,
+        KECCAK256 => 30,
+        // NOTE SLOAD 和 SSTORE 的开销取决于冷热访问和新旧值，全部在解释器里计算
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    pub immediate: Vec<u8>,
+}
+
+/// 反汇编: 逐条解析指令，跳过 PUSH 的立即数
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut out = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let size = immediate_size(opcode);
+        // NOTE 代码末尾被截断的 PUSH 立即数，执行时按 0 补齐
+        let end = (pc + 1 + size).min(code.len());
+        out.push(Instruction {
+            pc,
+            opcode,
+            immediate: code[pc + 1..end].to_vec(),
+        });
+        pc += 1 + size;
+    }
+    out
+}
+
+/// 反汇编成文本，每行形如 `0002: PUSH1 0x03`
+pub fn disassemble_to_string(code: &[u8]) -> String {
+    let mut lines = Vec::new();
+    for ins in disassemble(code) {
+        let name = mnemonic(ins.opcode).unwrap_or_else(|| format!("UNKNOWN(0x{:02x})", ins.opcode));
+        if ins.immediate.is_empty() && immediate_size(ins.opcode) == 0 {
+            lines.push(format!("{:04x}: {}", ins.pc, name));
+        } else {
+            let hex: String = ins.immediate.iter().map(|b| format!("{:02x}", b)).collect();
+            lines.push(format!("{:04x}: {} 0x{}", ins.pc, name, hex));
+        }
+    }
+    lines.join("\n")
+}
+
+/// 极简的汇编器，手写合约时比直接写十六进制可读得多
+///     "PUSH1 0x02 PUSH1 0x03 ADD"
+/// PUSHn 后面必须跟一个十六进制立即数，位数不足时高位补 0
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let mut code = Vec::new();
+    let mut tokens = source.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let op = from_mnemonic(token).ok_or_else(|| format!("unknown mnemonic `{}`", token))?;
+        code.push(op);
+        let size = immediate_size(op);
+        if size == 0 {
+            continue;
+        }
+        let imm = tokens
+            .next()
+            .ok_or_else(|| format!("`{}` needs an immediate", token))?;
+        let digits = imm.trim_start_matches("0x");
+        // NOTE 先确认全是 ASCII 十六进制数字: 下面按字符补 0、按字节切片，
+        // 遇到多字节字符会切在字符中间而 panic
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("bad immediate `{}`", imm));
+        }
+        if digits.len() > size * 2 {
+            return Err(format!("immediate `{}` does not fit in {}", imm, token));
+        }
+        let padded = format!("{:0>width$}", digits, width = size * 2);
+        for i in 0..size {
+            code.push(u8::from_str_radix(&padded[i * 2..i * 2 + 2], 16).unwrap());
+        }
+    }
+    Ok(code)
+}
+
+#[test]
+fn test_mnemonics() {
+    assert_eq!(from_mnemonic("push1"), Some(PUSH1));
+    assert_eq!(from_mnemonic("PUSH32"), Some(PUSH32));
+    assert_eq!(from_mnemonic("PUSH0"), Some(PUSH0));
+    assert_eq!(from_mnemonic("DUP16"), Some(DUP16));
+    assert_eq!(from_mnemonic("SWAP17"), None);
+    assert_eq!(mnemonic(0x61).as_deref(), Some("PUSH2"));
+    assert_eq!(mnemonic(0x0c), None);
+}
+
+#[test]
+fn test_assemble_and_disassemble() {
+    let code = assemble("PUSH1 0x02 PUSH2 0x0300 ADD PUSH0 MSTORE STOP").unwrap();
+    assert_eq!(
+        code,
+        vec![0x60, 0x02, 0x61, 0x03, 0x00, 0x01, 0x5f, 0x52, 0x00]
+    );
+
+    let text = disassemble_to_string(&code);
+    assert_eq!(
+        text,
+        "0000: PUSH1 0x02\n0002: PUSH2 0x0300\n0005: ADD\n0006: PUSH0\n0007: MSTORE\n0008: STOP"
+    );
+
+    // NOTE 0x5b 出现在 PUSH 的立即数里时不是指令，反汇编必须跳过它
+    let tricky = [0x60, 0x5b, 0x5b, 0xef];
+    let ins = disassemble(&tricky);
+    assert_eq!(ins.len(), 3);
+    assert_eq!(ins[1].pc, 2);
+    assert!(disassemble_to_string(&tricky).ends_with("0003: UNKNOWN(0xef)"));
+
+    assert!(assemble("PUSH1").is_err());
+    assert!(assemble("PUSH1 0x0102").is_err());
+    assert!(assemble("FOO").is_err());
+    assert_eq!(assemble("PUSH1 é"), Err(String::from("bad immediate `é`")));
+    assert_eq!(
+        assemble("PUSH2 0x€"),
+        Err(String::from("bad immediate `0x€`"))
+    );
+    assert!(assemble("PUSH1 +1").is_err());
+}
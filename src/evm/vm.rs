@@ -0,0 +1,656 @@
+#![allow(dead_code)]
+
+/*
+ * EVM 解释器
+ *      - 栈: 最多 1024 个 256 位的字
+ *      - 内存: 按字节寻址，按 32 字节为单位扩展，扩展得越大越贵（二次方增长）
+ *      - 存储: 256 位键到 256 位值的映射，执行成功才会写回，REVERT 和异常都会回滚
+ *      - gas: 每条指令先扣费再执行，扣到不够时抛出 OutOfGas 并消耗掉全部 gas
+ *
+ * NOTE gas 规则是简化过的: 没有退款，SSTORE 只区分 "0 -> 非 0" 和其他情况
+ */
+
+use super::opcode::*;
+use super::word::Word;
+use crate::crypto::hash::keccak256;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+pub type Storage = HashMap<Word, Word>;
+
+const STACK_LIMIT: usize = 1024;
+/// 内存上限，实际的 EVM 靠 gas 限制内存，这里直接拒绝过大的偏移，避免分配失败
+const MEMORY_LIMIT: u64 = 1 << 24;
+
+const SLOAD_COLD: u64 = 2100;
+const SLOAD_WARM: u64 = 100;
+const SSTORE_SET: u64 = 20000;
+const SSTORE_RESET: u64 = 5000;
+
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub caller: Word,
+    pub value: Word,
+    pub calldata: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError {
+    StackUnderflow { pc: usize },
+    StackOverflow { pc: usize },
+    InvalidJump { pc: usize, dest: Word },
+    InvalidOpcode { pc: usize, opcode: u8 },
+    OutOfGas { pc: usize },
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::StackUnderflow { pc } => write!(f, "stack underflow at pc {}", pc),
+            VmError::StackOverflow { pc } => write!(f, "stack overflow at pc {}", pc),
+            VmError::InvalidJump { pc, dest } => {
+                write!(f, "jump to {:?} at pc {} is not a JUMPDEST", dest, pc)
+            }
+            VmError::InvalidOpcode { pc, opcode } => {
+                write!(f, "invalid opcode 0x{:02x} at pc {}", opcode, pc)
+            }
+            VmError::OutOfGas { pc } => write!(f, "out of gas at pc {}", pc),
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exit {
+    Stop,
+    Return,
+    Revert,
+    Error(VmError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub exit: Exit,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+}
+
+impl Outcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self.exit, Exit::Stop | Exit::Return)
+    }
+}
+
+/// 预先扫描一遍代码，找出所有合法的跳转目标（不能落在 PUSH 的立即数里）
+pub fn analyze_jumpdests(code: &[u8]) -> Vec<bool> {
+    let mut valid = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        if code[pc] == JUMPDEST {
+            valid[pc] = true;
+        }
+        pc += 1 + immediate_size(code[pc]);
+    }
+    valid
+}
+
+struct Machine<'a> {
+    code: &'a [u8],
+    ctx: &'a Context,
+    jumpdests: Vec<bool>,
+    pc: usize,
+    stack: Vec<Word>,
+    memory: Vec<u8>,
+    gas_left: u64,
+    // NOTE 在副本上修改存储，成功时再整体写回，这就是最朴素的 "回滚"
+    storage: Storage,
+    warm_slots: HashSet<Word>,
+}
+
+/// 控制流: 继续执行下一条指令，或者带着输出结束
+enum Step {
+    Continue,
+    Halt(Exit, Vec<u8>),
+}
+
+impl<'a> Machine<'a> {
+    fn charge(&mut self, gas: u64) -> Result<(), VmError> {
+        if gas > self.gas_left {
+            self.gas_left = 0;
+            return Err(VmError::OutOfGas { pc: self.pc });
+        }
+        self.gas_left -= gas;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Word, VmError> {
+        self.stack
+            .pop()
+            .ok_or(VmError::StackUnderflow { pc: self.pc })
+    }
+
+    fn push(&mut self, word: Word) -> Result<(), VmError> {
+        if self.stack.len() >= STACK_LIMIT {
+            return Err(VmError::StackOverflow { pc: self.pc });
+        }
+        self.stack.push(word);
+        Ok(())
+    }
+
+    fn memory_cost(words: u64) -> u64 {
+        3 * words + words * words / 512
+    }
+
+    /// 确保 [offset, offset + size) 可访问，按需扩展内存并扣除扩展费用，返回起始下标
+    fn expand_memory(&mut self, offset: Word, size: Word) -> Result<usize, VmError> {
+        let size = size.to_u64().ok_or(VmError::OutOfGas { pc: self.pc })?;
+        if size == 0 {
+            // NOTE 长度为 0 的访问不会扩展内存，偏移可以是任意值
+            return Ok(0);
+        }
+        let offset = offset.to_u64().ok_or(VmError::OutOfGas { pc: self.pc })?;
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= MEMORY_LIMIT)
+            .ok_or(VmError::OutOfGas { pc: self.pc })?;
+
+        let old_words = self.memory.len() as u64 / 32;
+        let new_words = end.div_ceil(32);
+        if new_words > old_words {
+            self.charge(Self::memory_cost(new_words) - Self::memory_cost(old_words))?;
+            self.memory.resize(new_words as usize * 32, 0);
+        }
+        Ok(offset as usize)
+    }
+
+    /// 从 src 中拷贝，越界的部分补 0（CALLDATACOPY、CODECOPY 的语义）
+    fn copy_padded(&mut self, dest: usize, src: &[u8], offset: Word, size: usize) {
+        let offset = offset.to_u64().map_or(usize::MAX, |o| o as usize);
+        for i in 0..size {
+            self.memory[dest + i] = offset
+                .checked_add(i)
+                .and_then(|j| src.get(j))
+                .copied()
+                .unwrap_or(0);
+        }
+    }
+
+    fn step(&mut self) -> Result<Step, VmError> {
+        let Some(&op) = self.code.get(self.pc) else {
+            // 代码末尾隐含一条 STOP
+            return Ok(Step::Halt(Exit::Stop, Vec::new()));
+        };
+        self.charge(static_gas(op))?;
+        let mut next_pc = self.pc + 1;
+
+        match op {
+            STOP => return Ok(Step::Halt(Exit::Stop, Vec::new())),
+
+            ADD | MUL | SUB | DIV | SDIV | MOD | SMOD | EXP | SIGNEXTEND | LT | GT | SLT | SGT
+            | EQ | AND | OR | XOR | BYTE | SHL | SHR | SAR => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                let result = match op {
+                    ADD => a.wrapping_add(b),
+                    MUL => a.wrapping_mul(b),
+                    SUB => a.wrapping_sub(b),
+                    DIV => a.div_rem(b).0,
+                    SDIV => {
+                        if b.is_zero() {
+                            Word::ZERO
+                        } else {
+                            a.signed_div(b)
+                        }
+                    }
+                    MOD => a.div_rem(b).1,
+                    SMOD => {
+                        if b.is_zero() {
+                            Word::ZERO
+                        } else {
+                            a.signed_rem(b)
+                        }
+                    }
+                    EXP => {
+                        // 指数每多一个字节多收 50 gas
+                        let exp_bytes = b.bits().div_ceil(8) as u64;
+                        self.charge(50 * exp_bytes)?;
+                        a.wrapping_pow(b)
+                    }
+                    SIGNEXTEND => b.sign_extend(a),
+                    LT => Word::from(a < b),
+                    GT => Word::from(a > b),
+                    SLT => Word::from(a.signed_cmp(b).is_lt()),
+                    SGT => Word::from(a.signed_cmp(b).is_gt()),
+                    EQ => Word::from(a == b),
+                    AND => a & b,
+                    OR => a | b,
+                    XOR => a ^ b,
+                    BYTE => b.byte(a),
+                    // NOTE 移位指令的栈顶是位移量，被移位的值在第二个
+                    SHL => b.shl(a.to_u64().map_or(256, |s| s.min(256) as usize)),
+                    SHR => b.shr(a.to_u64().map_or(256, |s| s.min(256) as usize)),
+                    SAR => b.sar(a.to_u64().map_or(256, |s| s.min(256) as usize)),
+                    _ => unreachable!(),
+                };
+                self.push(result)?;
+            }
+            ADDMOD | MULMOD => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                let n = self.pop()?;
+                let result = if op == ADDMOD {
+                    a.add_mod(b, n)
+                } else {
+                    a.mul_mod(b, n)
+                };
+                self.push(result)?;
+            }
+            ISZERO => {
+                let a = self.pop()?;
+                self.push(Word::from(a.is_zero()))?;
+            }
+            NOT => {
+                let a = self.pop()?;
+                self.push(!a)?;
+            }
+
+            KECCAK256 => {
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let start = self.expand_memory(offset, size)?;
+                let len = size.low_u64() as usize;
+                self.charge(6 * (len as u64).div_ceil(32))?;
+                let hash = keccak256(&self.memory[start..start + len]);
+                self.push(Word::from_be_bytes(hash))?;
+            }
+
+            CALLER => self.push(self.ctx.caller)?,
+            CALLVALUE => self.push(self.ctx.value)?,
+            CALLDATALOAD => {
+                let offset = self.pop()?;
+                let mut buf = [0u8; 32];
+                if let Some(offset) = offset.to_u64() {
+                    for (i, b) in buf.iter_mut().enumerate() {
+                        *b = (offset as usize)
+                            .checked_add(i)
+                            .and_then(|j| self.ctx.calldata.get(j))
+                            .copied()
+                            .unwrap_or(0);
+                    }
+                }
+                self.push(Word::from_be_bytes(buf))?;
+            }
+            CALLDATASIZE => self.push(Word::from_u64(self.ctx.calldata.len() as u64))?,
+            CODESIZE => self.push(Word::from_u64(self.code.len() as u64))?,
+            CALLDATACOPY | CODECOPY => {
+                let dest = self.pop()?;
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let start = self.expand_memory(dest, size)?;
+                let len = size.low_u64() as usize;
+                self.charge(3 * (len as u64).div_ceil(32))?;
+                let src = if op == CALLDATACOPY {
+                    self.ctx.calldata.as_slice()
+                } else {
+                    self.code
+                };
+                self.copy_padded(start, src, offset, len);
+            }
+
+            POP => {
+                self.pop()?;
+            }
+            MLOAD => {
+                let offset = self.pop()?;
+                let start = self.expand_memory(offset, Word::from_u64(32))?;
+                let bytes: [u8; 32] = self.memory[start..start + 32].try_into().unwrap();
+                self.push(Word::from_be_bytes(bytes))?;
+            }
+            MSTORE => {
+                let offset = self.pop()?;
+                let value = self.pop()?;
+                let start = self.expand_memory(offset, Word::from_u64(32))?;
+                self.memory[start..start + 32].copy_from_slice(&value.to_be_bytes());
+            }
+            MSTORE8 => {
+                let offset = self.pop()?;
+                let value = self.pop()?;
+                let start = self.expand_memory(offset, Word::ONE)?;
+                self.memory[start] = value.low_u64() as u8;
+            }
+            SLOAD => {
+                let key = self.pop()?;
+                let cost = if self.warm_slots.insert(key) {
+                    SLOAD_COLD
+                } else {
+                    SLOAD_WARM
+                };
+                self.charge(cost)?;
+                let value = self.storage.get(&key).copied().unwrap_or_default();
+                self.push(value)?;
+            }
+            SSTORE => {
+                let key = self.pop()?;
+                let value = self.pop()?;
+                let current = self.storage.get(&key).copied().unwrap_or_default();
+                let mut cost = if current.is_zero() && !value.is_zero() {
+                    SSTORE_SET
+                } else {
+                    SSTORE_RESET
+                };
+                if self.warm_slots.insert(key) {
+                    cost += SLOAD_COLD;
+                }
+                self.charge(cost)?;
+                // NOTE 存 0 等于删除，保持存储中没有值为 0 的条目
+                if value.is_zero() {
+                    self.storage.remove(&key);
+                } else {
+                    self.storage.insert(key, value);
+                }
+            }
+            JUMP => {
+                let dest = self.pop()?;
+                next_pc = self.jump_target(dest)?;
+            }
+            JUMPI => {
+                let dest = self.pop()?;
+                let cond = self.pop()?;
+                if !cond.is_zero() {
+                    next_pc = self.jump_target(dest)?;
+                }
+            }
+            PC => self.push(Word::from_u64(self.pc as u64))?,
+            MSIZE => self.push(Word::from_u64(self.memory.len() as u64))?,
+            // NOTE GAS 返回的是扣除本条指令费用之后剩余的 gas
+            GAS => self.push(Word::from_u64(self.gas_left))?,
+            JUMPDEST => {}
+            PUSH0 => self.push(Word::ZERO)?,
+            PUSH1..=PUSH32 => {
+                let size = immediate_size(op);
+                let start = self.pc + 1;
+                let end = (start + size).min(self.code.len());
+                // 代码末尾截断的立即数在低位补 0
+                let mut buf = vec![0u8; size];
+                buf[..end - start].copy_from_slice(&self.code[start..end]);
+                self.push(Word::from_be_slice(&buf))?;
+                next_pc = start + size;
+            }
+            DUP1..=DUP16 => {
+                let n = (op - DUP1 + 1) as usize;
+                if self.stack.len() < n {
+                    return Err(VmError::StackUnderflow { pc: self.pc });
+                }
+                let value = self.stack[self.stack.len() - n];
+                self.push(value)?;
+            }
+            SWAP1..=SWAP16 => {
+                let n = (op - SWAP1 + 1) as usize;
+                let len = self.stack.len();
+                if len < n + 1 {
+                    return Err(VmError::StackUnderflow { pc: self.pc });
+                }
+                self.stack.swap(len - 1, len - 1 - n);
+            }
+
+            RETURN | REVERT => {
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let start = self.expand_memory(offset, size)?;
+                let len = size.low_u64() as usize;
+                let output = self.memory[start..start + len].to_vec();
+                let exit = if op == RETURN {
+                    Exit::Return
+                } else {
+                    Exit::Revert
+                };
+                return Ok(Step::Halt(exit, output));
+            }
+            _ => {
+                return Err(VmError::InvalidOpcode {
+                    pc: self.pc,
+                    opcode: op,
+                })
+            }
+        }
+
+        self.pc = next_pc;
+        Ok(Step::Continue)
+    }
+
+    fn jump_target(&self, dest: Word) -> Result<usize, VmError> {
+        match dest.to_u64() {
+            Some(d) if (d as usize) < self.jumpdests.len() && self.jumpdests[d as usize] => {
+                Ok(d as usize)
+            }
+            _ => Err(VmError::InvalidJump { pc: self.pc, dest }),
+        }
+    }
+}
+
+/// 执行一段字节码。只有正常结束（STOP / RETURN）时才把存储的修改写回 storage
+pub fn execute(code: &[u8], ctx: &Context, storage: &mut Storage) -> Outcome {
+    let mut machine = Machine {
+        code,
+        ctx,
+        jumpdests: analyze_jumpdests(code),
+        pc: 0,
+        stack: Vec::new(),
+        memory: Vec::new(),
+        gas_left: ctx.gas_limit,
+        storage: storage.clone(),
+        warm_slots: HashSet::new(),
+    };
+
+    loop {
+        match machine.step() {
+            Ok(Step::Continue) => {}
+            Ok(Step::Halt(exit, output)) => {
+                if exit != Exit::Revert {
+                    *storage = machine.storage;
+                }
+                return Outcome {
+                    exit,
+                    output,
+                    gas_used: ctx.gas_limit - machine.gas_left,
+                };
+            }
+            // NOTE 异常终止会消耗掉全部 gas，这是对恶意或错误代码的惩罚
+            Err(e) => {
+                return Outcome {
+                    exit: Exit::Error(e),
+                    output: Vec::new(),
+                    gas_used: ctx.gas_limit,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn run(source: &str, calldata: &[u8], storage: &mut Storage) -> Outcome {
+    let code = assemble(source).unwrap();
+    let ctx = Context {
+        caller: Word::from_u64(0xca11e5),
+        value: Word::ZERO,
+        calldata: calldata.to_vec(),
+        gas_limit: 1_000_000,
+    };
+    execute(&code, &ctx, storage)
+}
+
+#[cfg(test)]
+fn output_word(outcome: &Outcome) -> Word {
+    Word::from_be_slice(&outcome.output)
+}
+
+#[test]
+fn test_arithmetic_contract() {
+    // (2 + 3) * 7 - 1，结果写到内存 0 处并返回 32 字节
+    let src = "PUSH1 0x01 PUSH1 0x07 PUSH1 0x03 PUSH1 0x02 ADD MUL SUB \
+               PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN";
+    let outcome = run(src, &[], &mut Storage::new());
+    assert_eq!(outcome.exit, Exit::Return);
+    assert_eq!(output_word(&outcome), Word::from_u64(34));
+
+    // NOTE 除以 0 在 EVM 里不会报错，结果为 0
+    let outcome = run(
+        "PUSH0 PUSH1 0x05 DIV PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN",
+        &[],
+        &mut Storage::new(),
+    );
+    assert_eq!(output_word(&outcome), Word::ZERO);
+
+    // -6 / 4 = -1（向 0 取整），SLT(-1, 0) = 1
+    let outcome = run(
+        "PUSH1 0x04 PUSH1 0x06 PUSH0 SUB SDIV PUSH0 SWAP1 SLT PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN",
+        &[],
+        &mut Storage::new(),
+    );
+    assert_eq!(output_word(&outcome), Word::ONE);
+}
+
+#[test]
+fn test_counter_contract_with_storage() {
+    // slot0 += calldata[0..32]，返回新值
+    let src = "PUSH0 CALLDATALOAD PUSH0 SLOAD ADD DUP1 PUSH0 SSTORE \
+               PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN";
+    let mut storage = Storage::new();
+    let arg = Word::from_u64(5).to_be_bytes();
+
+    let first = run(src, &arg, &mut storage);
+    assert_eq!(output_word(&first), Word::from_u64(5));
+    let second = run(src, &arg, &mut storage);
+    assert_eq!(output_word(&second), Word::from_u64(10));
+    assert_eq!(storage.get(&Word::ZERO), Some(&Word::from_u64(10)));
+
+    // NOTE 第一次是 0 -> 非 0，比第二次贵得多
+    assert!(first.gas_used > second.gas_used + 10000);
+}
+
+#[test]
+fn test_loop_with_jumps() {
+    // 计算 1 + 2 + ... + 10
+    //  0: PUSH1 0x0a                           i = 10
+    //  2: PUSH0                                sum = 0，栈顶是 sum
+    //  3: JUMPDEST                             loop:
+    //  4: DUP2 ISZERO PUSH1 0x14 JUMPI         if i == 0 goto end
+    //  9: DUP2 ADD                             sum += i
+    // 11: SWAP1 PUSH1 0x01 SWAP1 SUB SWAP1     i -= 1
+    // 17: PUSH1 0x03 JUMP                      goto loop
+    // 20: JUMPDEST                             end:
+    let src = "PUSH1 0x0a PUSH0 JUMPDEST DUP2 ISZERO PUSH1 0x14 JUMPI DUP2 ADD \
+               SWAP1 PUSH1 0x01 SWAP1 SUB SWAP1 PUSH1 0x03 JUMP \
+               JUMPDEST PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN";
+    let outcome = run(src, &[], &mut Storage::new());
+    assert_eq!(outcome.exit, Exit::Return);
+    assert_eq!(output_word(&outcome), Word::from_u64(55));
+}
+
+#[test]
+fn test_invalid_jump_and_errors() {
+    // NOTE 目标 0x04 处的 0x5b 是 PUSH1 的立即数，不是真正的 JUMPDEST
+    let code = [PUSH1, 0x04, JUMP, PUSH1, JUMPDEST];
+    let ctx = Context {
+        gas_limit: 1000,
+        ..Context::default()
+    };
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert!(matches!(
+        outcome.exit,
+        Exit::Error(VmError::InvalidJump { pc: 2, .. })
+    ));
+    assert_eq!(outcome.gas_used, 1000);
+
+    let outcome = run("ADD", &[], &mut Storage::new());
+    assert_eq!(outcome.exit, Exit::Error(VmError::StackUnderflow { pc: 0 }));
+
+    let outcome = execute(&[0x0c], &ctx, &mut Storage::new());
+    assert_eq!(
+        outcome.exit,
+        Exit::Error(VmError::InvalidOpcode {
+            pc: 0,
+            opcode: 0x0c
+        })
+    );
+
+    // 第 1025 次 PUSH0 让栈溢出
+    let ctx = Context {
+        gas_limit: 1_000_000,
+        ..Context::default()
+    };
+    let outcome = execute(&[PUSH0; 1025], &ctx, &mut Storage::new());
+    assert_eq!(
+        outcome.exit,
+        Exit::Error(VmError::StackOverflow { pc: 1024 })
+    );
+}
+
+#[test]
+fn test_gas_metering() {
+    let code = assemble("PUSH1 0x01 PUSH1 0x02 ADD").unwrap();
+    let mut ctx = Context {
+        gas_limit: 9,
+        ..Context::default()
+    };
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert_eq!(outcome.exit, Exit::Stop);
+    assert_eq!(outcome.gas_used, 9);
+
+    ctx.gas_limit = 8;
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert_eq!(outcome.exit, Exit::Error(VmError::OutOfGas { pc: 4 }));
+
+    // 内存扩展: 写到偏移 0x1000 需要 129 个字，3 * 129 + 129 * 129 / 512 = 419
+    let code = assemble("PUSH1 0x01 PUSH2 0x1000 MSTORE").unwrap();
+    ctx.gas_limit = 1_000_000;
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert_eq!(outcome.gas_used, 3 + 3 + 3 + 419);
+
+    // 写入一个巨大的偏移直接耗尽 gas，而不是去分配内存
+    let code = assemble("PUSH1 0x01 PUSH32 0xffffffffff MSTORE").unwrap();
+    let outcome = execute(&code, &ctx, &mut Storage::new());
+    assert!(matches!(
+        outcome.exit,
+        Exit::Error(VmError::OutOfGas { .. })
+    ));
+}
+
+#[test]
+fn test_revert_rolls_back_storage() {
+    let mut storage = Storage::new();
+    storage.insert(Word::ONE, Word::from_u64(7));
+
+    // 先写存储，再 REVERT 并返回原因
+    let src = "PUSH1 0x2a PUSH1 0x01 SSTORE PUSH1 0xee PUSH0 MSTORE8 PUSH1 0x01 PUSH0 REVERT";
+    let outcome = run(src, &[], &mut storage);
+    assert_eq!(outcome.exit, Exit::Revert);
+    assert_eq!(outcome.output, vec![0xee]);
+    assert!(!outcome.is_success());
+    assert!(outcome.gas_used < 1_000_000);
+    assert_eq!(storage.get(&Word::ONE), Some(&Word::from_u64(7)));
+
+    // 异常终止同样回滚
+    let outcome = run("PUSH1 0x2a PUSH1 0x01 SSTORE INVALID", &[], &mut storage);
+    assert!(matches!(outcome.exit, Exit::Error(_)));
+    assert_eq!(storage.get(&Word::ONE), Some(&Word::from_u64(7)));
+}
+
+#[test]
+fn test_keccak_and_calldata() {
+    // 对 calldata 整体求 keccak256
+    let src = "CALLDATASIZE PUSH0 PUSH0 CALLDATACOPY CALLDATASIZE PUSH0 KECCAK256 \
+               PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN";
+    let outcome = run(src, b"abc", &mut Storage::new());
+    assert_eq!(outcome.output, keccak256(b"abc").to_vec());
+
+    // CALLER 由执行上下文提供
+    let outcome = run(
+        "CALLER PUSH0 MSTORE PUSH1 0x20 PUSH0 RETURN",
+        &[],
+        &mut Storage::new(),
+    );
+    assert_eq!(output_word(&outcome), Word::from_u64(0xca11e5));
+}
@@ -0,0 +1,6 @@
+/*
+ * EVM 的字长是 256 位，栈上的每个元素、存储的每个键和值都是一个 256 位的 "字"（Word）。
+ * EVM 的算术全部是模 2^256 的，解释器里只用 wrapping 语义的运算。
+ */
+
+pub type Word = crate::bigint::uint::U256;
@@ -0,0 +1,420 @@
+#![allow(dead_code)]
+
+/*
+ * 恒定乘积做市商（Uniswap V2 风格的 AMM）
+ *      池子里有两种代币，储备量 x 和 y，交易前后保持 x * y = k 不变（扣掉手续费后的部分）。
+ *      用 dx 个 A 换 B 时，能换出的数量 dy 满足 (x + dx) * (y - dy) = x * y，即
+ *          dy = y * dx / (x + dx)
+ *      价格由储备量的比值决定，交易量相对储备越大，成交价越差（价格冲击），所以需要滑点保护。
+ *
+ * 手续费留在池子里，k 只增不减，流动性提供者（LP）持有的份额因此升值。
+ * 份额记在各个提供者名下，只能赎回自己的份额。
+ * 所有舍入都偏向池子: 换出的数量向下取整，需要存入的数量向上取整。
+ */
+
+use super::fixed::{isqrt, mul_div, Fixed, Rounding};
+use crate::bigint::uint::U256;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// 手续费的基数，30 表示 0.3%
+pub const BPS: u128 = 10_000;
+/// 首次添加流动性时永久锁定的份额，防止有人把份额价格抬到极高再操纵
+pub const MINIMUM_LIQUIDITY: u128 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    A,
+    B,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmmError {
+    ZeroAmount,
+    InsufficientLiquidity,
+    /// 成交结果比用户能接受的最差结果还差
+    SlippageExceeded {
+        min: u128,
+        actual: u128,
+    },
+    InsufficientShares {
+        owned: u128,
+        requested: u128,
+    },
+    Overflow,
+}
+
+impl fmt::Display for AmmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmmError::ZeroAmount => write!(f, "amount must be positive"),
+            AmmError::InsufficientLiquidity => write!(f, "insufficient liquidity"),
+            AmmError::SlippageExceeded { min, actual } => {
+                write!(
+                    f,
+                    "slippage exceeded: wanted at least {}, got {}",
+                    min, actual
+                )
+            }
+            AmmError::InsufficientShares { owned, requested } => {
+                write!(f, "owns {} shares, requested {}", owned, requested)
+            }
+            AmmError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for AmmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pool {
+    reserve_a: u128,
+    reserve_b: u128,
+    /// 所有提供者的份额之和，加上永久锁定的 MINIMUM_LIQUIDITY
+    total_shares: u128,
+    shares: BTreeMap<String, u128>,
+    fee_bps: u128,
+}
+
+impl Pool {
+    pub fn new(fee_bps: u128) -> Pool {
+        assert!(fee_bps < BPS, "fee must be below 100%");
+        Pool {
+            reserve_a: 0,
+            reserve_b: 0,
+            total_shares: 0,
+            shares: BTreeMap::new(),
+            fee_bps,
+        }
+    }
+
+    pub fn reserves(&self) -> (u128, u128) {
+        (self.reserve_a, self.reserve_b)
+    }
+
+    pub fn total_shares(&self) -> u128 {
+        self.total_shares
+    }
+
+    pub fn shares_of(&self, provider: &str) -> u128 {
+        self.shares.get(provider).copied().unwrap_or(0)
+    }
+
+    /// x * y，可能超过 u128
+    pub fn k(&self) -> U256 {
+        U256::from(self.reserve_a).wrapping_mul(U256::from(self.reserve_b))
+    }
+
+    /// 边际价格: 1 个 A 值多少个 B
+    pub fn spot_price(&self) -> Option<Fixed> {
+        Fixed::from_ratio(self.reserve_b, self.reserve_a)
+    }
+
+    fn reserves_for(&self, input: Token) -> (u128, u128) {
+        match input {
+            Token::A => (self.reserve_a, self.reserve_b),
+            Token::B => (self.reserve_b, self.reserve_a),
+        }
+    }
+
+    /// 扣除手续费后按恒定乘积计算能换出的数量
+    pub fn quote(&self, input: Token, amount_in: u128) -> Result<u128, AmmError> {
+        if amount_in == 0 {
+            return Err(AmmError::ZeroAmount);
+        }
+        let (reserve_in, reserve_out) = self.reserves_for(input);
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(AmmError::InsufficientLiquidity);
+        }
+        let in_with_fee = mul_div(amount_in, BPS - self.fee_bps, BPS, Rounding::Down)
+            .ok_or(AmmError::Overflow)?;
+        let denom = reserve_in
+            .checked_add(in_with_fee)
+            .ok_or(AmmError::Overflow)?;
+        mul_div(reserve_out, in_with_fee, denom, Rounding::Down).ok_or(AmmError::Overflow)
+    }
+
+    /// 卖出 amount_in，至少换回 min_out，否则整笔交易失败
+    pub fn swap(&mut self, input: Token, amount_in: u128, min_out: u128) -> Result<u128, AmmError> {
+        let out = self.quote(input, amount_in)?;
+        if out == 0 {
+            return Err(AmmError::InsufficientLiquidity);
+        }
+        if out < min_out {
+            return Err(AmmError::SlippageExceeded {
+                min: min_out,
+                actual: out,
+            });
+        }
+        let (reserve_in, reserve_out) = match input {
+            Token::A => (&mut self.reserve_a, &mut self.reserve_b),
+            Token::B => (&mut self.reserve_b, &mut self.reserve_a),
+        };
+        // NOTE quote 只检查了扣费后的数量，完整的 amount_in 加进储备时仍可能溢出
+        *reserve_in = reserve_in
+            .checked_add(amount_in)
+            .ok_or(AmmError::Overflow)?;
+        *reserve_out -= out;
+        Ok(out)
+    }
+
+    /// provider 按当前比例存入两种代币，返回 (获得的份额, 实际用掉的 A, 实际用掉的 B)。
+    /// 多出来的那一种代币不会被拿走
+    pub fn add_liquidity(
+        &mut self,
+        provider: &str,
+        max_a: u128,
+        max_b: u128,
+        min_shares: u128,
+    ) -> Result<(u128, u128, u128), AmmError> {
+        if max_a == 0 || max_b == 0 {
+            return Err(AmmError::ZeroAmount);
+        }
+        let (shares, used_a, used_b) = if self.total_shares == 0 {
+            let shares = isqrt(U256::from(max_a).wrapping_mul(U256::from(max_b)));
+            let shares = shares
+                .checked_sub(MINIMUM_LIQUIDITY)
+                .filter(|s| *s > 0)
+                .ok_or(AmmError::InsufficientLiquidity)?;
+            (shares, max_a, max_b)
+        } else {
+            let (ra, rb, total) = (self.reserve_a, self.reserve_b, self.total_shares);
+            let optimal_b = mul_div(max_a, rb, ra, Rounding::Up).ok_or(AmmError::Overflow)?;
+            let (used_a, used_b) = if optimal_b <= max_b {
+                (max_a, optimal_b)
+            } else {
+                let optimal_a = mul_div(max_b, ra, rb, Rounding::Up).ok_or(AmmError::Overflow)?;
+                (optimal_a.min(max_a), max_b)
+            };
+            let by_a = mul_div(used_a, total, ra, Rounding::Down).ok_or(AmmError::Overflow)?;
+            let by_b = mul_div(used_b, total, rb, Rounding::Down).ok_or(AmmError::Overflow)?;
+            (by_a.min(by_b), used_a, used_b)
+        };
+        if shares < min_shares || shares == 0 {
+            return Err(AmmError::SlippageExceeded {
+                min: min_shares,
+                actual: shares,
+            });
+        }
+        // NOTE 先把新的储备和份额全部算出来，任何一项溢出都不修改池子，避免只更新了一半
+        let overflow = || AmmError::Overflow;
+        let reserve_a = self.reserve_a.checked_add(used_a).ok_or_else(overflow)?;
+        let reserve_b = self.reserve_b.checked_add(used_b).ok_or_else(overflow)?;
+        // 第一次注入时先锁定 MINIMUM_LIQUIDITY
+        let base = if self.total_shares == 0 {
+            MINIMUM_LIQUIDITY
+        } else {
+            self.total_shares
+        };
+        let total_shares = base.checked_add(shares).ok_or_else(overflow)?;
+        let owned = self
+            .shares_of(provider)
+            .checked_add(shares)
+            .ok_or_else(overflow)?;
+        self.reserve_a = reserve_a;
+        self.reserve_b = reserve_b;
+        self.total_shares = total_shares;
+        self.shares.insert(provider.to_string(), owned);
+        Ok((shares, used_a, used_b))
+    }
+
+    /// provider 赎回自己名下的份额，按比例取回两种代币（向下取整）
+    pub fn remove_liquidity(
+        &mut self,
+        provider: &str,
+        shares: u128,
+        min_a: u128,
+        min_b: u128,
+    ) -> Result<(u128, u128), AmmError> {
+        if shares == 0 {
+            return Err(AmmError::ZeroAmount);
+        }
+        // NOTE 锁定的最小流动性不记在任何人名下，所以永远不能被赎回
+        let owned = self.shares_of(provider);
+        if shares > owned {
+            return Err(AmmError::InsufficientShares {
+                owned,
+                requested: shares,
+            });
+        }
+        let total = self.total_shares;
+        let a = mul_div(shares, self.reserve_a, total, Rounding::Down).ok_or(AmmError::Overflow)?;
+        let b = mul_div(shares, self.reserve_b, total, Rounding::Down).ok_or(AmmError::Overflow)?;
+        if a < min_a {
+            return Err(AmmError::SlippageExceeded {
+                min: min_a,
+                actual: a,
+            });
+        }
+        if b < min_b {
+            return Err(AmmError::SlippageExceeded {
+                min: min_b,
+                actual: b,
+            });
+        }
+        self.reserve_a -= a;
+        self.reserve_b -= b;
+        self.total_shares -= shares;
+        if owned == shares {
+            self.shares.remove(provider);
+        } else {
+            self.shares.insert(provider.to_string(), owned - shares);
+        }
+        Ok((a, b))
+    }
+}
+
+#[test]
+fn test_swap_math() {
+    let mut pool = Pool::new(30);
+    let (shares, _, _) = pool
+        .add_liquidity("alice", 1_000_000, 4_000_000, 0)
+        .unwrap();
+    // sqrt(1e6 * 4e6) = 2e6，减去锁定的 1000
+    assert_eq!(shares, 2_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(pool.spot_price(), Fixed::from_int(4));
+
+    // 997 * 4e6 / (1e6 + 997) = 3984.02...
+    assert_eq!(pool.quote(Token::A, 1000), Ok(3984));
+    assert_eq!(
+        pool.swap(Token::A, 1000, 3985),
+        Err(AmmError::SlippageExceeded {
+            min: 3985,
+            actual: 3984
+        })
+    );
+    let k = pool.k();
+    assert_eq!(pool.swap(Token::A, 1000, 3984), Ok(3984));
+    assert_eq!(pool.reserves(), (1_001_000, 3_996_016));
+    assert!(pool.k() > k);
+
+    // 大额交易的价格冲击: 卖出等于储备量的 A 只能换回不到一半的 B
+    let out = pool.quote(Token::A, 1_001_000).unwrap();
+    assert!(out < 3_996_016 / 2);
+    assert_eq!(pool.quote(Token::B, 0), Err(AmmError::ZeroAmount));
+    assert_eq!(
+        Pool::new(30).quote(Token::A, 1),
+        Err(AmmError::InsufficientLiquidity)
+    );
+}
+
+#[test]
+fn test_liquidity() {
+    let mut pool = Pool::new(30);
+    let (first, _, _) = pool.add_liquidity("alice", 10_000, 10_000, 0).unwrap();
+    assert_eq!(first, 9_000);
+
+    // 比例不对时只用掉需要的部分
+    let (shares, used_a, used_b) = pool.add_liquidity("bob", 5_000, 9_999, 0).unwrap();
+    assert_eq!((shares, used_a, used_b), (5_000, 5_000, 5_000));
+    assert_eq!(pool.total_shares(), 15_000);
+    assert_eq!(
+        (pool.shares_of("alice"), pool.shares_of("bob")),
+        (9_000, 5_000)
+    );
+
+    pool.swap(Token::B, 3_000, 0).unwrap();
+    // 赎回时拿到的总价值比存入时多（手续费收入），A 少了 B 多了
+    let (a, b) = pool.remove_liquidity("bob", 5_000, 0, 0).unwrap();
+    assert!(a < 5_000 && b > 5_000);
+    assert!(a * b > 5_000 * 5_000);
+
+    // 份额记在各自名下，bob 不能赎回 alice 的份额
+    assert_eq!(
+        pool.remove_liquidity("bob", 1, 0, 0),
+        Err(AmmError::InsufficientShares {
+            owned: 0,
+            requested: 1
+        })
+    );
+    assert_eq!(
+        pool.remove_liquidity("alice", 9_001, 0, 0),
+        Err(AmmError::InsufficientShares {
+            owned: 9_000,
+            requested: 9_001
+        })
+    );
+    assert!(matches!(
+        pool.remove_liquidity("alice", 9_000, 100_000, 0),
+        Err(AmmError::SlippageExceeded { .. })
+    ));
+    pool.remove_liquidity("alice", 9_000, 0, 0).unwrap();
+    // 锁定的份额让池子永远不会被完全清空
+    let (ra, rb) = pool.reserves();
+    assert!(ra > 0 && rb > 0);
+    assert_eq!(pool.total_shares(), MINIMUM_LIQUIDITY);
+    assert_eq!(pool.shares_of("alice"), 0);
+
+    // B 的储备加上去会溢出，A 的储备也不能被改动
+    let mut pool = Pool::new(30);
+    pool.add_liquidity("alice", 10_000, u128::MAX - 5, 0)
+        .unwrap();
+    let before = pool.clone();
+    assert_eq!(
+        pool.add_liquidity("alice", 10_000, u128::MAX, 0),
+        Err(AmmError::Overflow)
+    );
+    assert_eq!(pool, before);
+}
+
+#[test]
+fn test_randomized_invariants() {
+    use crate::testing::XorShift;
+
+    let providers = ["alice", "bob", "carol"];
+    let mut rng = XorShift::new(0x5eed);
+    let mut pool = Pool::new(30);
+    pool.add_liquidity("alice", 1_000_000_000, 2_000_000_000, 0)
+        .unwrap();
+
+    for _ in 0..2_000 {
+        let k_before = pool.k();
+        let (ra, rb) = pool.reserves();
+        let total = pool.total_shares();
+        let provider = providers[rng.below(providers.len() as u64) as usize];
+        let owned = pool.shares_of(provider);
+        match rng.below(4) {
+            0 | 1 => {
+                let input = if rng.below(2) == 0 {
+                    Token::A
+                } else {
+                    Token::B
+                };
+                let amount = rng.below(ra.max(rb) as u64 / 10) as u128 + 1;
+                if pool.swap(input, amount, 0).is_ok() {
+                    // 有手续费时 k 严格不减
+                    assert!(pool.k() >= k_before);
+                }
+            }
+            2 => {
+                let a = rng.below(ra as u64 / 5) as u128 + 1;
+                let b = rng.below(rb as u64 / 5) as u128 + 1;
+                if let Ok((shares, used_a, used_b)) = pool.add_liquidity(provider, a, b, 0) {
+                    assert_eq!(pool.shares_of(provider), owned + shares);
+                    assert!(used_a <= a && used_b <= b);
+                }
+            }
+            _ => {
+                // 偶尔多要一份，超过自己名下的份额必须失败
+                let shares = rng.below(owned as u64 / 2 + 2) as u128;
+                match pool.remove_liquidity(provider, shares, 0, 0) {
+                    Ok((a, b)) => {
+                        assert!(shares <= owned);
+                        assert_eq!(pool.shares_of(provider), owned - shares);
+                        assert!(a <= ra && b <= rb);
+                    }
+                    Err(_) => assert_eq!(pool.shares_of(provider), owned),
+                }
+            }
+        }
+        // 每份额对应的 sqrt(k) 不减: k_after * total_before^2 >= k_before * total_after^2
+        let total_after = pool.total_shares();
+        let lhs = pool.k().wrapping_mul(U256::from(total * total));
+        let rhs = k_before.wrapping_mul(U256::from(total_after * total_after));
+        assert!(lhs >= rhs);
+        let owned: u128 = providers.iter().map(|p| pool.shares_of(p)).sum();
+        assert_eq!(pool.total_shares(), owned + MINIMUM_LIQUIDITY);
+        let (ra, rb) = pool.reserves();
+        assert!(ra > 0 && rb > 0);
+    }
+}
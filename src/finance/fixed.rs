@@ -0,0 +1,231 @@
+#![allow(dead_code)]
+
+/*
+ * 定点数
+ *      金额绝对不能用浮点数: 0.1 + 0.2 != 0.3，而且不同机器上的舍入结果还可能不同，链上的所有节点必须算出完全相同的结果。
+ *      这里用 u128 存储 "真实值 * 10^18"（以太坊里叫 WAD），18 位小数对价格和利率都足够了。
+ *
+ * 舍入方向
+ *      每一次乘除都要决定向上还是向下取整，原则是 "对协议有利":
+ *      付给用户的向下取整，向用户收取的向上取整，这样舍入误差永远不会让协议亏钱。
+ *
+ * 两个 u128 相乘可能超过 u128，中间结果用 bigint 模块的 U256 计算。
+ * 需要负数时（例如计算盈亏）转换成 bigint::decimal::Decimal，两者都是 18 位小数。
+ */
+
+use crate::bigint::decimal::Decimal;
+use crate::bigint::int::I256;
+use crate::bigint::uint::U256;
+use std::fmt;
+
+pub const SCALE: u128 = 1_000_000_000_000_000_000;
+const DECIMALS: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// a * b / denom，中间结果不会溢出；最终结果超过 u128 或除数为 0 时返回 None
+pub fn mul_div(a: u128, b: u128, denom: u128, rounding: Rounding) -> Option<u128> {
+    if denom == 0 {
+        return None;
+    }
+    let product = U256::from(a).wrapping_mul(U256::from(b));
+    let (q, r) = product.div_rem(U256::from(denom));
+    let q = q.to_u128()?;
+    if rounding == Rounding::Up && !r.is_zero() {
+        q.checked_add(1)
+    } else {
+        Some(q)
+    }
+}
+
+/// 256 位整数的平方根（向下取整），结果一定能放进 u128。牛顿迭代
+pub fn isqrt(n: U256) -> u128 {
+    if n.is_zero() {
+        return 0;
+    }
+    // 初值取 2^ceil(bits/2)，一定不小于真实的平方根，之后单调递减
+    let mut x = U256::ONE.shl(n.bits().div_ceil(2));
+    loop {
+        let y = x.wrapping_add(n.div_rem(x).0).shr(1);
+        if y >= x {
+            return x.to_u128().unwrap();
+        }
+        x = y;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(u128);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(SCALE);
+
+    pub const fn from_raw(raw: u128) -> Fixed {
+        Fixed(raw)
+    }
+
+    pub const fn raw(self) -> u128 {
+        self.0
+    }
+
+    pub fn from_int(n: u128) -> Option<Fixed> {
+        n.checked_mul(SCALE).map(Fixed)
+    }
+
+    /// num / den，向下取整
+    pub fn from_ratio(num: u128, den: u128) -> Option<Fixed> {
+        mul_div(num, SCALE, den, Rounding::Down).map(Fixed)
+    }
+
+    /// 解析 "1.05" 这样的十进制字符串，小数位超过 18 位视为错误而不是悄悄截断
+    pub fn parse(s: &str) -> Option<Fixed> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() || frac.len() > DECIMALS {
+            return None;
+        }
+        if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let int: u128 = int.parse().ok()?;
+        let frac: u128 = format!("{:0<width$}", frac, width = DECIMALS)
+            .parse()
+            .ok()?;
+        int.checked_mul(SCALE)?.checked_add(frac).map(Fixed)
+    }
+
+    pub fn checked_add(self, rhs: Fixed) -> Option<Fixed> {
+        self.0.checked_add(rhs.0).map(Fixed)
+    }
+
+    pub fn checked_sub(self, rhs: Fixed) -> Option<Fixed> {
+        self.0.checked_sub(rhs.0).map(Fixed)
+    }
+
+    pub fn mul(self, rhs: Fixed, rounding: Rounding) -> Option<Fixed> {
+        mul_div(self.0, rhs.0, SCALE, rounding).map(Fixed)
+    }
+
+    pub fn div(self, rhs: Fixed, rounding: Rounding) -> Option<Fixed> {
+        mul_div(self.0, SCALE, rhs.0, rounding).map(Fixed)
+    }
+
+    /// 整数金额乘以定点数，例如 "数量 * 价格"，结果是整数金额
+    pub fn apply(self, amount: u128, rounding: Rounding) -> Option<u128> {
+        mul_div(amount, self.0, SCALE, rounding)
+    }
+
+    /// 整数金额除以定点数，例如 "价值 / 价格"
+    pub fn apply_inverse(self, amount: u128, rounding: Rounding) -> Option<u128> {
+        mul_div(amount, SCALE, self.0, rounding)
+    }
+
+    /// 整数次幂，用于按区块数复利。每一步都向上取整，对借款人计息时偏向协议
+    pub fn pow(self, mut exp: u64, rounding: Rounding) -> Option<Fixed> {
+        let mut base = self;
+        let mut acc = Fixed::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = acc.mul(base, rounding)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(base, rounding)?;
+            }
+        }
+        Some(acc)
+    }
+}
+
+impl fmt::Display for Fixed {
+    /// 去掉小数部分末尾的 0，整数不带小数点
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let int = self.0 / SCALE;
+        let frac = self.0 % SCALE;
+        if frac == 0 {
+            return write!(f, "{}", int);
+        }
+        let digits = format!("{:0width$}", frac, width = DECIMALS);
+        write!(f, "{}.{}", int, digits.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self)
+    }
+}
+
+/// u128 一定小于 2^255，转换成 I256 不会变成负数
+impl From<Fixed> for Decimal {
+    fn from(f: Fixed) -> Decimal {
+        Decimal::from_raw(I256::from_bits(U256::from(f.0)))
+    }
+}
+
+#[test]
+fn test_fixed_point() {
+    let a = Fixed::parse("0.1").unwrap();
+    let b = Fixed::parse("0.2").unwrap();
+    // NOTE 浮点数里 0.1 + 0.2 == 0.30000000000000004
+    assert_eq!(a.checked_add(b), Fixed::parse("0.3"));
+    assert_eq!(Fixed::parse("1.5").unwrap().to_string(), "1.5");
+    assert_eq!(Fixed::from_int(42).unwrap().to_string(), "42");
+    assert_eq!(Fixed::from_raw(1).to_string(), "0.000000000000000001");
+    assert_eq!(Fixed::parse("1.0000000000000000001"), None);
+    assert_eq!(Fixed::parse("-1"), None);
+    assert_eq!(Fixed::parse(".5"), None);
+
+    // 1/3 向下和向上取整差一个最小单位
+    let third_down = Fixed::ONE
+        .div(Fixed::from_int(3).unwrap(), Rounding::Down)
+        .unwrap();
+    let third_up = Fixed::ONE
+        .div(Fixed::from_int(3).unwrap(), Rounding::Up)
+        .unwrap();
+    assert_eq!(third_up.raw() - third_down.raw(), 1);
+    assert_eq!(third_down.to_string(), "0.333333333333333333");
+
+    // 中间结果超过 u128 也能算对
+    let big = u128::MAX / 2;
+    assert_eq!(mul_div(big, 6, 3, Rounding::Down), Some(big * 2));
+    assert_eq!(mul_div(u128::MAX, 2, 1, Rounding::Down), None);
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    assert_eq!(Fixed::from_ratio(3, 4), Fixed::parse("0.75"));
+    assert_eq!(
+        Fixed::parse("2").unwrap().apply(7, Rounding::Down),
+        Some(14)
+    );
+    assert_eq!(
+        Fixed::parse("1.5").unwrap().apply_inverse(10, Rounding::Up),
+        Some(7)
+    );
+
+    // 1.01^100 = 2.70481382942152609...
+    let compound = Fixed::parse("1.01")
+        .unwrap()
+        .pow(100, Rounding::Down)
+        .unwrap();
+    assert!(compound.to_string().starts_with("2.704813829421526"));
+
+    assert_eq!(isqrt(U256::from_u64(0)), 0);
+    assert_eq!(isqrt(U256::from_u64(15)), 3);
+    assert_eq!(isqrt(U256::from_u64(16)), 4);
+    assert_eq!(isqrt(U256::MAX), u128::MAX);
+    let square = U256::from(u128::MAX).wrapping_mul(U256::from(u128::MAX));
+    assert_eq!(isqrt(square), u128::MAX);
+
+    // 换成 Decimal 之后可以算出负的差值
+    let pnl = Decimal::from(Fixed::parse("1.25").unwrap())
+        .checked_sub(Decimal::from(Fixed::parse("2").unwrap()))
+        .unwrap();
+    assert_eq!(pnl.to_string(), "-0.75");
+    assert_eq!(
+        Decimal::from(Fixed::from_raw(u128::MAX)).raw(),
+        I256::from_bits(U256::from(u128::MAX))
+    );
+}
@@ -0,0 +1,525 @@
+#![allow(dead_code)]
+
+/*
+ * 超额抵押借贷池
+ *      用户存入抵押品（例如 ETH），借出另一种资产（例如稳定币），借款价值不能超过抵押品价值的一定比例（抵押率）。
+ *      抵押品价格下跌后，如果 "抵押品价值 * 清算阈值 < 债务"，任何人都可以替他还一部分债，
+ *      并以低于市价的折扣（清算奖励）拿走对应的抵押品。
+ *
+ * 利息用 "借款指数" 计算: 指数从 1 开始，每个区块乘以 (1 + 利率)。
+ *      用户借款时记录的是 "缩放后的债务" = 借款额 / 当前指数，
+ *      实际债务 = 缩放后的债务 * 当前指数。这样计息时只需要更新一个数，不用遍历所有用户。
+ */
+
+use super::fixed::{Fixed, Rounding};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LendingError {
+    ZeroAmount,
+    UnknownAccount,
+    /// 操作之后债务会超过允许的借款额度
+    InsufficientCollateral {
+        limit: u128,
+        debt: u128,
+    },
+    InsufficientLiquidity {
+        available: u128,
+        requested: u128,
+    },
+    /// 仓位还是健康的，不能清算
+    PositionHealthy,
+    /// 单次清算最多偿还一半债务
+    ExceedsCloseFactor {
+        max: u128,
+        requested: u128,
+    },
+    Overflow,
+}
+
+impl fmt::Display for LendingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LendingError::ZeroAmount => write!(f, "amount must be positive"),
+            LendingError::UnknownAccount => write!(f, "unknown account"),
+            LendingError::InsufficientCollateral { limit, debt } => {
+                write!(f, "debt {} would exceed borrow limit {}", debt, limit)
+            }
+            LendingError::InsufficientLiquidity {
+                available,
+                requested,
+            } => write!(
+                f,
+                "requested {} but only {} available",
+                requested, available
+            ),
+            LendingError::PositionHealthy => write!(f, "position is not liquidatable"),
+            LendingError::ExceedsCloseFactor { max, requested } => {
+                write!(f, "can repay at most {}, requested {}", max, requested)
+            }
+            LendingError::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for LendingError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    /// 借款额度 = 抵押品价值 * collateral_factor
+    pub collateral_factor: Fixed,
+    /// 债务超过 抵押品价值 * liquidation_threshold 时可以被清算，必须大于 collateral_factor
+    pub liquidation_threshold: Fixed,
+    /// 清算人用 1 单位债务换到 liquidation_bonus 单位价值的抵押品
+    pub liquidation_bonus: Fixed,
+    pub rate_per_block: Fixed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    pub collateral: u128,
+    scaled_debt: u128,
+}
+
+#[derive(Debug, Clone)]
+pub struct LendingPool {
+    params: Params,
+    /// 1 单位抵押品值多少单位借款资产，由预言机提供
+    price: Fixed,
+    borrow_index: Fixed,
+    /// 池子里可以借出的资产
+    cash: u128,
+    total_scaled_debt: u128,
+    positions: BTreeMap<String, Position>,
+}
+
+impl LendingPool {
+    pub fn new(params: Params, price: Fixed) -> LendingPool {
+        assert!(params.collateral_factor < params.liquidation_threshold);
+        assert!(params.liquidation_threshold <= Fixed::ONE);
+        LendingPool {
+            params,
+            price,
+            borrow_index: Fixed::ONE,
+            cash: 0,
+            total_scaled_debt: 0,
+            positions: BTreeMap::new(),
+        }
+    }
+
+    pub fn cash(&self) -> u128 {
+        self.cash
+    }
+
+    pub fn set_price(&mut self, price: Fixed) {
+        self.price = price;
+    }
+
+    pub fn position(&self, user: &str) -> Option<&Position> {
+        self.positions.get(user)
+    }
+
+    // NOTE 下面几个查询在结果超出 u128 时返回 Overflow，而不是停在 u128::MAX:
+    // 一个饱和的数字看起来和正常的数字没有区别，拿去比较额度、计算健康因子都会得到错误的结论
+
+    /// 当前债务，向上取整
+    pub fn debt_of(&self, user: &str) -> Result<u128, LendingError> {
+        let scaled = self.positions.get(user).map(|p| p.scaled_debt).unwrap_or(0);
+        self.borrow_index
+            .apply(scaled, Rounding::Up)
+            .ok_or(LendingError::Overflow)
+    }
+
+    pub fn total_debt(&self) -> Result<u128, LendingError> {
+        self.borrow_index
+            .apply(self.total_scaled_debt, Rounding::Up)
+            .ok_or(LendingError::Overflow)
+    }
+
+    /// 出借人的总资产 = 池中现金 + 所有借款人的债务
+    pub fn total_assets(&self) -> Result<u128, LendingError> {
+        self.cash
+            .checked_add(self.total_debt()?)
+            .ok_or(LendingError::Overflow)
+    }
+
+    fn collateral_value(&self, collateral: u128, factor: Fixed) -> Result<u128, LendingError> {
+        self.price
+            .apply(collateral, Rounding::Down)
+            .and_then(|value| factor.apply(value, Rounding::Down))
+            .ok_or(LendingError::Overflow)
+    }
+
+    /// 健康因子 = 抵押品价值 * 清算阈值 / 债务，小于 1 可以被清算；没有债务时返回 None
+    pub fn health_factor(&self, user: &str) -> Result<Option<Fixed>, LendingError> {
+        let debt = self.debt_of(user)?;
+        let Some(position) = self.positions.get(user).filter(|_| debt > 0) else {
+            return Ok(None);
+        };
+        let value =
+            self.collateral_value(position.collateral, self.params.liquidation_threshold)?;
+        Fixed::from_ratio(value, debt)
+            .map(Some)
+            .ok_or(LendingError::Overflow)
+    }
+
+    fn check_limit(&self, collateral: u128, debt: u128) -> Result<(), LendingError> {
+        let limit = self.collateral_value(collateral, self.params.collateral_factor)?;
+        if debt > limit {
+            return Err(LendingError::InsufficientCollateral { limit, debt });
+        }
+        Ok(())
+    }
+
+    /// 计息 blocks 个区块
+    pub fn accrue(&mut self, blocks: u64) -> Result<(), LendingError> {
+        let growth = Fixed::ONE
+            .checked_add(self.params.rate_per_block)
+            .and_then(|r| r.pow(blocks, Rounding::Up))
+            .ok_or(LendingError::Overflow)?;
+        self.borrow_index = self
+            .borrow_index
+            .mul(growth, Rounding::Up)
+            .ok_or(LendingError::Overflow)?;
+        Ok(())
+    }
+
+    /// 出借人存入可借出的资产
+    pub fn supply(&mut self, amount: u128) -> Result<(), LendingError> {
+        self.cash = self
+            .cash
+            .checked_add(amount)
+            .ok_or(LendingError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn deposit_collateral(&mut self, user: &str, amount: u128) -> Result<(), LendingError> {
+        if amount == 0 {
+            return Err(LendingError::ZeroAmount);
+        }
+        let position = self.positions.entry(user.to_string()).or_default();
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(LendingError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn withdraw_collateral(&mut self, user: &str, amount: u128) -> Result<(), LendingError> {
+        let position = self
+            .positions
+            .get(user)
+            .ok_or(LendingError::UnknownAccount)?;
+        let remaining =
+            position
+                .collateral
+                .checked_sub(amount)
+                .ok_or(LendingError::InsufficientLiquidity {
+                    available: position.collateral,
+                    requested: amount,
+                })?;
+        self.check_limit(remaining, self.debt_of(user)?)?;
+        self.positions.get_mut(user).unwrap().collateral = remaining;
+        Ok(())
+    }
+
+    pub fn borrow(&mut self, user: &str, amount: u128) -> Result<(), LendingError> {
+        if amount == 0 {
+            return Err(LendingError::ZeroAmount);
+        }
+        let position = self
+            .positions
+            .get(user)
+            .ok_or(LendingError::UnknownAccount)?;
+        if amount > self.cash {
+            return Err(LendingError::InsufficientLiquidity {
+                available: self.cash,
+                requested: amount,
+            });
+        }
+        // 缩放后的债务向上取整: 借款人多欠一点，不会少欠
+        let scaled = self
+            .borrow_index
+            .apply_inverse(amount, Rounding::Up)
+            .ok_or(LendingError::Overflow)?;
+        let new_scaled = position
+            .scaled_debt
+            .checked_add(scaled)
+            .ok_or(LendingError::Overflow)?;
+        let total_scaled_debt = self
+            .total_scaled_debt
+            .checked_add(scaled)
+            .ok_or(LendingError::Overflow)?;
+        let debt = self
+            .borrow_index
+            .apply(new_scaled, Rounding::Up)
+            .ok_or(LendingError::Overflow)?;
+        self.check_limit(position.collateral, debt)?;
+
+        self.positions.get_mut(user).unwrap().scaled_debt = new_scaled;
+        self.total_scaled_debt = total_scaled_debt;
+        self.cash -= amount;
+        Ok(())
+    }
+
+    /// 还款，超出债务的部分不收。返回实际还款额
+    pub fn repay(&mut self, user: &str, amount: u128) -> Result<u128, LendingError> {
+        if amount == 0 {
+            return Err(LendingError::ZeroAmount);
+        }
+        let debt = self.debt_of(user)?;
+        let paid = amount.min(debt);
+        self.reduce_debt(user, paid, debt)?;
+        Ok(paid)
+    }
+
+    fn reduce_debt(&mut self, user: &str, paid: u128, debt: u128) -> Result<(), LendingError> {
+        let cash = self.cash.checked_add(paid).ok_or(LendingError::Overflow)?;
+        let position = self
+            .positions
+            .get_mut(user)
+            .ok_or(LendingError::UnknownAccount)?;
+        // 还清时直接归零，否则缩放后的债务向下取整地减少，不让舍入误差抵掉真实的债务
+        let scaled = if paid == debt {
+            position.scaled_debt
+        } else {
+            self.borrow_index
+                .apply_inverse(paid, Rounding::Down)
+                .ok_or(LendingError::Overflow)?
+                .min(position.scaled_debt)
+        };
+        position.scaled_debt -= scaled;
+        self.total_scaled_debt -= scaled;
+        self.cash = cash;
+        Ok(())
+    }
+
+    /// 清算: 替 user 偿还 repay 的债务，返回清算人得到的抵押品数量
+    pub fn liquidate(&mut self, user: &str, repay: u128) -> Result<u128, LendingError> {
+        if repay == 0 {
+            return Err(LendingError::ZeroAmount);
+        }
+        match self.health_factor(user)? {
+            Some(hf) if hf < Fixed::ONE => {}
+            _ => return Err(LendingError::PositionHealthy),
+        }
+        let debt = self.debt_of(user)?;
+        let max = debt.div_ceil(2);
+        if repay > max {
+            return Err(LendingError::ExceedsCloseFactor {
+                max,
+                requested: repay,
+            });
+        }
+        let value = self
+            .params
+            .liquidation_bonus
+            .apply(repay, Rounding::Down)
+            .ok_or(LendingError::Overflow)?;
+        let collateral = self.positions[user].collateral;
+        // NOTE 抵押品不够支付奖励时全部给清算人，剩下的是坏账，由出借人承担
+        let seized = self
+            .price
+            .apply_inverse(value, Rounding::Down)
+            .ok_or(LendingError::Overflow)?
+            .min(collateral);
+
+        self.reduce_debt(user, repay, debt)?;
+        self.positions.get_mut(user).unwrap().collateral -= seized;
+        Ok(seized)
+    }
+}
+
+#[cfg(test)]
+fn test_params() -> Params {
+    Params {
+        collateral_factor: Fixed::parse("0.75").unwrap(),
+        liquidation_threshold: Fixed::parse("0.8").unwrap(),
+        liquidation_bonus: Fixed::parse("1.05").unwrap(),
+        rate_per_block: Fixed::parse("0.0001").unwrap(),
+    }
+}
+
+#[test]
+fn test_borrow_and_liquidate() {
+    // 1 个抵押品值 2000
+    let mut pool = LendingPool::new(test_params(), Fixed::from_int(2000).unwrap());
+    pool.supply(1_000_000).unwrap();
+    pool.deposit_collateral("alice", 10).unwrap();
+
+    // 额度 = 10 * 2000 * 0.75 = 15000
+    assert_eq!(
+        pool.borrow("alice", 15_001),
+        Err(LendingError::InsufficientCollateral {
+            limit: 15_000,
+            debt: 15_001
+        })
+    );
+    pool.borrow("alice", 15_000).unwrap();
+    assert_eq!(pool.debt_of("alice").unwrap(), 15_000);
+    assert_eq!(pool.cash(), 985_000);
+    // 16000 / 15000
+    assert_eq!(
+        pool.health_factor("alice").unwrap().unwrap().to_string(),
+        "1.066666666666666666"
+    );
+    assert!(pool.withdraw_collateral("alice", 1).is_err());
+    assert_eq!(
+        pool.liquidate("alice", 100),
+        Err(LendingError::PositionHealthy)
+    );
+    assert_eq!(pool.borrow("bob", 1), Err(LendingError::UnknownAccount));
+
+    // 价格跌到 1800: 18000 * 0.8 = 14400 < 15000
+    pool.set_price(Fixed::from_int(1800).unwrap());
+    assert!(pool.health_factor("alice").unwrap().unwrap() < Fixed::ONE);
+    assert_eq!(
+        pool.liquidate("alice", 7_501),
+        Err(LendingError::ExceedsCloseFactor {
+            max: 7_500,
+            requested: 7_501
+        })
+    );
+    // 7200 * 1.05 / 1800 = 4.2，向下取整为 4
+    assert_eq!(pool.liquidate("alice", 7_200), Ok(4));
+    assert_eq!(pool.debt_of("alice").unwrap(), 7_800);
+    assert_eq!(pool.position("alice").unwrap().collateral, 6);
+    // 6 * 1800 * 0.8 = 8640 > 7800，恢复健康
+    assert!(pool.health_factor("alice").unwrap().unwrap() > Fixed::ONE);
+
+    assert_eq!(pool.repay("alice", 10_000), Ok(7_800));
+    assert_eq!(pool.debt_of("alice").unwrap(), 0);
+    assert_eq!(pool.health_factor("alice").unwrap(), None);
+    pool.withdraw_collateral("alice", 6).unwrap();
+    assert_eq!(pool.cash(), 1_000_000);
+    assert_eq!(pool.supply(u128::MAX), Err(LendingError::Overflow));
+    assert_eq!(pool.cash(), 1_000_000);
+}
+
+#[test]
+fn test_interest_accrual() {
+    let mut pool = LendingPool::new(test_params(), Fixed::ONE);
+    pool.supply(1_000_000).unwrap();
+    pool.deposit_collateral("alice", 1_000_000).unwrap();
+    pool.borrow("alice", 100_000).unwrap();
+
+    // 1.0001^10000 = 2.71814...
+    pool.accrue(10_000).unwrap();
+    let debt = pool.debt_of("alice").unwrap();
+    assert!((271_814..=271_815).contains(&debt), "{}", debt);
+    // 利息归出借人
+    assert_eq!(pool.total_assets().unwrap(), 900_000 + debt);
+
+    // 计息后额度变紧
+    assert!(pool.borrow("alice", 750_000 - debt + 1).is_err());
+    pool.borrow("alice", 750_000 - debt - 10).unwrap();
+
+    // 部分还款不会因为舍入而少还
+    let before = pool.debt_of("alice").unwrap();
+    pool.repay("alice", 1).unwrap();
+    assert!(pool.debt_of("alice").unwrap() >= before - 1);
+}
+
+#[test]
+fn test_randomized_solvency() {
+    use crate::testing::XorShift;
+
+    let users = ["alice", "bob", "carol", "dave"];
+    let mut rng = XorShift::new(42);
+    let mut pool = LendingPool::new(test_params(), Fixed::from_int(100).unwrap());
+    pool.supply(10_000_000).unwrap();
+    let mut assets = pool.total_assets().unwrap();
+    let mut collateral_in = 0u128;
+    let mut collateral_out = 0u128;
+
+    for _ in 0..3_000 {
+        let user = users[rng.below(users.len() as u64) as usize];
+        let amount = rng.below(50_000) as u128 + 1;
+        let limited = match rng.below(7) {
+            0 => {
+                pool.deposit_collateral(user, amount / 100 + 1).unwrap();
+                collateral_in += amount / 100 + 1;
+                false
+            }
+            1 => pool.borrow(user, amount).is_ok(),
+            2 => {
+                let ok = pool.withdraw_collateral(user, amount / 100).is_ok();
+                if ok {
+                    collateral_out += amount / 100;
+                }
+                ok
+            }
+            3 => {
+                let _ = pool.repay(user, amount);
+                false
+            }
+            4 => {
+                let _ = pool.accrue(rng.below(100));
+                false
+            }
+            5 => {
+                // 价格在 60 到 140 之间波动
+                pool.set_price(Fixed::from_int(60 + rng.below(81) as u128).unwrap());
+                false
+            }
+            _ => {
+                let max = pool.debt_of(user).unwrap().div_ceil(2);
+                if max > 0 {
+                    if let Ok(seized) = pool.liquidate(user, rng.below(max as u64) as u128 + 1) {
+                        collateral_out += seized;
+                    }
+                }
+                false
+            }
+        };
+        // 借款和提取成功后仓位必须在额度以内
+        if limited {
+            let position = pool.position(user).unwrap();
+            let limit = pool
+                .collateral_value(position.collateral, test_params().collateral_factor)
+                .unwrap();
+            assert!(pool.debt_of(user).unwrap() <= limit);
+        }
+
+        let scaled: u128 = pool.positions.values().map(|p| p.scaled_debt).sum();
+        assert_eq!(scaled, pool.total_scaled_debt);
+        let collateral: u128 = pool.positions.values().map(|p| p.collateral).sum();
+        assert_eq!(collateral, collateral_in - collateral_out);
+        // 舍入全部偏向协议，出借人的资产只增不减（这里不模拟坏账核销）
+        assert!(pool.total_assets().unwrap() >= assets);
+        assets = pool.total_assets().unwrap();
+    }
+}
+
+#[test]
+fn test_debt_overflow() {
+    // 每个区块利率 100%，40 个区块后债务是借款的 2^40 倍，超出 u128
+    let params = Params {
+        rate_per_block: Fixed::ONE,
+        ..test_params()
+    };
+    let mut pool = LendingPool::new(params, Fixed::ONE);
+    pool.supply(10u128.pow(30)).unwrap();
+    pool.deposit_collateral("alice", 10u128.pow(31)).unwrap();
+    pool.borrow("alice", 10u128.pow(30)).unwrap();
+    pool.accrue(40).unwrap();
+
+    // 溢出时报错，而不是返回一个饱和的 u128::MAX
+    assert_eq!(pool.debt_of("alice"), Err(LendingError::Overflow));
+    assert_eq!(pool.total_debt(), Err(LendingError::Overflow));
+    assert_eq!(pool.total_assets(), Err(LendingError::Overflow));
+    assert_eq!(pool.health_factor("alice"), Err(LendingError::Overflow));
+    assert_eq!(pool.repay("alice", 1), Err(LendingError::Overflow));
+    assert_eq!(pool.liquidate("alice", 1), Err(LendingError::Overflow));
+    assert_eq!(
+        pool.withdraw_collateral("alice", 1),
+        Err(LendingError::Overflow)
+    );
+    // 抵押品的价值同样不会饱和
+    pool.set_price(Fixed::from_int(10u128.pow(10)).unwrap());
+    assert_eq!(
+        pool.collateral_value(10u128.pow(31), Fixed::ONE),
+        Err(LendingError::Overflow)
+    );
+}
@@ -0,0 +1,5 @@
+pub(crate) mod amm;
+pub(crate) mod fixed;
+pub(crate) mod lending;
+
+// NOTE 金额一律用整数或定点数表示，整个模块里不出现 f32 / f64
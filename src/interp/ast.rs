@@ -0,0 +1,116 @@
+#![allow(dead_code)]
+
+/*
+ * 语法树
+ *      和 Rust 一样，几乎所有东西都是表达式（Expr），包括 代码块、if、loop、while、for、赋值。
+ *      只有 let 和 "表达式加分号" 是语句（Stmt）。
+ */
+
+use super::lexer::Pos;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Int(i64),
+    Bool(bool),
+    /// ()
+    Unit,
+    Var(String, Pos),
+    Unary(UnaryOp, Box<Expr>, Pos),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>, Pos),
+    /// x = e 或 x += e（op 为 Some(Add)），值总是 ()
+    Assign {
+        name: String,
+        op: Option<BinaryOp>,
+        value: Box<Expr>,
+        pos: Pos,
+    },
+    Block(Block),
+    /// 没有 else 时 otherwise 为 None，这时 then 的值必须是 ()
+    If {
+        cond: Box<Expr>,
+        then: Block,
+        otherwise: Option<Box<Expr>>,
+        pos: Pos,
+    },
+    /// 值是 break 带出来的值
+    Loop(Block),
+    /// 值总是 ()
+    While {
+        cond: Box<Expr>,
+        body: Block,
+        pos: Pos,
+    },
+    /// for var in start..end，值总是 ()
+    For {
+        var: String,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        body: Block,
+        pos: Pos,
+    },
+    Break(Option<Box<Expr>>),
+    Continue,
+}
+
+impl Expr {
+    /**
+     * 块状表达式（代码块、if、loop、while、for）单独作为一条语句时，后面可以不写分号。
+     * 和 Rust 一样，这时它的值必须是 ()，例如
+     *      if x > 0 { 1 } else { 2 }
+     *      x
+     * 会报错 "expected `()`, found integer"
+     */
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            Expr::Block(_)
+                | Expr::If { .. }
+                | Expr::Loop(_)
+                | Expr::While { .. }
+                | Expr::For { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Let {
+        name: String,
+        mutable: bool,
+        value: Expr,
+    },
+    /// 带分号的表达式，值被丢弃
+    Semi(Expr),
+    /// 不带分号的块状表达式，值必须是 ()
+    BlockLike(Expr, Pos),
+}
+
+/// { stmt; stmt; tail }，tail 就是代码块的值，没有时为 ()
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub tail: Option<Box<Expr>>,
+}
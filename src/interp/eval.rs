@@ -0,0 +1,530 @@
+#![allow(dead_code)]
+
+/*
+ * 求值: 遍历语法树，对每种 Expr 用 match 计算出一个 Value
+ *
+ * 语义和 Rust 保持一致:
+ *      代码块的值是最后一个不带分号的表达式，没有就是 ()
+ *      if 是表达式；没有 else 时 if 的值是 ()，所以 then 分支的值也必须是 ()
+ *      loop 的值是 break 带出来的值；while、for 的值永远是 ()
+ *      赋值表达式的值是 ()
+ *      整数溢出、除以 0 是运行时错误（相当于 debug 模式下的 panic）
+ *
+ * break / continue 需要跳出好几层递归，这里把它们和错误一起放进 Result 的 Err 里，用 ? 一路向上传递，
+ * 直到被对应的循环拦下来。
+ */
+
+use super::ast::{BinaryOp, Block, Expr, Stmt, UnaryOp};
+use super::lexer::{Pos, SyntaxError};
+use super::parser::parse;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Unit,
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Bool(_) => "bool",
+            Value::Unit => "`()`",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    Undefined(String, Pos),
+    /// 给不可变的变量赋值
+    Immutable(String, Pos),
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+        pos: Pos,
+    },
+    DivisionByZero(Pos),
+    Overflow(Pos),
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Undefined(name, pos) => {
+                write!(f, "{}: cannot find value `{}` in this scope", pos, name)
+            }
+            RuntimeError::Immutable(name, pos) => write!(
+                f,
+                "{}: cannot assign twice to immutable variable `{}`",
+                pos, name
+            ),
+            RuntimeError::TypeMismatch {
+                expected,
+                found,
+                pos,
+            } => write!(f, "{}: expected {}, found {}", pos, expected, found),
+            RuntimeError::DivisionByZero(pos) => write!(f, "{}: attempt to divide by zero", pos),
+            RuntimeError::Overflow(pos) => write!(f, "{}: attempt to compute with overflow", pos),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Syntax(SyntaxError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(err) => write!(f, "syntax error at {}", err),
+            Error::Runtime(err) => write!(f, "runtime error at {}", err),
+        }
+    }
+}
+
+// NOTE Display 已经带上了内层错误的内容，所以不再通过 source() 重复返回它
+impl std::error::Error for Error {}
+
+impl From<SyntaxError> for Error {
+    fn from(err: SyntaxError) -> Error {
+        Error::Syntax(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Error {
+        Error::Runtime(err)
+    }
+}
+
+/// 打断正常执行顺序的几种情况
+enum Flow {
+    Break(Value),
+    Continue,
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Flow {
+    fn from(err: RuntimeError) -> Flow {
+        Flow::Error(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Binding {
+    value: Value,
+    mutable: bool,
+}
+
+/// 变量作用域是一个栈，进入代码块压入一层，离开时弹出，同一层里可以重复 let（shadowing）
+#[derive(Debug)]
+pub struct Interpreter {
+    scopes: Vec<HashMap<String, Binding>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+fn expect_int(value: Value, pos: Pos) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Int(n) => Ok(n),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "integer",
+            found: other.type_name(),
+            pos,
+        }),
+    }
+}
+
+fn expect_bool(value: Value, pos: Pos) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "bool",
+            found: other.type_name(),
+            pos,
+        }),
+    }
+}
+
+fn expect_unit(value: Value, pos: Pos) -> Result<(), RuntimeError> {
+    match value {
+        Value::Unit => Ok(()),
+        other => Err(RuntimeError::TypeMismatch {
+            expected: "`()`",
+            found: other.type_name(),
+            pos,
+        }),
+    }
+}
+
+/// 算术运算都用 checked_*，溢出时报错而不是悄悄回绕
+fn arithmetic(op: BinaryOp, a: i64, b: i64, pos: Pos) -> Result<i64, RuntimeError> {
+    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && b == 0 {
+        return Err(RuntimeError::DivisionByZero(pos));
+    }
+    let result = match op {
+        BinaryOp::Add => a.checked_add(b),
+        BinaryOp::Sub => a.checked_sub(b),
+        BinaryOp::Mul => a.checked_mul(b),
+        BinaryOp::Div => a.checked_div(b),
+        BinaryOp::Rem => a.checked_rem(b),
+        _ => unreachable!("not an arithmetic operator: {:?}", op),
+    };
+    result.ok_or(RuntimeError::Overflow(pos))
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    /// 在最外层作用域里执行，let 定义的变量会保留下来，REPL 的每一行都能看到之前的变量
+    pub fn eval(&mut self, src: &str) -> Result<Value, Error> {
+        let program = parse(src)?;
+        match self.block_body(&program) {
+            Ok(value) => Ok(value),
+            Err(Flow::Error(err)) => Err(err.into()),
+            // 语法分析已经保证 break、continue 都在循环里
+            Err(Flow::Break(_) | Flow::Continue) => unreachable!("break outside of a loop"),
+        }
+    }
+
+    /// 最外层作用域里的变量，按名字排序
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut vars: Vec<_> = self.scopes[0]
+            .iter()
+            .map(|(name, b)| (name.clone(), b.value))
+            .collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        vars
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|s| s.get_mut(name))
+    }
+
+    fn define(&mut self, name: &str, value: Value, mutable: bool) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), Binding { value, mutable });
+    }
+
+    /// 新开一层作用域执行，无论正常结束、break 还是出错都要把这一层弹出
+    fn scoped<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Flow>) -> Result<T, Flow> {
+        self.scopes.push(HashMap::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    fn block(&mut self, block: &Block) -> Result<Value, Flow> {
+        self.scoped(|this| this.block_body(block))
+    }
+
+    fn block_body(&mut self, block: &Block) -> Result<Value, Flow> {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let {
+                    name,
+                    mutable,
+                    value,
+                } => {
+                    let value = self.expr(value)?;
+                    self.define(name, value, *mutable);
+                }
+                Stmt::Semi(expr) => {
+                    self.expr(expr)?;
+                }
+                Stmt::BlockLike(expr, pos) => {
+                    let value = self.expr(expr)?;
+                    expect_unit(value, *pos)?;
+                }
+            }
+        }
+        match &block.tail {
+            Some(tail) => self.expr(tail),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Value, Flow> {
+        Ok(match expr {
+            Expr::Int(n) => Value::Int(*n),
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Unit => Value::Unit,
+            Expr::Var(name, pos) => match self.lookup(name) {
+                Some(binding) => binding.value,
+                None => return Err(RuntimeError::Undefined(name.clone(), *pos).into()),
+            },
+            Expr::Unary(op, operand, pos) => {
+                let value = self.expr(operand)?;
+                match op {
+                    UnaryOp::Neg => {
+                        let n = expect_int(value, *pos)?;
+                        Value::Int(n.checked_neg().ok_or(RuntimeError::Overflow(*pos))?)
+                    }
+                    UnaryOp::Not => Value::Bool(!expect_bool(value, *pos)?),
+                }
+            }
+            Expr::Binary(op, lhs, rhs, pos) => self.binary(*op, lhs, rhs, *pos)?,
+            Expr::Assign {
+                name,
+                op,
+                value,
+                pos,
+            } => {
+                let value = self.expr(value)?;
+                let binding = self
+                    .lookup(name)
+                    .ok_or_else(|| RuntimeError::Undefined(name.clone(), *pos))?;
+                if !binding.mutable {
+                    return Err(RuntimeError::Immutable(name.clone(), *pos).into());
+                }
+                binding.value = match op {
+                    None => value,
+                    Some(op) => {
+                        let old = expect_int(binding.value, *pos)?;
+                        Value::Int(arithmetic(*op, old, expect_int(value, *pos)?, *pos)?)
+                    }
+                };
+                Value::Unit
+            }
+            Expr::Block(block) => self.block(block)?,
+            Expr::If {
+                cond,
+                then,
+                otherwise,
+                pos,
+            } => {
+                let cond = expect_bool(self.expr(cond)?, *pos)?;
+                match (cond, otherwise) {
+                    (true, Some(_)) => self.block(then)?,
+                    (false, Some(otherwise)) => self.expr(otherwise)?,
+                    // 没有 else 的 if: 条件不成立时值是 ()，所以成立时也必须是 ()
+                    (true, None) => {
+                        let value = self.block(then)?;
+                        expect_unit(value, *pos)?;
+                        Value::Unit
+                    }
+                    (false, None) => Value::Unit,
+                }
+            }
+            Expr::Loop(body) => loop {
+                match self.block(body) {
+                    Ok(_) | Err(Flow::Continue) => {}
+                    Err(Flow::Break(value)) => break value,
+                    Err(err) => return Err(err),
+                }
+            },
+            Expr::While { cond, body, pos } => {
+                while expect_bool(self.expr(cond)?, *pos)? {
+                    match self.block(body) {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break(_)) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Value::Unit
+            }
+            Expr::For {
+                var,
+                start,
+                end,
+                body,
+                pos,
+            } => {
+                let start = expect_int(self.expr(start)?, *pos)?;
+                let end = expect_int(self.expr(end)?, *pos)?;
+                for i in start..end {
+                    // 循环变量每一轮都是一个新的不可变绑定
+                    let result = self.scoped(|this| {
+                        this.define(var, Value::Int(i), false);
+                        this.block_body(body)
+                    });
+                    match result {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break(_)) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Value::Unit
+            }
+            Expr::Break(value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Flow::Break(value));
+            }
+            Expr::Continue => return Err(Flow::Continue),
+        })
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: &Expr, rhs: &Expr, pos: Pos) -> Result<Value, Flow> {
+        let left = self.expr(lhs)?;
+        // && 和 || 短路: 左边已经能决定结果时，右边不求值
+        if let BinaryOp::And | BinaryOp::Or = op {
+            let left = expect_bool(left, pos)?;
+            if left == (op == BinaryOp::Or) {
+                return Ok(Value::Bool(left));
+            }
+            return Ok(Value::Bool(expect_bool(self.expr(rhs)?, pos)?));
+        }
+
+        let right = self.expr(rhs)?;
+        Ok(match op {
+            BinaryOp::Eq | BinaryOp::Ne => {
+                if std::mem::discriminant(&left) != std::mem::discriminant(&right) {
+                    return Err(RuntimeError::TypeMismatch {
+                        expected: left.type_name(),
+                        found: right.type_name(),
+                        pos,
+                    }
+                    .into());
+                }
+                Value::Bool((left == right) == (op == BinaryOp::Eq))
+            }
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                let (a, b) = (expect_int(left, pos)?, expect_int(right, pos)?);
+                Value::Bool(match op {
+                    BinaryOp::Lt => a < b,
+                    BinaryOp::Le => a <= b,
+                    BinaryOp::Gt => a > b,
+                    _ => a >= b,
+                })
+            }
+            _ => {
+                let (a, b) = (expect_int(left, pos)?, expect_int(right, pos)?);
+                Value::Int(arithmetic(op, a, b, pos)?)
+            }
+        })
+    }
+}
+
+/// 执行一段独立的程序
+pub fn eval(src: &str) -> Result<Value, Error> {
+    Interpreter::new().eval(src)
+}
+
+#[test]
+fn test_eval_expressions() {
+    let int = |src| match eval(src) {
+        Ok(Value::Int(n)) => n,
+        other => panic!("{}: {:?}", src, other),
+    };
+
+    assert_eq!(int("1 + 2 * 3 - -4"), 11);
+    assert_eq!(int("(1 + 2) * 3 % 5"), 4);
+    // 代码块的值是最后一个不带分号的表达式
+    assert_eq!(int("let x = { let y = 2; y * y }; x + 1"), 5);
+    assert_eq!(eval("{ 1; }"), Ok(Value::Unit));
+    // 遮蔽（shadowing）: 内层的 x 离开代码块就消失了
+    assert_eq!(int("let x = 1; { let x = 10; x; } x"), 1);
+    // if 是表达式
+    assert_eq!(
+        int("let n = 7; if n % 2 == 0 { 0 } else if n > 5 { 2 } else { 1 }"),
+        2
+    );
+    // loop 用 break 带出值
+    assert_eq!(
+        int("let mut i = 1; let mut acc = 1; loop { if i > 10 { break acc; } acc *= i; i += 1; }"),
+        3_628_800
+    );
+    // while、for 的值永远是 ()
+    assert_eq!(
+        eval("let mut i = 0; while i < 3 { i += 1 }"),
+        Ok(Value::Unit)
+    );
+    assert_eq!(
+        int("let mut sum = 0; for i in 0..100 { if i % 3 == 0 { continue } if i > 10 { break } sum += i; } sum"),
+        1 + 2 + 4 + 5 + 7 + 8 + 10
+    );
+    // 赋值表达式的值也是 ()
+    assert_eq!(eval("let mut x = 1; x = 2"), Ok(Value::Unit));
+    assert_eq!(int("let mut x = 17; x %= 5; x"), 2);
+    // && 短路，右边的除以 0 不会执行
+    assert_eq!(eval("false && 1 / 0 == 0"), Ok(Value::Bool(false)));
+}
+
+#[test]
+fn test_eval_errors() {
+    let err = |src| eval(src).unwrap_err().to_string();
+
+    assert_eq!(
+        err("let x = 1; x = 2;"),
+        "runtime error at 1:14: cannot assign twice to immutable variable `x`"
+    );
+    assert_eq!(
+        err("y + 1"),
+        "runtime error at 1:1: cannot find value `y` in this scope"
+    );
+    assert_eq!(
+        err("{ let z = 1; } z"),
+        "runtime error at 1:16: cannot find value `z` in this scope"
+    );
+    // 没有 else 的 if，分支的值必须是 ()
+    assert_eq!(
+        err("let x = if true { 1 };"),
+        "runtime error at 1:9: expected `()`, found integer"
+    );
+    // 单独作为语句的块状表达式，值也必须是 ()
+    assert_eq!(
+        err("if true { 1 } else { 2 } 3"),
+        "runtime error at 1:1: expected `()`, found integer"
+    );
+    assert_eq!(
+        err("if 1 { }"),
+        "runtime error at 1:1: expected bool, found integer"
+    );
+    assert_eq!(
+        err("1 == true"),
+        "runtime error at 1:3: expected integer, found bool"
+    );
+    assert_eq!(
+        err("10 / (5 - 5)"),
+        "runtime error at 1:4: attempt to divide by zero"
+    );
+    assert_eq!(
+        err("9223372036854775807 + 1"),
+        "runtime error at 1:21: attempt to compute with overflow"
+    );
+    assert_eq!(
+        err("for i in 0..3 { break i; }"),
+        "syntax error at 1:17: `break` with value from a `for` loop"
+    );
+    // 错误链里每一层只出现一次
+    assert!(std::error::Error::source(&eval("y").unwrap_err()).is_none());
+
+    // 出错时作用域要正确弹出，之后的代码还能继续执行
+    let mut interp = Interpreter::new();
+    assert!(interp
+        .eval("let a = 1; loop { let b = 2; b / 0; }")
+        .is_err());
+    assert_eq!(interp.eval("a"), Ok(Value::Int(1)));
+    assert!(interp.eval("b").is_err());
+}
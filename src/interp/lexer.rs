@@ -0,0 +1,308 @@
+#![allow(dead_code)]
+
+/*
+ * 词法分析: 把源代码切成一个个 token
+ *      let mut x = 1 + 2;  ->  Let Mut Ident("x") Assign Int(1) Plus Int(2) Semi
+ *
+ * 每个 token 记录所在的行和列（从 1 开始），报错时指出位置。
+ */
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Int(i64),
+    Ident(String),
+
+    // 关键字
+    Let,
+    Mut,
+    If,
+    Else,
+    Loop,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    True,
+    False,
+
+    // 运算符和标点
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Bang,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    AndAnd,
+    OrOr,
+    DotDot,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Semi,
+
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TokenKind::Int(n) => return write!(f, "`{}`", n),
+            TokenKind::Ident(name) => return write!(f, "`{}`", name),
+            TokenKind::Let => "let",
+            TokenKind::Mut => "mut",
+            TokenKind::If => "if",
+            TokenKind::Else => "else",
+            TokenKind::Loop => "loop",
+            TokenKind::While => "while",
+            TokenKind::For => "for",
+            TokenKind::In => "in",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
+            TokenKind::True => "true",
+            TokenKind::False => "false",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::Bang => "!",
+            TokenKind::Assign => "=",
+            TokenKind::PlusAssign => "+=",
+            TokenKind::MinusAssign => "-=",
+            TokenKind::StarAssign => "*=",
+            TokenKind::SlashAssign => "/=",
+            TokenKind::PercentAssign => "%=",
+            TokenKind::Eq => "==",
+            TokenKind::Ne => "!=",
+            TokenKind::Lt => "<",
+            TokenKind::Le => "<=",
+            TokenKind::Gt => ">",
+            TokenKind::Ge => ">=",
+            TokenKind::AndAnd => "&&",
+            TokenKind::OrOr => "||",
+            TokenKind::DotDot => "..",
+            TokenKind::LParen => "(",
+            TokenKind::RParen => ")",
+            TokenKind::LBrace => "{",
+            TokenKind::RBrace => "}",
+            TokenKind::Semi => ";",
+            TokenKind::Eof => return write!(f, "end of input"),
+        };
+        write!(f, "`{}`", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+}
+
+/// 词法和语法错误，相当于 rustc 的编译错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub pos: Pos,
+    pub message: String,
+}
+
+impl SyntaxError {
+    pub fn new(pos: Pos, message: impl Into<String>) -> SyntaxError {
+        SyntaxError {
+            pos,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pos, self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+fn keyword(word: &str) -> Option<TokenKind> {
+    Some(match word {
+        "let" => TokenKind::Let,
+        "mut" => TokenKind::Mut,
+        "if" => TokenKind::If,
+        "else" => TokenKind::Else,
+        "loop" => TokenKind::Loop,
+        "while" => TokenKind::While,
+        "for" => TokenKind::For,
+        "in" => TokenKind::In,
+        "break" => TokenKind::Break,
+        "continue" => TokenKind::Continue,
+        "true" => TokenKind::True,
+        "false" => TokenKind::False,
+        _ => return None,
+    })
+}
+
+/// 结果总是以 Eof 结尾，语法分析时不用到处判断越界
+pub fn tokenize(src: &str) -> Result<Vec<Token>, SyntaxError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line, mut col) = (0, 1, 1);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = Pos { line, col };
+        let next = chars.get(i + 1).copied();
+
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            col = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            col += 1;
+            continue;
+        }
+        // 行注释
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let start = i;
+        let kind = if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let n = text
+                .parse()
+                .map_err(|_| SyntaxError::new(pos, "integer literal is too large"))?;
+            TokenKind::Int(n)
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            keyword(&word).unwrap_or(TokenKind::Ident(word))
+        } else {
+            // 先匹配两个字符的运算符，再匹配一个字符的
+            let two = match (c, next) {
+                ('=', Some('=')) => Some(TokenKind::Eq),
+                ('!', Some('=')) => Some(TokenKind::Ne),
+                ('<', Some('=')) => Some(TokenKind::Le),
+                ('>', Some('=')) => Some(TokenKind::Ge),
+                ('&', Some('&')) => Some(TokenKind::AndAnd),
+                ('|', Some('|')) => Some(TokenKind::OrOr),
+                ('.', Some('.')) => Some(TokenKind::DotDot),
+                ('+', Some('=')) => Some(TokenKind::PlusAssign),
+                ('-', Some('=')) => Some(TokenKind::MinusAssign),
+                ('*', Some('=')) => Some(TokenKind::StarAssign),
+                ('/', Some('=')) => Some(TokenKind::SlashAssign),
+                ('%', Some('=')) => Some(TokenKind::PercentAssign),
+                _ => None,
+            };
+            if let Some(kind) = two {
+                i += 2;
+                kind
+            } else {
+                i += 1;
+                match c {
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    '*' => TokenKind::Star,
+                    '/' => TokenKind::Slash,
+                    '%' => TokenKind::Percent,
+                    '!' => TokenKind::Bang,
+                    '=' => TokenKind::Assign,
+                    '<' => TokenKind::Lt,
+                    '>' => TokenKind::Gt,
+                    '(' => TokenKind::LParen,
+                    ')' => TokenKind::RParen,
+                    '{' => TokenKind::LBrace,
+                    '}' => TokenKind::RBrace,
+                    ';' => TokenKind::Semi,
+                    _ => return Err(SyntaxError::new(pos, format!("unknown character `{}`", c))),
+                }
+            }
+        };
+        col += i - start;
+        tokens.push(Token { kind, pos });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        pos: Pos { line, col },
+    });
+    Ok(tokens)
+}
+
+#[test]
+fn test_tokenize() {
+    let kinds =
+        |src| -> Vec<TokenKind> { tokenize(src).unwrap().into_iter().map(|t| t.kind).collect() };
+    assert_eq!(
+        kinds("let mut x_1 = 1_000 >= -2; // comment"),
+        [
+            TokenKind::Let,
+            TokenKind::Mut,
+            TokenKind::Ident("x_1".to_string()),
+            TokenKind::Assign,
+            TokenKind::Int(1000),
+            TokenKind::Ge,
+            TokenKind::Minus,
+            TokenKind::Int(2),
+            TokenKind::Semi,
+            TokenKind::Eof,
+        ]
+    );
+    assert_eq!(
+        kinds("for i in 0..n"),
+        [
+            TokenKind::For,
+            TokenKind::Ident("i".to_string()),
+            TokenKind::In,
+            TokenKind::Int(0),
+            TokenKind::DotDot,
+            TokenKind::Ident("n".to_string()),
+            TokenKind::Eof,
+        ]
+    );
+
+    let tokens = tokenize("x\n  += 1").unwrap();
+    assert_eq!(tokens[1].pos, Pos { line: 2, col: 3 });
+    assert_eq!(
+        tokenize("1 # 2").unwrap_err().to_string(),
+        "1:3: unknown character `#`"
+    );
+    assert!(tokenize("99999999999999999999").is_err());
+}
@@ -0,0 +1,7 @@
+pub(crate) mod ast;
+pub(crate) mod eval;
+pub(crate) mod lexer;
+pub(crate) mod parser;
+pub(crate) mod repl;
+
+// NOTE 一门很小的表达式语言: 源代码 -> lexer -> token -> parser -> 语法树 -> eval -> 值，语义尽量和 Rust 一致
@@ -0,0 +1,437 @@
+#![allow(dead_code)]
+
+/*
+ * 语法分析: Pratt parser（自顶向下的运算符优先级分析）
+ *      每个二元运算符有左右两个 "结合力"（binding power），数字越大结合得越紧:
+ *          =  +=  -= ...       2  1     右结合: 右边的结合力更小
+ *          ||                  3  4
+ *          &&                  5  6
+ *          == != < <= > >=     7  8     不能连写: a < b < c 是错误
+ *          +  -                9  10    左结合: 右边的结合力更大
+ *          *  /  %             11 12
+ *          -x  !x              13       前缀运算符
+ *      parse_expr(min_bp) 只会吃掉结合力不小于 min_bp 的运算符，于是 1 + 2 * 3 会先把 2 * 3 组合在一起。
+ *
+ * 和 rustc 一样，有些错误在语法分析阶段就报出来（相当于编译错误）:
+ *      break 不在循环里、while / for 里的 break 带了值、比较运算符连写
+ */
+
+use super::ast::{BinaryOp, Block, Expr, Stmt, UnaryOp};
+use super::lexer::{tokenize, Pos, SyntaxError, Token, TokenKind};
+
+const ASSIGN_BP: (u8, u8) = (2, 1);
+const PREFIX_BP: u8 = 13;
+
+fn infix_bp(kind: &TokenKind) -> Option<(BinaryOp, u8, u8)> {
+    Some(match kind {
+        TokenKind::OrOr => (BinaryOp::Or, 3, 4),
+        TokenKind::AndAnd => (BinaryOp::And, 5, 6),
+        TokenKind::Eq => (BinaryOp::Eq, 7, 8),
+        TokenKind::Ne => (BinaryOp::Ne, 7, 8),
+        TokenKind::Lt => (BinaryOp::Lt, 7, 8),
+        TokenKind::Le => (BinaryOp::Le, 7, 8),
+        TokenKind::Gt => (BinaryOp::Gt, 7, 8),
+        TokenKind::Ge => (BinaryOp::Ge, 7, 8),
+        TokenKind::Plus => (BinaryOp::Add, 9, 10),
+        TokenKind::Minus => (BinaryOp::Sub, 9, 10),
+        TokenKind::Star => (BinaryOp::Mul, 11, 12),
+        TokenKind::Slash => (BinaryOp::Div, 11, 12),
+        TokenKind::Percent => (BinaryOp::Rem, 11, 12),
+        _ => return None,
+    })
+}
+
+/// 赋值运算符，Some(None) 是普通的 =
+fn assign_op(kind: &TokenKind) -> Option<Option<BinaryOp>> {
+    Some(match kind {
+        TokenKind::Assign => None,
+        TokenKind::PlusAssign => Some(BinaryOp::Add),
+        TokenKind::MinusAssign => Some(BinaryOp::Sub),
+        TokenKind::StarAssign => Some(BinaryOp::Mul),
+        TokenKind::SlashAssign => Some(BinaryOp::Div),
+        TokenKind::PercentAssign => Some(BinaryOp::Rem),
+        _ => return None,
+    })
+}
+
+fn is_comparison(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoopKind {
+    Loop,
+    While,
+    For,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    /// 当前所在的循环，从外到内，用来检查 break / continue
+    loops: Vec<LoopKind>,
+}
+
+/// 整个程序就是一个不带大括号的代码块
+pub fn parse(src: &str) -> Result<Block, SyntaxError> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        index: 0,
+        loops: Vec::new(),
+    };
+    parser.block_body(&TokenKind::Eof)
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        // Eof 之后不再前进
+        if self.index + 1 < self.tokens.len() {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> SyntaxError {
+        let token = self.peek();
+        SyntaxError::new(
+            token.pos,
+            format!("expected {}, found {}", expected, token.kind),
+        )
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<Pos, SyntaxError> {
+        let pos = self.peek().pos;
+        if self.eat(kind) {
+            Ok(pos)
+        } else {
+            Err(self.unexpected(&kind.to_string()))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, SyntaxError> {
+        match &self.peek().kind {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn block_body(&mut self, end: &TokenKind) -> Result<Block, SyntaxError> {
+        let mut block = Block::default();
+        loop {
+            if &self.peek().kind == end {
+                return Ok(block);
+            }
+            if self.eat(&TokenKind::Semi) {
+                continue;
+            }
+            if self.eat(&TokenKind::Let) {
+                let mutable = self.eat(&TokenKind::Mut);
+                let name = self.ident()?;
+                self.expect(&TokenKind::Assign)?;
+                let value = self.expr(0)?;
+                self.expect(&TokenKind::Semi)?;
+                block.stmts.push(Stmt::Let {
+                    name,
+                    mutable,
+                    value,
+                });
+                continue;
+            }
+
+            // NOTE 语句开头的块状表达式到此为止，不会和后面的运算符组合: { 1 } - 1 是两条语句
+            let pos = self.peek().pos;
+            let expr = if self.starts_block_like() {
+                self.block_like()?
+            } else {
+                self.expr(0)?
+            };
+            if self.eat(&TokenKind::Semi) {
+                block.stmts.push(Stmt::Semi(expr));
+            } else if &self.peek().kind == end {
+                block.tail = Some(Box::new(expr));
+                return Ok(block);
+            } else if expr.is_block_like() {
+                block.stmts.push(Stmt::BlockLike(expr, pos));
+            } else {
+                return Err(self.unexpected("`;`"));
+            }
+        }
+    }
+
+    fn starts_block_like(&self) -> bool {
+        matches!(
+            self.peek().kind,
+            TokenKind::LBrace | TokenKind::If | TokenKind::Loop | TokenKind::While | TokenKind::For
+        )
+    }
+
+    fn block(&mut self) -> Result<Block, SyntaxError> {
+        self.expect(&TokenKind::LBrace)?;
+        let block = self.block_body(&TokenKind::RBrace)?;
+        self.expect(&TokenKind::RBrace)?;
+        Ok(block)
+    }
+
+    fn loop_body(&mut self, kind: LoopKind) -> Result<Block, SyntaxError> {
+        self.loops.push(kind);
+        let body = self.block();
+        self.loops.pop();
+        body
+    }
+
+    fn block_like(&mut self) -> Result<Expr, SyntaxError> {
+        let pos = self.peek().pos;
+        match self.peek().kind {
+            TokenKind::LBrace => Ok(Expr::Block(self.block()?)),
+            TokenKind::If => {
+                self.advance();
+                let cond = Box::new(self.expr(0)?);
+                let then = self.block()?;
+                let otherwise = if self.eat(&TokenKind::Else) {
+                    // else if 就是 else 后面跟着另一个 if 表达式
+                    if self.peek().kind == TokenKind::If {
+                        Some(Box::new(self.block_like()?))
+                    } else {
+                        Some(Box::new(Expr::Block(self.block()?)))
+                    }
+                } else {
+                    None
+                };
+                Ok(Expr::If {
+                    cond,
+                    then,
+                    otherwise,
+                    pos,
+                })
+            }
+            TokenKind::Loop => {
+                self.advance();
+                Ok(Expr::Loop(self.loop_body(LoopKind::Loop)?))
+            }
+            TokenKind::While => {
+                self.advance();
+                let cond = Box::new(self.expr(0)?);
+                let body = self.loop_body(LoopKind::While)?;
+                Ok(Expr::While { cond, body, pos })
+            }
+            TokenKind::For => {
+                self.advance();
+                let var = self.ident()?;
+                self.expect(&TokenKind::In)?;
+                let start = Box::new(self.expr(0)?);
+                self.expect(&TokenKind::DotDot)?;
+                let end = Box::new(self.expr(0)?);
+                let body = self.loop_body(LoopKind::For)?;
+                Ok(Expr::For {
+                    var,
+                    start,
+                    end,
+                    body,
+                    pos,
+                })
+            }
+            _ => Err(self.unexpected("block")),
+        }
+    }
+
+    /// break 后面能不能跟一个值
+    fn starts_expr(&self) -> bool {
+        !matches!(
+            self.peek().kind,
+            TokenKind::Semi | TokenKind::RBrace | TokenKind::RParen | TokenKind::Eof
+        )
+    }
+
+    fn prefix(&mut self) -> Result<Expr, SyntaxError> {
+        if self.starts_block_like() {
+            return self.block_like();
+        }
+        let token = self.advance();
+        Ok(match token.kind {
+            TokenKind::Int(n) => Expr::Int(n),
+            TokenKind::True => Expr::Bool(true),
+            TokenKind::False => Expr::Bool(false),
+            TokenKind::Ident(name) => Expr::Var(name, token.pos),
+            TokenKind::LParen => {
+                if self.eat(&TokenKind::RParen) {
+                    return Ok(Expr::Unit);
+                }
+                let inner = self.expr(0)?;
+                self.expect(&TokenKind::RParen)?;
+                inner
+            }
+            TokenKind::Minus | TokenKind::Bang => {
+                let op = if token.kind == TokenKind::Minus {
+                    UnaryOp::Neg
+                } else {
+                    UnaryOp::Not
+                };
+                Expr::Unary(op, Box::new(self.expr(PREFIX_BP)?), token.pos)
+            }
+            TokenKind::Break => {
+                let value = if self.starts_expr() {
+                    Some(Box::new(self.expr(0)?))
+                } else {
+                    None
+                };
+                match (self.loops.last(), &value) {
+                    (None, _) => {
+                        return Err(SyntaxError::new(token.pos, "`break` outside of a loop"))
+                    }
+                    (Some(LoopKind::While), Some(_)) => {
+                        return Err(SyntaxError::new(
+                            token.pos,
+                            "`break` with value from a `while` loop",
+                        ))
+                    }
+                    (Some(LoopKind::For), Some(_)) => {
+                        return Err(SyntaxError::new(
+                            token.pos,
+                            "`break` with value from a `for` loop",
+                        ))
+                    }
+                    _ => Expr::Break(value),
+                }
+            }
+            TokenKind::Continue => {
+                if self.loops.is_empty() {
+                    return Err(SyntaxError::new(token.pos, "`continue` outside of a loop"));
+                }
+                Expr::Continue
+            }
+            kind => {
+                return Err(SyntaxError::new(
+                    token.pos,
+                    format!("expected expression, found {}", kind),
+                ))
+            }
+        })
+    }
+
+    fn expr(&mut self, min_bp: u8) -> Result<Expr, SyntaxError> {
+        let mut lhs = self.prefix()?;
+        loop {
+            let pos = self.peek().pos;
+            if let Some(op) = assign_op(&self.peek().kind) {
+                if ASSIGN_BP.0 < min_bp {
+                    break;
+                }
+                let Expr::Var(name, _) = lhs else {
+                    return Err(SyntaxError::new(
+                        pos,
+                        "invalid left-hand side of assignment",
+                    ));
+                };
+                self.advance();
+                let value = Box::new(self.expr(ASSIGN_BP.1)?);
+                lhs = Expr::Assign {
+                    name,
+                    op,
+                    value,
+                    pos,
+                };
+                continue;
+            }
+
+            let Some((op, l_bp, r_bp)) = infix_bp(&self.peek().kind) else {
+                break;
+            };
+            if l_bp < min_bp {
+                break;
+            }
+            self.advance();
+            let rhs = self.expr(r_bp)?;
+            if is_comparison(op) {
+                if let Some((next, _, _)) = infix_bp(&self.peek().kind) {
+                    if is_comparison(next) {
+                        return Err(SyntaxError::new(
+                            self.peek().pos,
+                            "comparison operators cannot be chained",
+                        ));
+                    }
+                }
+            }
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs), pos);
+        }
+        Ok(lhs)
+    }
+}
+
+#[test]
+fn test_parse() {
+    use BinaryOp::*;
+
+    let tail = |src: &str| *parse(src).unwrap().tail.unwrap();
+    let bin = |op, l, r, col| Expr::Binary(op, Box::new(l), Box::new(r), Pos { line: 1, col });
+
+    // 乘法先结合，减法左结合
+    assert_eq!(
+        tail("1 + 2 * 3"),
+        bin(
+            Add,
+            Expr::Int(1),
+            bin(Mul, Expr::Int(2), Expr::Int(3), 7),
+            3
+        )
+    );
+    assert_eq!(
+        tail("8 - 4 - 2"),
+        bin(
+            Sub,
+            bin(Sub, Expr::Int(8), Expr::Int(4), 3),
+            Expr::Int(2),
+            7
+        )
+    );
+    assert!(
+        matches!(tail("x = y = 1"), Expr::Assign { value, .. } if matches!(*value, Expr::Assign { .. }))
+    );
+
+    // 语句开头的块状表达式后面不需要分号，也不会和后面的 - 1 组合
+    let program = parse("let x = 1; if x > 0 { x } else { 0 } - 1").unwrap();
+    assert_eq!(program.stmts.len(), 2);
+    assert!(matches!(
+        program.stmts[1],
+        Stmt::BlockLike(Expr::If { .. }, _)
+    ));
+    assert!(matches!(
+        *program.tail.unwrap(),
+        Expr::Unary(UnaryOp::Neg, ..)
+    ));
+
+    let err = |src| parse(src).unwrap_err().to_string();
+    assert_eq!(err("1 + 2 3"), "1:7: expected `;`, found `3`");
+    assert_eq!(err("break 1"), "1:1: `break` outside of a loop");
+    assert_eq!(
+        err("while true { break 1; }"),
+        "1:14: `break` with value from a `while` loop"
+    );
+    assert!(parse("loop { while true { continue } break 1 }").is_ok());
+    assert_eq!(
+        err("1 < 2 < 3"),
+        "1:7: comparison operators cannot be chained"
+    );
+    assert_eq!(err("1 = 2"), "1:3: invalid left-hand side of assignment");
+    assert_eq!(err("let = 1;"), "1:5: expected identifier, found `=`");
+}
@@ -0,0 +1,101 @@
+#![allow(dead_code)]
+
+/*
+ * REPL（Read-Eval-Print Loop）: cargo run -- repl
+ *      >> let mut x = 1;
+ *      >> loop { x *= 2; if x > 100 { break x } }
+ *      128
+ *
+ * 大括号没有闭合时继续读下一行（提示符变成 ".."），攒成一整段再执行。
+ * :vars 列出所有变量，:quit 退出。
+ */
+
+use super::eval::{Interpreter, Value};
+use std::io::{self, BufRead, Write};
+
+/// 还没有闭合的 { 的个数，用来判断一段输入是否完整
+/// NOTE 和词法分析一样跳过 // 注释，注释里的大括号不算数
+fn open_braces(src: &str) -> i32 {
+    src.lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .flat_map(str::chars)
+        .fold(0, |depth, c| match c {
+            '{' => depth + 1,
+            '}' => depth - 1,
+            _ => depth,
+        })
+}
+
+/// 输入和输出都是参数，测试时可以用内存里的缓冲区代替终端
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut interp = Interpreter::new();
+    let mut pending = String::new();
+    loop {
+        let prompt = if pending.is_empty() { ">> " } else { ".. " };
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(());
+        }
+
+        if pending.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ":quit" | ":q" => return Ok(()),
+                ":vars" => {
+                    for (name, value) in interp.globals() {
+                        writeln!(output, "{} = {}", name, value)?;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        pending.push_str(&line);
+        if open_braces(&pending) > 0 {
+            continue;
+        }
+        match interp.eval(&pending) {
+            // 和 Rust 一样，() 不打印
+            Ok(Value::Unit) => {}
+            Ok(value) => writeln!(output, "{}", value)?,
+            Err(err) => writeln!(output, "error: {}", err)?,
+        }
+        pending.clear();
+    }
+}
+
+#[test]
+fn test_repl() {
+    let input = "\
+let mut x = 1;
+loop {
+    x *= 2;
+    if x > 100 { break x }
+}
+let y = x - ;
+x = x + 1;
+:vars
+if x > 0 { 1 } else { 2 }
+x % 100 // { 注释里的大括号
+:quit
+never evaluated
+";
+    let mut output = Vec::new();
+    run(io::Cursor::new(input), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    println!("{}", output);
+    assert_eq!(
+        output,
+        ">> >> .. .. .. 128\n\
+         >> error: syntax error at 1:13: expected expression, found `;`\n\
+         >> >> x = 129\n\
+         >> 1\n\
+         >> 29\n\
+         >> "
+    );
+}
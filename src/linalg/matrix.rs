@@ -0,0 +1,303 @@
+#![allow(dead_code)]
+
+/*
+ * 固定大小的矩阵: Matrix<T, R, C>
+ *      数组 [T; N] 的长度 N 是类型的一部分，[i32; 3] 和 [i32; 4] 是两个不同的类型。
+ *      const 泛型让我们自己的类型也能带上长度: Matrix<f64, 2, 3> 就是 "2 行 3 列的 f64 矩阵"。
+ *
+ * 好处是形状错误在编译期就被发现:
+ *      加法要求两个矩阵形状完全相同    Matrix<T, R, C> + Matrix<T, R, C>
+ *      乘法要求左边的列数等于右边的行数  Matrix<T, R, K> * Matrix<T, K, C> -> Matrix<T, R, C>
+ *      转置交换行列                    Matrix<T, R, C> -> Matrix<T, C, R>
+ *
+ * 数据直接存在 [[T; C]; R] 里，没有堆分配，整个矩阵可以 Copy。
+ */
+
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+/// 矩阵元素需要的运算，以及 0 和 1（单位矩阵要用到）
+pub trait Scalar:
+    Copy + PartialEq + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty => $zero:expr, $one:expr;)*) => {
+        $(impl Scalar for $t {
+            const ZERO: Self = $zero;
+            const ONE: Self = $one;
+        })*
+    };
+}
+
+impl_scalar! {
+    i32 => 0, 1;
+    i64 => 0, 1;
+    i128 => 0, 1;
+    u32 => 0, 1;
+    u64 => 0, 1;
+    usize => 0, 1;
+    f32 => 0.0, 1.0;
+    f64 => 0.0, 1.0;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix<T, const R: usize, const C: usize> {
+    data: [[T; C]; R],
+}
+
+impl<T, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub const ROWS: usize = R;
+    pub const COLS: usize = C;
+
+    pub fn new(data: [[T; C]; R]) -> Self {
+        Matrix { data }
+    }
+
+    /// 用 f(行, 列) 生成每个元素
+    pub fn from_fn(mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Matrix {
+            data: std::array::from_fn(|i| std::array::from_fn(|j| f(i, j))),
+        }
+    }
+
+    pub fn rows(&self) -> &[[T; C]; R] {
+        &self.data
+    }
+
+    pub fn row(&self, i: usize) -> &[T; C] {
+        &self.data[i]
+    }
+
+    pub fn into_inner(self) -> [[T; C]; R] {
+        self.data
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub fn zeros() -> Self {
+        Matrix {
+            data: [[T::ZERO; C]; R],
+        }
+    }
+
+    pub fn column(&self, j: usize) -> [T; R] {
+        std::array::from_fn(|i| self.data[i][j])
+    }
+
+    pub fn transpose(&self) -> Matrix<T, C, R> {
+        Matrix::from_fn(|i, j| self.data[j][i])
+    }
+
+    pub fn scale(&self, k: T) -> Self {
+        Matrix::from_fn(|i, j| self.data[i][j] * k)
+    }
+
+    /// 矩阵乘以列向量，向量的长度必须等于列数
+    pub fn mul_vec(&self, v: &[T; C]) -> [T; R] {
+        std::array::from_fn(|i| dot(&self.data[i], v))
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(T) -> U) -> Matrix<U, R, C> {
+        Matrix::from_fn(|i, j| f(self.data[i][j]))
+    }
+}
+
+/// 只有方阵才有单位矩阵、迹和幂: 把 R 和 C 写成同一个参数 N
+impl<T: Scalar, const N: usize> Matrix<T, N, N> {
+    pub fn identity() -> Self {
+        Matrix::from_fn(|i, j| if i == j { T::ONE } else { T::ZERO })
+    }
+
+    pub fn trace(&self) -> T {
+        (0..N).fold(T::ZERO, |acc, i| acc + self.data[i][i])
+    }
+
+    /// 快速幂，和 bigint 里的 pow_mod 是同一个思路
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut base = *self;
+        let mut result = Self::identity();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            exp >>= 1;
+            // NOTE 最后一位用完就不再平方，否则结果放得下时也可能因为多余的平方而溢出
+            if exp > 0 {
+                base = base * base;
+            }
+        }
+        result
+    }
+}
+
+impl<T: Scalar> Matrix<T, 2, 2> {
+    pub fn determinant(&self) -> T {
+        let [[a, b], [c, d]] = self.data;
+        a * d - b * c
+    }
+}
+
+impl<T: Scalar> Matrix<T, 3, 3> {
+    /// 按第一行展开
+    pub fn determinant(&self) -> T {
+        let [[a, b, c], [d, e, f], [g, h, i]] = self.data;
+        a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g)
+    }
+}
+
+fn dot<T: Scalar, const N: usize>(a: &[T; N], b: &[T; N]) -> T {
+    a.iter().zip(b).fold(T::ZERO, |acc, (&x, &y)| acc + x * y)
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Default for Matrix<T, R, C> {
+    fn default() -> Self {
+        Self::zeros()
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Add for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Matrix::from_fn(|i, j| self.data[i][j] + rhs.data[i][j])
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Sub for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Matrix::from_fn(|i, j| self.data[i][j] - rhs.data[i][j])
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>, const R: usize, const C: usize> Neg for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(|x| -x)
+    }
+}
+
+/*
+ * 形状不匹配的乘法根本找不到对应的 impl，所以无法编译:
+ *      let a: Matrix<i32, 2, 3> = Matrix::zeros();
+ *      let b: Matrix<i32, 2, 3> = Matrix::zeros();
+ *      let c = a * b;
+ * NOTE cannot multiply `Matrix<i32, 2, 3>` by `Matrix<i32, 2, 3>`
+ *      the trait `Mul<Matrix<i32, 2, 3>>` is not implemented for `Matrix<i32, 2, 3>`
+ *      but trait `Mul<Matrix<i32, 3, _>>` is implemented for it
+ */
+impl<T: Scalar, const R: usize, const K: usize, const C: usize> Mul<Matrix<T, K, C>>
+    for Matrix<T, R, K>
+{
+    type Output = Matrix<T, R, C>;
+
+    fn mul(self, rhs: Matrix<T, K, C>) -> Matrix<T, R, C> {
+        Matrix::from_fn(|i, j| {
+            (0..K).fold(T::ZERO, |acc, k| acc + self.data[i][k] * rhs.data[k][j])
+        })
+    }
+}
+
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.data[i][j]
+    }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.data[i][j]
+    }
+}
+
+impl<T, const R: usize, const C: usize> From<[[T; C]; R]> for Matrix<T, R, C> {
+    fn from(data: [[T; C]; R]) -> Self {
+        Matrix { data }
+    }
+}
+
+/// 每列右对齐到同一宽度，宽度和精度参数会传给每个元素: {:6.2}
+impl<T: fmt::Display, const R: usize, const C: usize> fmt::Display for Matrix<T, R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<Vec<String>> = self
+            .data
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|x| match f.precision() {
+                        Some(p) => format!("{:.*}", p, x),
+                        None => x.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = cells
+            .iter()
+            .flatten()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(f.width().unwrap_or(0));
+        for (i, row) in cells.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[")?;
+            for (j, cell) in row.iter().enumerate() {
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:>width$}", cell)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_matrix() {
+    let a = Matrix::new([[1, 2, 3], [4, 5, 6]]);
+    let b: Matrix<i32, 3, 2> = a.transpose();
+    assert_eq!(b, Matrix::new([[1, 4], [2, 5], [3, 6]]));
+    assert_eq!(Matrix::<i32, 2, 3>::ROWS, 2);
+
+    // 2x3 乘 3x2 得到 2x2，结果的类型由编译器推导出来
+    let c = a * b;
+    assert_eq!(c, Matrix::new([[14, 32], [32, 77]]));
+    assert_eq!(c.trace(), 91);
+    assert_eq!(c.determinant(), 14 * 77 - 32 * 32);
+    assert_eq!(a.mul_vec(&[1, 0, -1]), [-2, -2]);
+    assert_eq!(a.column(1), [2, 5]);
+    assert_eq!((a + a - a.scale(2)), Matrix::zeros());
+    assert_eq!(-a, a.scale(-1));
+
+    let i3 = Matrix::<i32, 3, 3>::identity();
+    assert_eq!(b * Matrix::identity(), b);
+    assert_eq!(i3 * b, b);
+    assert_eq!(i3.determinant(), 1);
+
+    // 斐波那契: [[1, 1], [1, 0]]^n 的右上角是 F(n)
+    let fib = Matrix::new([[1u64, 1], [1, 0]]).pow(50);
+    assert_eq!(fib[(0, 1)], 12_586_269_025);
+    // 边界: M^92 的左上角是 F(93)，u64 能放下的最大斐波那契数，多平方一次就会溢出
+    let fib = Matrix::new([[1u64, 1], [1, 0]]).pow(92);
+    assert_eq!(fib[(0, 0)], 12_200_160_415_121_876_738);
+    assert_eq!(Matrix::new([[50_000i32]]).pow(1), Matrix::new([[50_000]]));
+    assert_eq!(Matrix::new([[2i32]]).pow(30), Matrix::new([[1 << 30]]));
+
+    // 旋转 90 度两次等于旋转 180 度
+    let rot = Matrix::new([[0.0, -1.0], [1.0, 0.0]]);
+    assert_eq!(rot * rot, Matrix::<f64, 2, 2>::identity().scale(-1.0));
+    let m = Matrix::new([[1.5, -2.0], [10.25, 0.0]]);
+    println!("{:.2}", m);
+    assert_eq!(m.to_string(), "[  1.5    -2]\n[10.25     0]");
+}
@@ -0,0 +1,4 @@
+pub(crate) mod matrix;
+pub(crate) mod ndarray;
+
+// NOTE Matrix 的形状写在类型里，编译期检查；NdArray 的形状是运行时的值，出错时返回 ShapeError
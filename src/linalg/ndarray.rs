@@ -0,0 +1,473 @@
+#![allow(dead_code)]
+
+/*
+ * N 维数组: NdArray<T>
+ *      Matrix 的形状在编译期就确定了，但很多时候维数和大小要到运行时才知道（读入的数据、用户的输入），
+ *      这时只能把元素放在堆上的 Vec 里，形状作为普通的值保存。
+ *
+ * 步长（strides）
+ *      所有元素按行优先（最后一维变化最快）连续存放，形状为 [2, 3, 4] 的数组步长是 [12, 4, 1]:
+ *      下标 [i, j, k] 对应 data[i * 12 + j * 4 + k]。
+ *
+ * 视图（NdView）
+ *      切片、按步长取元素、转置都不需要复制数据，只要修改 起始偏移、形状、步长 三样东西:
+ *          slice(axis, a..b)   偏移 += a * stride[axis]，shape[axis] = b - a
+ *          step(axis, n)       stride[axis] *= n，shape[axis] 变为原来的 1/n（向上取整）
+ *          index_axis(axis, i) 偏移 += i * stride[axis]，去掉这一维
+ *          transpose()         形状和步长都反过来
+ *      和 &s[1..3] 借用 String 的一部分是同一个道理，视图的生命周期不能超过原数组。
+ */
+
+use super::matrix::Matrix;
+use crate::error::DomainError;
+use std::fmt;
+use std::ops::{Index, IndexMut, Range};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeError {
+    /// 元素个数和形状不符
+    SizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    AxisOutOfRange {
+        axis: usize,
+        ndim: usize,
+    },
+    IndexOutOfRange {
+        axis: usize,
+        index: usize,
+        len: usize,
+    },
+    ZeroStep,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::SizeMismatch { expected, actual } => {
+                write!(f, "shape needs {} elements, got {}", expected, actual)
+            }
+            ShapeError::AxisOutOfRange { axis, ndim } => {
+                write!(f, "axis {} out of range for {}-d array", axis, ndim)
+            }
+            ShapeError::IndexOutOfRange { axis, index, len } => {
+                write!(
+                    f,
+                    "index {} out of range for axis {} of length {}",
+                    index, axis, len
+                )
+            }
+            ShapeError::ZeroStep => write!(f, "step must be positive"),
+        }
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+impl DomainError for ShapeError {
+    const CODE: u16 = 3004;
+}
+
+/// 行优先的步长: 最后一维是 1，往前每一维乘上后面那一维的长度
+fn row_major_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for axis in (0..shape.len().saturating_sub(1)).rev() {
+        strides[axis] = strides[axis + 1] * shape[axis + 1];
+    }
+    strides
+}
+
+/// 下标合法时返回它在 data 中相对起始偏移的位置
+fn offset_of(shape: &[usize], strides: &[usize], index: &[usize]) -> Option<usize> {
+    if index.len() != shape.len() {
+        return None;
+    }
+    let mut offset = 0;
+    for ((&i, &len), &stride) in index.iter().zip(shape).zip(strides) {
+        if i >= len {
+            return None;
+        }
+        offset += i * stride;
+    }
+    Some(offset)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NdArray<T> {
+    data: Vec<T>,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+}
+
+impl<T> NdArray<T> {
+    pub fn from_vec(shape: &[usize], data: Vec<T>) -> Result<Self, ShapeError> {
+        let expected = shape.iter().product();
+        if data.len() != expected {
+            return Err(ShapeError::SizeMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(NdArray {
+            data,
+            shape: shape.to_vec(),
+            strides: row_major_strides(shape),
+        })
+    }
+
+    /// 按行优先的顺序，用每个元素的下标生成它的值
+    pub fn from_fn(shape: &[usize], mut f: impl FnMut(&[usize]) -> T) -> Self {
+        let len = shape.iter().product();
+        let mut data = Vec::with_capacity(len);
+        let mut index = vec![0; shape.len()];
+        for _ in 0..len {
+            data.push(f(&index));
+            increment(&mut index, shape);
+        }
+        NdArray {
+            data,
+            strides: row_major_strides(shape),
+            shape: shape.to_vec(),
+        }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 按行优先顺序的全部元素
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn get(&self, index: &[usize]) -> Option<&T> {
+        offset_of(&self.shape, &self.strides, index).map(|i| &self.data[i])
+    }
+
+    pub fn get_mut(&mut self, index: &[usize]) -> Option<&mut T> {
+        offset_of(&self.shape, &self.strides, index).map(|i| &mut self.data[i])
+    }
+
+    /// 数据是连续的，改变形状只需要重新计算步长，元素个数必须不变
+    pub fn reshape(self, shape: &[usize]) -> Result<Self, ShapeError> {
+        NdArray::from_vec(shape, self.data)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> NdArray<U> {
+        NdArray {
+            data: self.data.iter().map(f).collect(),
+            shape: self.shape.clone(),
+            strides: self.strides.clone(),
+        }
+    }
+
+    pub fn view(&self) -> NdView<'_, T> {
+        NdView {
+            data: &self.data,
+            offset: 0,
+            shape: self.shape.clone(),
+            strides: self.strides.clone(),
+        }
+    }
+
+    pub fn slice(&self, axis: usize, range: Range<usize>) -> Result<NdView<'_, T>, ShapeError> {
+        self.view().slice(axis, range)
+    }
+}
+
+impl<T: Clone> NdArray<T> {
+    pub fn from_elem(shape: &[usize], value: T) -> Self {
+        let len = shape.iter().product();
+        NdArray {
+            data: vec![value; len],
+            shape: shape.to_vec(),
+            strides: row_major_strides(shape),
+        }
+    }
+}
+
+impl<T: Clone + Default> NdArray<T> {
+    pub fn zeros(shape: &[usize]) -> Self {
+        NdArray::from_elem(shape, T::default())
+    }
+}
+
+impl<T> Index<&[usize]> for NdArray<T> {
+    type Output = T;
+
+    fn index(&self, index: &[usize]) -> &T {
+        match self.get(index) {
+            Some(x) => x,
+            None => panic!("index {:?} out of bounds for shape {:?}", index, self.shape),
+        }
+    }
+}
+
+impl<T> IndexMut<&[usize]> for NdArray<T> {
+    fn index_mut(&mut self, index: &[usize]) -> &mut T {
+        let shape = self.shape.clone();
+        match self.get_mut(index) {
+            Some(x) => x,
+            None => panic!("index {:?} out of bounds for shape {:?}", index, shape),
+        }
+    }
+}
+
+/// 编译期形状变成运行时形状，反过来则需要检查，所以只提供这一个方向的 From
+impl<T, const R: usize, const C: usize> From<Matrix<T, R, C>> for NdArray<T> {
+    fn from(m: Matrix<T, R, C>) -> Self {
+        let data = m.into_inner().into_iter().flatten().collect();
+        NdArray {
+            data,
+            shape: vec![R, C],
+            strides: vec![C, 1],
+        }
+    }
+}
+
+/// 多维下标按行优先的顺序加 1，最后一维进位到前一维
+fn increment(index: &mut [usize], shape: &[usize]) {
+    for axis in (0..index.len()).rev() {
+        index[axis] += 1;
+        if index[axis] < shape[axis] {
+            return;
+        }
+        index[axis] = 0;
+    }
+}
+
+/// 借用 NdArray 的一部分，不拥有数据
+#[derive(Debug)]
+pub struct NdView<'a, T> {
+    data: &'a [T],
+    offset: usize,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+}
+
+// NOTE 不用 derive(Clone): derive 会要求 T: Clone，但复制视图只是复制一个引用，和 T 无关
+impl<T> Clone for NdView<'_, T> {
+    fn clone(&self) -> Self {
+        NdView {
+            data: self.data,
+            offset: self.offset,
+            shape: self.shape.clone(),
+            strides: self.strides.clone(),
+        }
+    }
+}
+
+impl<'a, T> NdView<'a, T> {
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 视图是否还是行优先连续的（切片、转置之后通常就不是了）
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == row_major_strides(&self.shape)
+    }
+
+    /// 返回的引用的生命周期是原数组的 'a，而不是视图自己的
+    pub fn get(&self, index: &[usize]) -> Option<&'a T> {
+        offset_of(&self.shape, &self.strides, index).map(|i| &self.data[self.offset + i])
+    }
+
+    fn check_axis(&self, axis: usize) -> Result<(), ShapeError> {
+        if axis < self.ndim() {
+            Ok(())
+        } else {
+            Err(ShapeError::AxisOutOfRange {
+                axis,
+                ndim: self.ndim(),
+            })
+        }
+    }
+
+    pub fn slice(mut self, axis: usize, range: Range<usize>) -> Result<Self, ShapeError> {
+        self.check_axis(axis)?;
+        let len = self.shape[axis];
+        if range.start > range.end || range.end > len {
+            return Err(ShapeError::IndexOutOfRange {
+                axis,
+                index: range.end.max(range.start),
+                len,
+            });
+        }
+        self.offset += range.start * self.strides[axis];
+        self.shape[axis] = range.end - range.start;
+        Ok(self)
+    }
+
+    /// 每隔 step 个取一个，相当于 Python 的 a[::step]
+    pub fn step(mut self, axis: usize, step: usize) -> Result<Self, ShapeError> {
+        self.check_axis(axis)?;
+        if step == 0 {
+            return Err(ShapeError::ZeroStep);
+        }
+        self.shape[axis] = self.shape[axis].div_ceil(step);
+        self.strides[axis] *= step;
+        Ok(self)
+    }
+
+    /// 固定某一维的下标，得到少一维的视图: 矩阵的第 i 行、第 j 列
+    pub fn index_axis(mut self, axis: usize, index: usize) -> Result<Self, ShapeError> {
+        self.check_axis(axis)?;
+        let len = self.shape[axis];
+        if index >= len {
+            return Err(ShapeError::IndexOutOfRange { axis, index, len });
+        }
+        self.offset += index * self.strides[axis];
+        self.shape.remove(axis);
+        self.strides.remove(axis);
+        Ok(self)
+    }
+
+    pub fn transpose(mut self) -> Self {
+        self.shape.reverse();
+        self.strides.reverse();
+        self
+    }
+
+    /// 按视图的逻辑顺序（行优先）遍历，而不是底层存储的顺序
+    pub fn iter(&self) -> NdIter<'a, T> {
+        NdIter {
+            remaining: self.len(),
+            index: vec![0; self.ndim()],
+            view: self.clone(),
+        }
+    }
+}
+
+impl<T: Clone> NdView<'_, T> {
+    /// 复制成一个新的连续数组
+    pub fn to_owned(&self) -> NdArray<T> {
+        NdArray {
+            data: self.iter().cloned().collect(),
+            shape: self.shape.clone(),
+            strides: row_major_strides(&self.shape),
+        }
+    }
+}
+
+pub struct NdIter<'a, T> {
+    view: NdView<'a, T>,
+    index: Vec<usize>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for NdIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let item = self.view.get(&self.index);
+        increment(&mut self.index, &self.view.shape);
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for NdIter<'_, T> {}
+
+#[test]
+fn test_ndarray() {
+    // a[i][j][k] = 100i + 10j + k
+    let a = NdArray::from_fn(&[2, 3, 4], |idx| idx[0] * 100 + idx[1] * 10 + idx[2]);
+    assert_eq!(a.strides(), [12, 4, 1]);
+    assert_eq!(a[&[1, 2, 3][..]], 123);
+    assert_eq!(a.get(&[1, 3, 0]), None);
+    assert_eq!(a.get(&[1, 2]), None);
+
+    // 第 1 个 "面" 的第 1..3 行，每隔一列取一个
+    let v = a
+        .view()
+        .index_axis(0, 1)
+        .unwrap()
+        .slice(0, 1..3)
+        .unwrap()
+        .step(1, 2)
+        .unwrap();
+    assert_eq!(v.shape(), [2, 2]);
+    assert_eq!(v.strides(), [4, 2]);
+    assert!(!v.is_contiguous());
+    assert_eq!(v.iter().copied().collect::<Vec<_>>(), [110, 112, 120, 122]);
+    assert_eq!(
+        v.clone().transpose().to_owned().into_vec(),
+        [110, 120, 112, 122]
+    );
+
+    assert_eq!(
+        a.slice(1, 2..4).unwrap_err(),
+        ShapeError::IndexOutOfRange {
+            axis: 1,
+            index: 4,
+            len: 3
+        }
+    );
+    assert_eq!(
+        a.view().index_axis(3, 0).unwrap_err().to_string(),
+        "axis 3 out of range for 3-d array"
+    );
+
+    let b = a.clone().reshape(&[6, 4]).unwrap();
+    assert_eq!(b[&[5, 3][..]], 123);
+    assert_eq!(
+        a.reshape(&[5, 5]).unwrap_err(),
+        ShapeError::SizeMismatch {
+            expected: 25,
+            actual: 24
+        }
+    );
+
+    let mut m: NdArray<i32> = Matrix::new([[1, 2, 3], [4, 5, 6]]).into();
+    m[&[0, 0][..]] = 10;
+    assert_eq!(m.shape(), [2, 3]);
+    assert_eq!(
+        m.view().transpose().to_owned().as_slice(),
+        [10, 4, 2, 5, 3, 6]
+    );
+    assert_eq!(NdArray::<f64>::zeros(&[3, 3]).len(), 9);
+}
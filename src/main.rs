@@ -15,6 +15,32 @@ mod test_generic;
 mod test_lifecycle;
 mod test_trait;
 
+mod abi;
+mod bigint;
+mod bridge;
+mod chain;
+mod crypto;
+mod error;
+mod evm;
+mod finance;
+mod interp;
+mod linalg;
+mod merkle;
+mod my_string;
+mod open_map;
+mod p2p;
+mod panic_lab;
+mod retry;
+mod rlp;
+mod solana;
+mod state;
+mod text;
+#[cfg(test)]
+mod testing;
+mod vfs;
+mod wallet;
+mod wordfreq;
+
 /**
  * 区块链
  * Ethereum
@@ -43,6 +69,14 @@ mod test_trait;
  * 文档注释，在定义的函数，类等之上时， rustdoc才会生效，在语句块中rustdoc是不会生产文档的。
  */
 fn main() {
+    // cargo run -- repl: 启动表达式语言的交互式解释器，见 interp/
+    if std::env::args().nth(1).as_deref() == Some("repl") {
+        if let Err(err) = interp::repl::run(std::io::stdin().lock(), std::io::stdout()) {
+            eprintln!("repl: {}", err);
+        }
+        return;
+    }
+
     // Tip：Cargo 具有 cargo doc 功能，开发者可以通过这个命令将工程中的说明注释转换成 HTML 格式的说明文档。
 
     // 普通单行注释
@@ -0,0 +1,277 @@
+#![allow(dead_code)]
+
+/*
+ * 默克尔树（Merkle Tree）
+ *      把一组交易哈希两两配对向上求哈希，最终得到一个根哈希（Merkle Root）写进区块头。
+ *      任意一笔交易被修改，根哈希都会改变。
+ *
+ * 包含证明（Inclusion Proof）
+ *      轻节点（Light Client）只保存区块头，不下载全部交易。想确认某笔交易在区块里，
+ *      只需要全节点提供从这笔交易到根的路径上的 "兄弟节点"，共 log2(n) 个哈希，
+ *      轻节点沿路径重新计算出根，和区块头中的根比较即可。
+ *
+ * 两个细节:
+ *      1. 叶子和内部节点使用不同的前缀（0x00 / 0x01）再求哈希，
+ *         否则攻击者可以把一个内部节点冒充成叶子（第二原像攻击）。
+ *      2. 节点数为奇数时，最后一个节点直接提升到上一层。
+ *         比特币的做法是复制最后一个节点，但这会让 [a, b, c] 和 [a, b, c, c] 得到相同的根（CVE-2012-2459）。
+ */
+
+use crate::chain::Hash;
+use crate::crypto::hash::Sha256;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn hash_leaf(data: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+pub fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize()
+}
+
+/// 兄弟节点在路径上的哪一侧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    /// 从叶子一层往上，每一层的兄弟节点；被直接提升的那一层没有兄弟
+    pub path: Vec<(Side, Hash)>,
+}
+
+impl MerkleProof {
+    /// 沿路径重新计算根
+    pub fn compute_root(&self, leaf: &Hash) -> Hash {
+        let mut acc = hash_leaf(leaf);
+        for (side, sibling) in &self.path {
+            acc = match side {
+                Side::Left => hash_node(sibling, &acc),
+                Side::Right => hash_node(&acc, sibling),
+            };
+        }
+        acc
+    }
+
+    pub fn verify(&self, root: &Hash, leaf: &Hash) -> bool {
+        self.compute_root(leaf) == *root
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTree {
+    // NOTE levels[0] 是叶子层的哈希，最后一层只有一个元素，就是根
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    pub fn new() -> MerkleTree {
+        MerkleTree { levels: Vec::new() }
+    }
+
+    pub fn from_leaves(leaves: &[Hash]) -> MerkleTree {
+        let mut tree = MerkleTree::new();
+        for leaf in leaves {
+            tree.push(*leaf);
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, |level| level.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 空树的根约定为全 0
+    pub fn root(&self) -> Hash {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or([0; 32])
+    }
+
+    /// 增量追加一个叶子: 只需要重新计算最右侧的一条路径，O(log n)
+    pub fn push(&mut self, leaf: Hash) {
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].push(hash_leaf(&leaf));
+
+        let mut depth = 0;
+        while self.levels[depth].len() > 1 {
+            let level = &self.levels[depth];
+            let last = level.len() - 1;
+            let parent = if last.is_multiple_of(2) {
+                // 落单的节点直接提升
+                level[last]
+            } else {
+                hash_node(&level[last - 1], &level[last])
+            };
+
+            if self.levels.len() == depth + 1 {
+                self.levels.push(Vec::new());
+            }
+            let upper = &mut self.levels[depth + 1];
+            let parent_index = last / 2;
+            if parent_index < upper.len() {
+                upper[parent_index] = parent;
+            } else {
+                upper.push(parent);
+            }
+            depth += 1;
+        }
+    }
+
+    pub fn proof(&self, leaf_index: usize) -> Option<MerkleProof> {
+        if leaf_index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut index = leaf_index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                let side = if sibling < index {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                path.push((side, level[sibling]));
+            }
+            index /= 2;
+        }
+        Some(MerkleProof { leaf_index, path })
+    }
+}
+
+#[cfg(test)]
+fn tx_hashes(n: usize) -> Vec<Hash> {
+    (0..n)
+        .map(|i| crate::crypto::hash::sha256(format!("tx-{}", i).as_bytes()))
+        .collect()
+}
+
+#[test]
+fn test_root_and_odd_leaves() {
+    let txs = tx_hashes(3);
+    let tree = MerkleTree::from_leaves(&txs);
+
+    // 手工计算: c 落单直接提升
+    let ab = hash_node(&hash_leaf(&txs[0]), &hash_leaf(&txs[1]));
+    let expected = hash_node(&ab, &hash_leaf(&txs[2]));
+    assert_eq!(tree.root(), expected);
+
+    // NOTE 复制最后一个叶子不会得到同样的根
+    let mut duplicated = txs.clone();
+    duplicated.push(txs[2]);
+    assert_ne!(MerkleTree::from_leaves(&duplicated).root(), tree.root());
+
+    assert_eq!(MerkleTree::new().root(), [0; 32]);
+    assert_eq!(
+        MerkleTree::from_leaves(&txs[..1]).root(),
+        hash_leaf(&txs[0])
+    );
+}
+
+#[test]
+fn test_incremental_append() {
+    // 独立的参照实现: 一次算出一整层，两两配对，落单的节点直接提升
+    fn batch_root(leaves: &[Hash]) -> Hash {
+        let mut level: Vec<Hash> = leaves.iter().map(hash_leaf).collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        level.first().copied().unwrap_or([0; 32])
+    }
+
+    // 逐个追加和一次性构建得到同样的根
+    let txs = tx_hashes(37);
+    let mut tree = MerkleTree::new();
+    for (i, tx) in txs.iter().enumerate() {
+        tree.push(*tx);
+        assert_eq!(
+            tree.root(),
+            batch_root(&txs[..=i]),
+            "after {} leaves",
+            i + 1
+        );
+    }
+    assert_eq!(tree.len(), 37);
+}
+
+#[test]
+fn test_inclusion_proofs() {
+    for n in [1, 2, 3, 5, 8, 13] {
+        let txs = tx_hashes(n);
+        let tree = MerkleTree::from_leaves(&txs);
+        let root = tree.root();
+        for (i, tx) in txs.iter().enumerate() {
+            let proof = tree.proof(i).unwrap();
+            assert!(proof.verify(&root, tx), "n={} i={}", n, i);
+            // 路径长度不超过树高
+            assert!(proof.path.len() <= usize::BITS as usize - n.leading_zeros() as usize);
+        }
+        assert!(tree.proof(n).is_none());
+    }
+}
+
+#[test]
+fn test_proof_tampering() {
+    let txs = tx_hashes(6);
+    let tree = MerkleTree::from_leaves(&txs);
+    let root = tree.root();
+    let proof = tree.proof(2).unwrap();
+    assert!(proof.verify(&root, &txs[2]));
+
+    // 用别的交易冒充
+    assert!(!proof.verify(&root, &txs[3]));
+
+    // 篡改兄弟节点
+    let mut forged = proof.clone();
+    forged.path[0].1[0] ^= 1;
+    assert!(!forged.verify(&root, &txs[2]));
+
+    // 交换左右位置
+    let mut forged = proof.clone();
+    forged.path[1].0 = match forged.path[1].0 {
+        Side::Left => Side::Right,
+        Side::Right => Side::Left,
+    };
+    assert!(!forged.verify(&root, &txs[2]));
+
+    // 去掉一层
+    let mut forged = proof.clone();
+    forged.path.pop();
+    assert!(!forged.verify(&root, &txs[2]));
+
+    // NOTE 把内部节点当成叶子: 因为叶子和节点的前缀不同，这种证明无法通过
+    let inner = hash_node(&hash_leaf(&txs[0]), &hash_leaf(&txs[1]));
+    let shortened = MerkleProof {
+        leaf_index: 0,
+        path: tree.proof(0).unwrap().path[1..].to_vec(),
+    };
+    assert!(!shortened.verify(&root, &inner));
+}
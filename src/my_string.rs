@@ -0,0 +1,412 @@
+#![allow(dead_code)]
+
+/*
+ * 自己实现一个 String
+ *      test_collections.rs 的 test_slice 里提到: String 有 起始位置、长度、容量 三个属性，str 只有 起始位置、长度。
+ *      这里用裸指针和 std::alloc 把这三个字段亲手写一遍:
+ *
+ *          MyString (栈上 3 个字)              堆
+ *          +-----+-----+-----+               +---+---+---+---+---+---+---+---+
+ *          | ptr | len | cap |  ---------->  | h | e | l | l | o |   |   |   |
+ *          +-----+-----+-----+               +---+---+---+---+---+---+---+---+
+ *                  5     8                    |<----- len ----->|
+ *                                             |<------------- cap ------------>|
+ *
+ *          MyStr (栈上 2 个字，借用别人的内存，不负责释放)
+ *          +-----+-----+
+ *          | ptr | len |
+ *          +-----+-----+
+ *
+ * 增长策略
+ *      MyString 的容量不够时翻倍（至少 8 字节），n 次追加总共只拷贝 O(n) 个字节，均摊 O(1)。
+ *      如果每次只重新分配正好够用的内存，追加 n 个字符就要拷贝 O(n^2) 个字节。
+ *      标准库的 String 也是按倍数增长，但具体的倍数和最小容量没有写进文档，随时可能调整。
+ *
+ * NOTE unsafe 块只出现在分配、释放和 "这段字节是合法的 UTF-8" 这几个地方，
+ * 对外暴露的方法都是安全的: 只能追加 &str 和 char，所以缓冲区里永远是合法的 UTF-8
+ */
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::{self, NonNull};
+
+/// 第一次分配的最小容量，太小的分配很快又要扩容
+const MIN_CAPACITY: usize = 8;
+
+/// 增长策略: 翻倍，且不小于需要的容量和最小容量
+pub fn next_capacity(capacity: usize, required: usize) -> usize {
+    capacity.saturating_mul(2).max(required).max(MIN_CAPACITY)
+}
+
+pub struct MyString {
+    ptr: NonNull<u8>,
+    len: usize,
+    cap: usize,
+}
+
+// SAFETY: MyString 独占它指向的堆内存，和 String 一样可以在线程间转移和共享
+unsafe impl Send for MyString {}
+unsafe impl Sync for MyString {}
+
+impl MyString {
+    /// 空字符串不分配内存，指针是一个 "悬空" 但对齐的非空值
+    pub const fn new() -> MyString {
+        MyString {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> MyString {
+        let mut s = MyString::new();
+        if capacity > 0 {
+            s.realloc(capacity);
+        }
+        s
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: ptr 指向 len 个已初始化的字节（len 为 0 时悬空指针也是合法的空切片），
+        // 而且只通过 push_str 写入过合法的 UTF-8
+        unsafe {
+            let bytes = std::slice::from_raw_parts(self.ptr.as_ptr(), self.len);
+            std::str::from_utf8_unchecked(bytes)
+        }
+    }
+
+    /// 借出一个只有 ptr 和 len 的视图
+    pub fn as_my_str(&self) -> MyStr<'_> {
+        MyStr::from(self.as_str())
+    }
+
+    /// 保证还能再追加 additional 个字节而不重新分配
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required > self.cap {
+            self.realloc(next_capacity(self.cap, required));
+        }
+    }
+
+    /// 把容量缩小到正好等于长度
+    pub fn shrink_to_fit(&mut self) {
+        if self.cap > self.len {
+            self.realloc(self.len);
+        }
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.reserve(s.len());
+        // SAFETY: reserve 之后 [len, len + s.len()) 在已分配的范围内，且和 s 不可能重叠（s 是 &str，self 是 &mut）
+        unsafe {
+            ptr::copy_nonoverlapping(s.as_ptr(), self.ptr.as_ptr().add(self.len), s.len());
+        }
+        self.len += s.len();
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        // 只改长度，不释放内存，容量不变
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// 把容量改成 new_cap，new_cap 为 0 时释放内存
+    fn realloc(&mut self, new_cap: usize) {
+        debug_assert!(new_cap >= self.len);
+        let new_ptr = if new_cap == 0 {
+            NonNull::dangling()
+        } else {
+            let new_layout = Layout::array::<u8>(new_cap).expect("capacity overflow");
+            // SAFETY: new_layout 的大小不为 0；旧的指针和 layout 正是上一次分配时用的
+            let raw = unsafe {
+                if self.cap == 0 {
+                    alloc::alloc(new_layout)
+                } else {
+                    alloc::realloc(self.ptr.as_ptr(), self.layout(), new_cap)
+                }
+            };
+            // 分配失败时按标准库的做法中止程序，而不是返回空指针让后面的代码踩内存
+            NonNull::new(raw).unwrap_or_else(|| alloc::handle_alloc_error(new_layout))
+        };
+        if new_cap == 0 && self.cap > 0 {
+            // SAFETY: 同上
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout()) };
+        }
+        self.ptr = new_ptr;
+        self.cap = new_cap;
+    }
+
+    fn layout(&self) -> Layout {
+        Layout::array::<u8>(self.cap).unwrap()
+    }
+}
+
+impl Drop for MyString {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            // SAFETY: cap > 0 说明 ptr 是用这个 layout 分配的，且之后不会再被使用
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout()) };
+        }
+    }
+}
+
+/// 和 String 一样，MyString 可以在所有需要 &str 的地方使用: &my_string、my_string.chars() ...
+impl Deref for MyString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Default for MyString {
+    fn default() -> Self {
+        MyString::new()
+    }
+}
+
+/// 深拷贝: 新的字符串拥有自己的堆内存，容量正好等于长度
+impl Clone for MyString {
+    fn clone(&self) -> MyString {
+        MyString::from(self.as_str())
+    }
+}
+
+impl From<&str> for MyString {
+    fn from(s: &str) -> MyString {
+        let mut out = MyString::with_capacity(s.len());
+        out.push_str(s);
+        out
+    }
+}
+
+impl PartialEq for MyString {
+    fn eq(&self, other: &MyString) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for MyString {}
+
+impl PartialEq<str> for MyString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for MyString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for MyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for MyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// 借用的字符串视图，相当于 &str: 只有指针和长度，没有容量，也不负责释放
+#[derive(Clone, Copy)]
+pub struct MyStr<'a> {
+    ptr: NonNull<u8>,
+    len: usize,
+    // NOTE 结构体里只有裸指针，编译器不知道它借用了谁；PhantomData 告诉借用检查器 "我借用了一个 &'a str"
+    _marker: PhantomData<&'a str>,
+}
+
+impl<'a> MyStr<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        // SAFETY: 只能从 &'a str 构造，指针和长度来自一个合法的字符串，且在 'a 期间有效
+        unsafe {
+            let bytes = std::slice::from_raw_parts(self.ptr.as_ptr(), self.len);
+            std::str::from_utf8_unchecked(bytes)
+        }
+    }
+
+    /// 按字节切片，只是移动指针、修改长度，不拷贝数据；切在字符中间时返回 None
+    pub fn slice(&self, start: usize, end: usize) -> Option<MyStr<'a>> {
+        self.as_str().get(start..end).map(MyStr::from)
+    }
+}
+
+impl<'a> From<&'a str> for MyStr<'a> {
+    fn from(s: &'a str) -> MyStr<'a> {
+        MyStr {
+            // NOTE &str 的指针永远非空，空字符串也是一个悬空但非空的指针
+            ptr: NonNull::from(s).cast(),
+            len: s.len(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Deref for MyStr<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for MyStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// 逐个字符追加，push 返回追加之后的 (长度, 容量)，记录每次扩容之后的容量。
+/// 同时检查标准库对 String 的承诺: 容量不减，并且不小于长度
+#[cfg(test)]
+fn capacity_trace(text: &str, mut push: impl FnMut(char) -> (usize, usize)) -> Vec<usize> {
+    let mut trace: Vec<usize> = Vec::new();
+    for c in text.chars() {
+        let (len, cap) = push(c);
+        assert!(cap >= len, "capacity {} below length {}", cap, len);
+        assert!(
+            trace.last().is_none_or(|&last| cap >= last),
+            "capacity shrank"
+        );
+        if trace.last() != Some(&cap) {
+            trace.push(cap);
+        }
+    }
+    trace
+}
+
+#[test]
+fn test_my_string_matches_std() {
+    use std::mem::size_of;
+
+    // 栈上的大小: 自己的实现和标准库一样是 3 个字，切片视图是 2 个字
+    assert_eq!(size_of::<MyString>(), size_of::<String>());
+    assert_eq!(size_of::<MyStr>(), size_of::<&str>());
+    assert_eq!(size_of::<String>(), 3 * size_of::<usize>());
+    assert_eq!(size_of::<&str>(), 2 * size_of::<usize>());
+
+    let text = "Rust 字符串：ptr、len、capacity 🦀 and a bit more text to force growth";
+    let mut mine = MyString::new();
+    let mut std = String::new();
+    assert_eq!(mine.capacity(), 0);
+    let mine_trace = capacity_trace(text, |c| {
+        mine.push(c);
+        (mine.len(), mine.capacity())
+    });
+    let std_trace = capacity_trace(text, |c| {
+        std.push(c);
+        (std.len(), std.capacity())
+    });
+    assert_eq!(mine, std.as_str());
+    assert_eq!(mine.len(), std.len());
+    assert_eq!(mine.chars().count(), std.chars().count());
+    // 翻倍增长: 追加 80 多个字节只扩容了 5 次
+    assert_eq!(mine_trace, vec![8, 16, 32, 64, 128]);
+    // NOTE 标准库没有承诺具体的容量序列，只承诺均摊 O(1): 扩容次数是 O(log n)。
+    // 上限取 2 * log2(n)，增长倍数换成 1.5 也不会超过
+    println!("MyString: {:?}\nString:   {:?}", mine_trace, std_trace);
+    let log2 = |n: usize| n.ilog2() as usize;
+    assert!(std_trace.len() <= 2 * log2(std.len()), "{:?}", std_trace);
+    let long = "x".repeat(100_000);
+    let mut std_long = String::new();
+    let long_trace = capacity_trace(&long, |c| {
+        std_long.push(c);
+        (std_long.len(), std_long.capacity())
+    });
+    assert!(long_trace.len() <= 2 * log2(long.len()), "{:?}", long_trace);
+
+    mine.push_str("!!");
+    std.push_str("!!");
+    assert_eq!(mine.pop(), std.pop());
+    assert_eq!(mine.pop(), Some('!'));
+    assert_eq!(mine.capacity(), 128);
+    mine.shrink_to_fit();
+    assert_eq!(mine.capacity(), mine.len());
+    assert_eq!(mine, text);
+
+    // reserve 之后再追加不会移动内存
+    mine.reserve(100);
+    let before = mine.as_ptr();
+    mine.push_str(&"x".repeat(100));
+    assert_eq!(mine.as_ptr(), before);
+
+    mine.clear();
+    assert!(mine.is_empty());
+    mine.shrink_to_fit();
+    assert_eq!(mine.capacity(), 0);
+    assert_eq!(mine.pop(), None);
+}
+
+#[test]
+fn test_my_str_view() {
+    let owned = MyString::from("你好, world");
+    let view = owned.as_my_str();
+    // 视图和拥有者指向同一块内存，没有拷贝
+    assert_eq!(view.as_ptr(), owned.as_ptr());
+    assert_eq!(view.len(), owned.len());
+
+    let hello = view.slice(0, 6).unwrap();
+    assert_eq!(hello.as_str(), "你好");
+    assert_eq!(hello.as_ptr(), owned.as_ptr());
+    let world = view.slice(8, 13).unwrap();
+    assert_eq!(&*world, "world");
+    // 切在 "你" 的中间
+    assert!(view.slice(0, 2).is_none());
+    assert!(view.slice(0, 100).is_none());
+
+    // Deref 到 str 之后，str 的方法都可以直接用
+    assert!(view.starts_with("你好"));
+    assert_eq!(owned.to_uppercase(), "你好, WORLD");
+
+    let cloned = owned.clone();
+    assert_eq!(cloned, owned);
+    assert_ne!(cloned.as_ptr(), owned.as_ptr());
+    assert_eq!(
+        format!("{} {:?}", owned, view),
+        "你好, world \"你好, world\""
+    );
+}
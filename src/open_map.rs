@@ -0,0 +1,649 @@
+#![allow(dead_code)]
+
+/*
+ * 开放寻址哈希表（Robin Hood hashing）
+ *      std::collections::HashMap 也是开放寻址（SwissTable），这里用更容易看懂的 Robin Hood 线性探测实现一个可控的版本。
+ *
+ * 开放寻址
+ *      所有元素直接放在一个数组里，没有链表。键的 "理想位置" 是 hash & (容量 - 1)，
+ *      被占了就往后找下一个空位（线性探测），查找时也从理想位置往后找。
+ *
+ * Robin Hood
+ *      每个元素离自己理想位置的距离叫 "探测距离"（probe distance）。
+ *      插入时如果遇到一个比自己 "更富"（距离更短）的元素，就把位置抢过来，让它继续往后找 —— 劫富济贫。
+ *      结果是所有元素的探测距离都比较平均，最坏情况的查找长度大大缩短；
+ *      而且查找时一旦遇到比自己距离还短的元素，就可以断定键不存在，不必一直找到空位。
+ *
+ * 删除
+ *      直接清空会在探测链中间留下 "空洞"，后面的元素就找不到了。
+ *      这里用 backward shift: 把后面距离大于 0 的元素依次往前挪一格，不需要墓碑（tombstone）标记。
+ *
+ * 哈希函数通过 BuildHasher 参数替换，和 std 的 HashMap<K, V, S> 是同一个扩展点。
+ */
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::ops::Index;
+
+const MIN_CAPACITY: usize = 8;
+
+/// 默认的最大装载率（百分比），超过就扩容为两倍
+pub const DEFAULT_MAX_LOAD: u8 = 87;
+
+/// FNV-1a: 每个字节 异或 + 乘法，非常快，但不抗碰撞攻击，只适合键不受外部控制的场合
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub type FnvBuildHasher = BuildHasherDefault<Fnv1a>;
+
+struct Slot<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+pub struct OpenMap<K, V, S = RandomState> {
+    slots: Vec<Option<Slot<K, V>>>,
+    len: usize,
+    max_load: u8,
+    hasher: S,
+}
+
+impl<K, V> OpenMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V> Default for OpenMap<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> OpenMap<K, V, S> {
+    /// 和 std 一样，创建时不分配内存，第一次插入时才分配
+    pub fn with_hasher(hasher: S) -> Self {
+        OpenMap {
+            slots: Vec::new(),
+            len: 0,
+            max_load: DEFAULT_MAX_LOAD,
+            hasher,
+        }
+    }
+
+    /// 最大装载率（百分比，1..=99）。越高越省内存，但探测链越长
+    pub fn with_max_load(mut self, percent: u8) -> Self {
+        assert!((1..100).contains(&percent), "load factor must be in 1..100");
+        self.max_load = percent;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 槽位个数，总是 2 的幂（或者 0）
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    /// 离理想位置的距离，环绕到数组开头时也要算对
+    fn distance(&self, hash: u64, index: usize) -> usize {
+        let ideal = hash as usize & (self.slots.len() - 1);
+        index.wrapping_sub(ideal) & (self.slots.len() - 1)
+    }
+
+    /// 所有元素探测距离的 (最大值, 平均值)，用来观察装载率和哈希函数的影响
+    pub fn probe_stats(&self) -> (usize, f64) {
+        let distances: Vec<usize> = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().map(|s| self.distance(s.hash, i)))
+            .collect();
+        let max = distances.iter().copied().max().unwrap_or(0);
+        let avg = if distances.is_empty() {
+            0.0
+        } else {
+            distances.iter().sum::<usize>() as f64 / distances.len() as f64
+        };
+        (max, avg)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            slots: self.slots.iter(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            slots: self.slots.iter_mut(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> OpenMap<K, V, S> {
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        map.reserve(capacity);
+        map
+    }
+
+    fn hash_of<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    /// 能放下 n 个元素而不超过装载率的最小容量
+    fn capacity_for(&self, n: usize) -> usize {
+        let needed = (n * 100).div_ceil(self.max_load as usize).max(n + 1);
+        needed.next_power_of_two().max(MIN_CAPACITY)
+    }
+
+    /// 保证再插入 additional 个元素不会扩容
+    pub fn reserve(&mut self, additional: usize) {
+        let target = self.capacity_for(self.len + additional);
+        if target > self.slots.len() {
+            self.resize(target);
+        }
+    }
+
+    /// 扩容后元素的理想位置都变了，只能逐个重新插入（rehash）
+    fn resize(&mut self, new_capacity: usize) {
+        let old = std::mem::replace(
+            &mut self.slots,
+            std::iter::repeat_with(|| None).take(new_capacity).collect(),
+        );
+        for slot in old.into_iter().flatten() {
+            self.insert_new(slot);
+        }
+    }
+
+    /// 插入一个确定不存在的键
+    fn insert_new(&mut self, mut slot: Slot<K, V>) {
+        let mask = self.slots.len() - 1;
+        let mut index = slot.hash as usize & mask;
+        let mut dist = 0;
+        loop {
+            let their_dist = match &self.slots[index] {
+                None => {
+                    self.slots[index] = Some(slot);
+                    return;
+                }
+                Some(existing) => self.distance(existing.hash, index),
+            };
+            // 对方比自己 "富"，抢过它的位置，让它接着往后找
+            if their_dist < dist {
+                let existing = self.slots[index].as_mut().unwrap();
+                std::mem::swap(existing, &mut slot);
+                dist = their_dist;
+            }
+            index = (index + 1) & mask;
+            dist += 1;
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let hash = self.hash_of(key);
+        let mask = self.slots.len() - 1;
+        let mut index = hash as usize & mask;
+        let mut dist = 0;
+        while let Some(slot) = &self.slots[index] {
+            // 如果键存在，它早就抢占了这个比它 "富" 的元素的位置，所以可以提前结束
+            if self.distance(slot.hash, index) < dist {
+                return None;
+            }
+            // 先比较哈希值，不相等就省掉一次可能很慢的键比较
+            if slot.hash == hash && slot.key.borrow() == key {
+                return Some(index);
+            }
+            index = (index + 1) & mask;
+            dist += 1;
+        }
+        None
+    }
+
+    /// 返回被覆盖的旧值
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find(&key) {
+            let slot = self.slots[index].as_mut().unwrap();
+            return Some(std::mem::replace(&mut slot.value, value));
+        }
+        self.reserve(1);
+        let hash = self.hash_of(&key);
+        self.insert_new(Slot { hash, key, value });
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|s| &s.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_mut().map(|s| &mut s.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut index = self.find(key)?;
+        let removed = self.slots[index].take().unwrap();
+        self.len -= 1;
+
+        // backward shift: 后面的元素只要不在理想位置上，就往前挪一格填补空洞
+        let mask = self.slots.len() - 1;
+        loop {
+            let next = (index + 1) & mask;
+            match &self.slots[next] {
+                Some(slot) if self.distance(slot.hash, next) > 0 => {
+                    self.slots[index] = self.slots[next].take();
+                    index = next;
+                }
+                _ => break,
+            }
+        }
+        Some(removed.value)
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for OpenMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// 和 std 一样，键不存在时 panic
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in OpenMap")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for OpenMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for OpenMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for OpenMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    slots: std::slice::Iter<'a, Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((&slot.key, &slot.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    slots: std::slice::IterMut<'a, Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    /// 键不能修改，否则它的哈希值和所在的位置就对不上了
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((&slot.key, &mut slot.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    slots: std::vec::IntoIter<Option<Slot<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((slot.key, slot.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V, S> IntoIterator for OpenMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            remaining: self.len,
+            slots: self.slots.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a OpenMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut OpenMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+/// Robin Hood 的不变量: 空位之后的元素一定在理想位置上；相邻两个元素，后一个的距离最多比前一个大 1
+#[cfg(test)]
+fn check_invariants<K, V, S>(map: &OpenMap<K, V, S>) {
+    let cap = map.slots.len();
+    let dist = |i: usize| {
+        map.slots[i % cap]
+            .as_ref()
+            .map(|s| map.distance(s.hash, i % cap))
+    };
+    for i in 0..cap {
+        match (dist(i), dist(i + 1)) {
+            (None, Some(next)) => assert_eq!(next, 0, "hole before slot {}", i + 1),
+            (Some(prev), Some(next)) => assert!(next <= prev + 1, "slot {} too far", i + 1),
+            _ => {}
+        }
+    }
+    assert_eq!(map.slots.iter().flatten().count(), map.len);
+    assert!(map.len * 100 <= cap * map.max_load as usize);
+}
+
+/// 所有键的哈希值都相同，退化成一条长长的探测链，用来检验正确性
+#[cfg(test)]
+#[derive(Default)]
+struct Collide;
+
+#[cfg(test)]
+impl Hasher for Collide {
+    fn write(&mut self, _: &[u8]) {}
+
+    fn finish(&self) -> u64 {
+        42
+    }
+}
+
+#[test]
+fn test_open_map_basic() {
+    let mut map: OpenMap<String, u32> = OpenMap::new();
+    assert_eq!(map.capacity(), 0);
+    assert_eq!(map.get("missing"), None);
+
+    assert_eq!(map.insert("alice".to_string(), 1), None);
+    assert_eq!(map.insert("bob".to_string(), 2), None);
+    assert_eq!(map.insert("alice".to_string(), 10), Some(1));
+    // 键是 String，可以直接用 &str 查找（Borrow）
+    assert_eq!(map.get("alice"), Some(&10));
+    assert_eq!(map["bob"], 2);
+    *map.get_mut("bob").unwrap() += 1;
+    assert_eq!(map.remove("bob"), Some(3));
+    assert_eq!(map.remove("bob"), None);
+    assert_eq!(map.len(), 1);
+
+    // 装载率决定了什么时候扩容
+    let mut map: OpenMap<u32, u32, FnvBuildHasher> =
+        OpenMap::with_hasher(FnvBuildHasher::default()).with_max_load(50);
+    for i in 0..4 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.capacity(), 8);
+    map.insert(4, 4);
+    assert_eq!(map.capacity(), 16);
+    check_invariants(&map);
+
+    for (_, v) in map.iter_mut() {
+        *v *= 2;
+    }
+    let mut pairs: Vec<(u32, u32)> = map.into_iter().collect();
+    pairs.sort();
+    assert_eq!(pairs, [(0, 0), (1, 2), (2, 4), (3, 6), (4, 8)]);
+
+    let map: OpenMap<&str, usize, FnvBuildHasher> = ["rust", "go", "zig"]
+        .into_iter()
+        .map(|s| (s, s.len()))
+        .collect();
+    assert_eq!(map.iter().len(), 3);
+    assert_eq!(map.values().sum::<usize>(), 9);
+    println!("{:?}", map);
+}
+
+#[test]
+fn test_open_map_matches_std() {
+    use crate::testing::XorShift;
+    use std::collections::HashMap;
+
+    fn run<S: BuildHasher>(mut map: OpenMap<u64, u64, S>, ops: usize, key_space: u64) {
+        let mut rng = XorShift::new(7);
+        let mut reference = HashMap::new();
+        for step in 0..ops {
+            let key = rng.below(key_space);
+            match rng.below(3) {
+                0 | 1 => assert_eq!(
+                    map.insert(key, step as u64),
+                    reference.insert(key, step as u64)
+                ),
+                _ => assert_eq!(map.remove(&key), reference.remove(&key)),
+            }
+            if step % 97 == 0 {
+                check_invariants(&map);
+            }
+        }
+        check_invariants(&map);
+        assert_eq!(map.len(), reference.len());
+        for (k, v) in &reference {
+            assert_eq!(map.get(k), Some(v));
+        }
+        let mut keys: Vec<u64> = map.keys().copied().collect();
+        keys.sort();
+        let mut expected: Vec<u64> = reference.keys().copied().collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+    }
+
+    run(OpenMap::new(), 5000, 500);
+    run(
+        OpenMap::with_hasher(FnvBuildHasher::default()).with_max_load(95),
+        5000,
+        500,
+    );
+    // 最坏情况: 全部冲突，每次操作都是线性扫描，但结果依然正确
+    run(
+        OpenMap::with_hasher(BuildHasherDefault::<Collide>::default()),
+        600,
+        60,
+    );
+}
+
+/**
+ * 和 std::collections::HashMap 对比不同装载率下的插入、命中、未命中查找的耗时:
+ *      cargo test --release bench_open_map -- --ignored --nocapture
+ */
+#[test]
+#[ignore]
+fn bench_open_map() {
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    // 固定最终容量为 2^20，按装载率决定插入多少个键，这样每一轮都正好停在目标装载率上
+    const CAPACITY: u64 = 1 << 20;
+    let keys: Vec<u64> = (0..CAPACITY)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+
+    trait Table {
+        fn put(&mut self, key: u64);
+        fn has(&self, key: u64) -> bool;
+    }
+
+    impl Table for HashMap<u64, u64, FnvBuildHasher> {
+        fn put(&mut self, key: u64) {
+            self.insert(key, key);
+        }
+        fn has(&self, key: u64) -> bool {
+            self.contains_key(&key)
+        }
+    }
+
+    impl<S: BuildHasher> Table for OpenMap<u64, u64, S> {
+        fn put(&mut self, key: u64) {
+            self.insert(key, key);
+        }
+        fn has(&self, key: u64) -> bool {
+            self.contains_key(&key)
+        }
+    }
+
+    fn measure(name: &str, table: &mut impl Table, keys: &[u64]) {
+        let start = Instant::now();
+        for &k in keys {
+            table.put(k);
+        }
+        let inserted = start.elapsed();
+        let start = Instant::now();
+        let hits = keys.iter().filter(|&&k| table.has(k)).count();
+        let hit_time = start.elapsed();
+        let start = Instant::now();
+        let misses = keys.iter().filter(|&&k| !table.has(k ^ 1)).count();
+        let miss_time = start.elapsed();
+        assert_eq!((hits, misses), (keys.len(), keys.len()));
+        println!(
+            "{:<24} insert {:>10.2?}  hit {:>10.2?}  miss {:>10.2?}",
+            name, inserted, hit_time, miss_time
+        );
+    }
+
+    for load in [50, 75, 87, 95] {
+        let keys = &keys[..(CAPACITY * load as u64 / 100) as usize];
+        println!("load {}%, {} keys", load, keys.len());
+        measure(
+            "  std HashMap (fnv)",
+            &mut HashMap::with_hasher(FnvBuildHasher::default()),
+            keys,
+        );
+        let mut map = OpenMap::with_hasher(FnvBuildHasher::default()).with_max_load(load);
+        measure("  OpenMap (fnv)", &mut map, keys);
+        let (max, avg) = map.probe_stats();
+        println!(
+            "{:<24} capacity {}  probe max {}  avg {:.2}",
+            "",
+            map.capacity(),
+            max,
+            avg
+        );
+    }
+}
@@ -0,0 +1,241 @@
+#![allow(dead_code)]
+
+/*
+ * 节点之间的消息和线路格式
+ *      TCP 是字节流，没有 "消息" 的边界，一次 read 可能只读到半条消息，也可能读到一条半。
+ *      所以每条消息前面加 4 字节大端长度（length-prefixed framing），读的一方先读长度再读正文。
+ *      正文是 RLP 编码的 [消息类型, 内容]。
+ *
+ * 握手:
+ *      双方连上之后各自先发 Version（协议版本、创世区块哈希、当前高度），
+ *      检查对方和自己在同一条链上，再回一个 Verack，之后才开始交换交易和区块。
+ */
+
+use crate::chain::{Block, Hash};
+use crate::error::DomainError;
+use crate::rlp::{self, rlp_struct, Decodable, Encodable, Item, RlpError};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// 单条消息的上限，防止对方声明一个超大的长度让我们分配内存
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum P2pError {
+    Io(io::Error),
+    Decode(RlpError),
+    UnknownMessage(u16),
+    FrameTooLarge(usize),
+    /// 握手阶段收到了其它消息，或者握手之后又收到了握手消息
+    UnexpectedMessage(&'static str),
+    ProtocolMismatch {
+        ours: u16,
+        theirs: u16,
+    },
+    /// 对方的创世区块不同，不在同一个网络里
+    GenesisMismatch,
+}
+
+impl fmt::Display for P2pError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // 被包住的错误由 source() 给出，这里不再重复
+            P2pError::Io(_) => write!(f, "network error"),
+            P2pError::Decode(_) => write!(f, "malformed message"),
+            P2pError::UnknownMessage(tag) => write!(f, "unknown message type {}", tag),
+            P2pError::FrameTooLarge(len) => write!(f, "frame of {} bytes exceeds the limit", len),
+            P2pError::UnexpectedMessage(name) => write!(f, "unexpected {} message", name),
+            P2pError::ProtocolMismatch { ours, theirs } => write!(
+                f,
+                "protocol version mismatch: ours {}, theirs {}",
+                ours, theirs
+            ),
+            P2pError::GenesisMismatch => write!(f, "peer is on a different chain"),
+        }
+    }
+}
+
+impl std::error::Error for P2pError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            P2pError::Io(err) => Some(err),
+            P2pError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl DomainError for P2pError {
+    const CODE: u16 = 3003;
+}
+
+impl From<io::Error> for P2pError {
+    fn from(err: io::Error) -> P2pError {
+        P2pError::Io(err)
+    }
+}
+
+impl From<RlpError> for P2pError {
+    fn from(err: RlpError) -> P2pError {
+        P2pError::Decode(err)
+    }
+}
+
+rlp_struct!(Block {
+    index,
+    timestamp,
+    prev_hash,
+    payload,
+    nonce,
+    difficulty,
+    hash
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub protocol: u16,
+    pub genesis: Hash,
+    pub height: u64,
+    pub user_agent: String,
+}
+
+rlp_struct!(Version {
+    protocol,
+    genesis,
+    height,
+    user_agent
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Version(Version),
+    Verack,
+    /// 交易对网络层来说是不透明的字节，用哈希去重
+    Tx(Vec<u8>),
+    /// 新挖出的区块
+    Block(Block),
+    /// 请求从 from 开始的所有区块
+    GetBlocks {
+        from: u64,
+    },
+    Blocks(Vec<Block>),
+}
+
+impl Message {
+    fn name(&self) -> &'static str {
+        match self {
+            Message::Version(_) => "version",
+            Message::Verack => "verack",
+            Message::Tx(_) => "tx",
+            Message::Block(_) => "block",
+            Message::GetBlocks { .. } => "getblocks",
+            Message::Blocks(_) => "blocks",
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let (tag, body): (u16, Item) = match self {
+            Message::Version(v) => (0, v.to_rlp()),
+            Message::Verack => (1, Item::List(Vec::new())),
+            Message::Tx(tx) => (2, tx.to_rlp()),
+            Message::Block(block) => (3, block.to_rlp()),
+            Message::GetBlocks { from } => (4, from.to_rlp()),
+            Message::Blocks(blocks) => (5, blocks.to_rlp()),
+        };
+        rlp::encode(&Item::List(vec![tag.to_rlp(), body]))
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Message, P2pError> {
+        let item = rlp::decode(data)?;
+        let list = item.as_list()?;
+        let [tag, body] = list else {
+            return Err(RlpError::ListLength {
+                expected: 2,
+                found: list.len(),
+            }
+            .into());
+        };
+        let message = match u16::from_rlp(tag)? {
+            0 => Message::Version(Version::from_rlp(body)?),
+            1 => Message::Verack,
+            2 => Message::Tx(Vec::from_rlp(body)?),
+            3 => Message::Block(Block::from_rlp(body)?),
+            4 => Message::GetBlocks {
+                from: u64::from_rlp(body)?,
+            },
+            5 => Message::Blocks(Vec::from_rlp(body)?),
+            tag => return Err(P2pError::UnknownMessage(tag)),
+        };
+        Ok(message)
+    }
+
+    /// 握手之外的地方出现了这条消息
+    pub fn unexpected(&self) -> P2pError {
+        P2pError::UnexpectedMessage(self.name())
+    }
+}
+
+pub fn write_message(w: &mut impl Write, message: &Message) -> io::Result<()> {
+    let body = message.encode();
+    // NOTE 长度和正文拼在一起一次写出，避免 Nagle 算法把一条消息拆成两个 TCP 包
+    let mut frame = Vec::with_capacity(4 + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&body);
+    w.write_all(&frame)?;
+    w.flush()
+}
+
+pub fn read_message(r: &mut impl Read) -> Result<Message, P2pError> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(P2pError::FrameTooLarge(len));
+    }
+    let mut body = vec![0u8; len];
+    r.read_exact(&mut body)?;
+    Message::decode(&body)
+}
+
+#[test]
+fn test_message_roundtrip() {
+    let mut chain = crate::chain::Blockchain::new(4);
+    chain.mine_block_at(b"payload", 1).unwrap();
+    let messages = [
+        Message::Version(Version {
+            protocol: PROTOCOL_VERSION,
+            genesis: Block::genesis().hash,
+            height: 1,
+            user_agent: "hello_rust/0.1".to_string(),
+        }),
+        Message::Verack,
+        Message::Tx(b"alice -> bob: 10".to_vec()),
+        Message::Block(chain.latest().clone()),
+        Message::GetBlocks { from: 0 },
+        Message::Blocks(chain.blocks().to_vec()),
+    ];
+
+    // 多条消息首尾相接写进同一个流，再逐条读出来
+    let mut stream = Vec::new();
+    for message in &messages {
+        write_message(&mut stream, message).unwrap();
+    }
+    let mut reader = &stream[..];
+    for message in &messages {
+        assert_eq!(&read_message(&mut reader).unwrap(), message);
+    }
+    assert!(matches!(read_message(&mut reader), Err(P2pError::Io(_))));
+
+    let huge = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes();
+    assert!(matches!(
+        read_message(&mut &huge[..]),
+        Err(P2pError::FrameTooLarge(_))
+    ));
+    let unknown = rlp::encode(&Item::List(vec![9u16.to_rlp(), Item::List(Vec::new())]));
+    assert!(matches!(
+        Message::decode(&unknown),
+        Err(P2pError::UnknownMessage(9))
+    ));
+}
@@ -0,0 +1,4 @@
+pub(crate) mod message;
+pub(crate) mod node;
+
+// NOTE 对应 test_pointer.rs 里 "吃透网络编程" 的 TODO: 只用标准库的 TcpListener / TcpStream 和线程，每个连接一个读线程
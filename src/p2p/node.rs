@@ -0,0 +1,569 @@
+#![allow(dead_code)]
+
+/*
+ * 网络节点
+ *      每个节点监听 127.0.0.1 上的一个端口，既接受别人的连接，也主动连接别人。
+ *      连接建立之后两边是对等的（peer-to-peer），没有客户端和服务器之分。
+ *
+ * 线程模型
+ *      1 个线程 accept 新连接；每个连接 1 个线程阻塞在 read 上，收到消息就处理。
+ *      账本（区块链 + 交易池）放在 Mutex 里，由所有连接的线程共享；
+ *      发给某个 peer 的数据要经过它自己的写锁，多个线程同时广播时消息不会交错。
+ *
+ * 广播（gossip）
+ *      收到一条新的交易或区块，先检查自己是否已经有了，没有才转发给除来源之外的所有 peer。
+ *      "已经有了就不转发" 保证了消息在有环的网络里也会停止传播。
+ *
+ * 同步
+ *      握手时发现对方更高，就向它要缺少的区块；要来的区块接不上本地链（说明发生了分叉），
+ *      就要整条链，交给 Blockchain::resolve_fork 决定是否替换。
+ *      NOTE 任何 peer 都可以不请自来地发送一整条链，所以 resolve_fork 比较的是累计工作量而不是区块个数，
+ *      并拒绝难度低于本地要求的区块，否则一个 peer 用不需要挖矿的区块就能覆盖所有节点的链。
+ */
+
+use super::message::{read_message, write_message, Message, P2pError, Version, PROTOCOL_VERSION};
+use crate::chain::{hash_bytes, Block, Blockchain, Hash};
+use crate::error::{Context, Result};
+use crate::rlp::{Decodable, Encodable};
+use std::collections::{BTreeMap, HashSet};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const USER_AGENT: &str = "hello_rust/0.1";
+
+/// 握手必须在这个时间内完成，防止一个只连接不说话的对端一直占着线程
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type PeerId = u64;
+
+struct Peer {
+    id: PeerId,
+    addr: SocketAddr,
+    user_agent: String,
+    writer: Mutex<TcpStream>,
+}
+
+impl Peer {
+    fn send(&self, message: &Message) {
+        // NOTE 发送失败说明连接已经断开，读线程随后会读到错误并把这个 peer 移除，这里不必处理
+        let _ = write_message(&mut *self.writer.lock().unwrap(), message);
+    }
+}
+
+/// 收到一个区块之后该做什么
+enum BlockOutcome {
+    Accepted,
+    Known,
+    /// 接不上本地链，需要从这个高度开始向对方要区块
+    Behind(u64),
+    Rejected,
+}
+
+/// 收到一批区块之后该做什么
+enum SyncOutcome {
+    Extended,
+    Unchanged,
+    /// 分叉了，需要对方的整条链
+    NeedFullChain,
+}
+
+struct Ledger {
+    chain: Blockchain,
+    mempool: BTreeMap<Hash, Vec<u8>>,
+    /// 见过的交易，包括已经打包进区块的，防止同一笔交易被反复广播
+    seen: HashSet<Hash>,
+}
+
+impl Ledger {
+    /// 返回交易是否是第一次见到
+    fn add_transaction(&mut self, tx: Vec<u8>) -> bool {
+        let hash = hash_bytes(&tx);
+        if !self.seen.insert(hash) {
+            return false;
+        }
+        self.mempool.insert(hash, tx);
+        true
+    }
+
+    /// 区块的 payload 是 RLP 编码的交易列表，已经上链的交易移出交易池
+    fn remove_included(&mut self, block: &Block) {
+        // NOTE 网络层不关心 payload 的格式，别的节点挖出的区块解不开也没关系
+        let Ok(txs) = Vec::<Vec<u8>>::decode_rlp(&block.payload) else {
+            return;
+        };
+        for tx in txs {
+            let hash = hash_bytes(&tx);
+            self.mempool.remove(&hash);
+            self.seen.insert(hash);
+        }
+    }
+
+    fn has_block(&self, block: &Block) -> bool {
+        self.chain
+            .blocks()
+            .get(block.index as usize)
+            .is_some_and(|b| b.hash == block.hash)
+    }
+
+    fn accept_block(&mut self, block: Block) -> BlockOutcome {
+        if self.has_block(&block) {
+            return BlockOutcome::Known;
+        }
+        let next = self.chain.latest().index + 1;
+        match self.chain.add_block(block.clone()) {
+            Ok(()) => {
+                self.remove_included(&block);
+                BlockOutcome::Accepted
+            }
+            Err(_) if block.index >= next => BlockOutcome::Behind(next),
+            Err(_) => BlockOutcome::Rejected,
+        }
+    }
+
+    fn sync(&mut self, blocks: Vec<Block>) -> SyncOutcome {
+        let Some(first) = blocks.first() else {
+            return SyncOutcome::Unchanged;
+        };
+        if first.index == 0 {
+            return match self.chain.resolve_fork(&blocks) {
+                Ok(true) => {
+                    // NOTE 被替换掉的那段分叉里的交易就此丢失，真实的节点会把它们放回交易池
+                    for block in &blocks {
+                        self.remove_included(block);
+                    }
+                    SyncOutcome::Extended
+                }
+                _ => SyncOutcome::Unchanged,
+            };
+        }
+
+        let mut extended = false;
+        for block in blocks {
+            if self.has_block(&block) {
+                continue;
+            }
+            match self.chain.add_block(block.clone()) {
+                Ok(()) => {
+                    self.remove_included(&block);
+                    extended = true;
+                }
+                Err(_) if !extended => return SyncOutcome::NeedFullChain,
+                // 中途接不上说明对方发来了不合法的区块，保留已经接上的部分
+                Err(_) => break,
+            }
+        }
+        if extended {
+            SyncOutcome::Extended
+        } else {
+            SyncOutcome::Unchanged
+        }
+    }
+}
+
+struct Shared {
+    ledger: Mutex<Ledger>,
+    peers: Mutex<Vec<Arc<Peer>>>,
+    next_peer_id: AtomicU64,
+    running: AtomicBool,
+}
+
+impl Shared {
+    fn version(&self) -> Version {
+        let ledger = self.ledger.lock().unwrap();
+        Version {
+            protocol: PROTOCOL_VERSION,
+            genesis: ledger.chain.blocks()[0].hash,
+            height: ledger.chain.latest().index,
+            user_agent: USER_AGENT.to_string(),
+        }
+    }
+
+    fn broadcast(&self, message: &Message, except: Option<PeerId>) {
+        // 先拷贝出 peer 列表再发送，发送时不持有列表的锁
+        let peers: Vec<Arc<Peer>> = self.peers.lock().unwrap().clone();
+        for peer in peers.iter().filter(|p| Some(p.id) != except) {
+            peer.send(message);
+        }
+    }
+
+    /// 双方各自发送 Version、检查对方的 Version、回复 Verack，成功后为这个连接启动读线程
+    fn handshake(self: &Arc<Self>, mut stream: TcpStream) -> Result<(), P2pError> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let ours = self.version();
+        write_message(&mut stream, &Message::Version(ours.clone()))?;
+        let theirs = match read_message(&mut stream)? {
+            Message::Version(v) => v,
+            other => return Err(other.unexpected()),
+        };
+        if theirs.protocol != PROTOCOL_VERSION {
+            return Err(P2pError::ProtocolMismatch {
+                ours: PROTOCOL_VERSION,
+                theirs: theirs.protocol,
+            });
+        }
+        if theirs.genesis != ours.genesis {
+            return Err(P2pError::GenesisMismatch);
+        }
+        write_message(&mut stream, &Message::Verack)?;
+        match read_message(&mut stream)? {
+            Message::Verack => {}
+            other => return Err(other.unexpected()),
+        }
+        stream.set_read_timeout(None)?;
+
+        let peer = Arc::new(Peer {
+            id: self.next_peer_id.fetch_add(1, Ordering::Relaxed),
+            addr: stream.peer_addr()?,
+            user_agent: theirs.user_agent,
+            writer: Mutex::new(stream.try_clone()?),
+        });
+        self.peers.lock().unwrap().push(Arc::clone(&peer));
+        if theirs.height > ours.height {
+            peer.send(&Message::GetBlocks {
+                from: ours.height + 1,
+            });
+        }
+
+        let shared = Arc::clone(self);
+        thread::spawn(move || shared.run_peer(peer, stream));
+        Ok(())
+    }
+
+    fn run_peer(&self, peer: Arc<Peer>, mut stream: TcpStream) {
+        // 读出错（对方断开、消息格式错误）或者对方违反协议，都断开连接
+        while let Ok(message) = read_message(&mut stream) {
+            if self.handle(&peer, message).is_err() {
+                break;
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+        self.peers.lock().unwrap().retain(|p| p.id != peer.id);
+    }
+
+    fn handle(&self, peer: &Peer, message: Message) -> Result<(), P2pError> {
+        match message {
+            Message::Tx(tx) => {
+                let new = self.ledger.lock().unwrap().add_transaction(tx.clone());
+                if new {
+                    self.broadcast(&Message::Tx(tx), Some(peer.id));
+                }
+            }
+            Message::Block(block) => {
+                let outcome = self.ledger.lock().unwrap().accept_block(block.clone());
+                match outcome {
+                    BlockOutcome::Accepted => self.broadcast(&Message::Block(block), Some(peer.id)),
+                    BlockOutcome::Behind(from) => peer.send(&Message::GetBlocks { from }),
+                    // NOTE 真实的节点会给发送不合法区块的 peer 扣分，扣到一定程度就断开
+                    BlockOutcome::Known | BlockOutcome::Rejected => {}
+                }
+            }
+            Message::GetBlocks { from } => {
+                let blocks = {
+                    let ledger = self.ledger.lock().unwrap();
+                    let blocks = ledger.chain.blocks();
+                    blocks.get(from as usize..).unwrap_or_default().to_vec()
+                };
+                peer.send(&Message::Blocks(blocks));
+            }
+            Message::Blocks(blocks) => {
+                let (outcome, latest) = {
+                    let mut ledger = self.ledger.lock().unwrap();
+                    let outcome = ledger.sync(blocks);
+                    (outcome, ledger.chain.latest().clone())
+                };
+                match outcome {
+                    // 把新的链头告诉其它 peer，它们会按需来要中间的区块
+                    SyncOutcome::Extended => self.broadcast(&Message::Block(latest), Some(peer.id)),
+                    SyncOutcome::NeedFullChain => peer.send(&Message::GetBlocks { from: 0 }),
+                    SyncOutcome::Unchanged => {}
+                }
+            }
+            Message::Version(_) | Message::Verack => return Err(message.unexpected()),
+        }
+        Ok(())
+    }
+}
+
+pub struct Node {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl Node {
+    /// 在 127.0.0.1 的随机端口上启动节点，端口由操作系统分配，多个节点互不冲突
+    pub fn start(difficulty: u32) -> Result<Node> {
+        let listener = TcpListener::bind("127.0.0.1:0").context("binding p2p listener")?;
+        let addr = listener.local_addr().context("binding p2p listener")?;
+        let shared = Arc::new(Shared {
+            ledger: Mutex::new(Ledger {
+                chain: Blockchain::new(difficulty),
+                mempool: BTreeMap::new(),
+                seen: HashSet::new(),
+            }),
+            peers: Mutex::new(Vec::new()),
+            next_peer_id: AtomicU64::new(0),
+            running: AtomicBool::new(true),
+        });
+
+        let accept = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if !accept.running.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let shared = Arc::clone(&accept);
+                // 握手可能要等上几秒，放到单独的线程里，不耽误接受下一个连接
+                thread::spawn(move || {
+                    let _ = shared.handshake(stream);
+                });
+            }
+        });
+        Ok(Node { addr, shared })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 主动连接另一个节点，握手完成后才返回
+    pub fn connect(&self, addr: SocketAddr) -> Result<()> {
+        let stream = TcpStream::connect(addr).with_context(|| format!("connecting to {}", addr))?;
+        self.shared
+            .handshake(stream)
+            .with_context(|| format!("handshake with {}", addr))
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.shared.peers.lock().unwrap().len()
+    }
+
+    /// 提交一笔交易并广播，返回交易哈希
+    pub fn submit_transaction(&self, tx: Vec<u8>) -> Hash {
+        let hash = hash_bytes(&tx);
+        if self
+            .shared
+            .ledger
+            .lock()
+            .unwrap()
+            .add_transaction(tx.clone())
+        {
+            self.shared.broadcast(&Message::Tx(tx), None);
+        }
+        hash
+    }
+
+    /// 把交易池里的所有交易打包挖出一个区块并广播
+    pub fn mine(&self) -> Block {
+        let block = {
+            // NOTE 挖矿期间一直持有账本的锁，收到的区块要等挖完才处理；难度低的玩具链可以接受
+            let mut ledger = self.shared.ledger.lock().unwrap();
+            let txs: Vec<Vec<u8>> = ledger.mempool.values().cloned().collect();
+            // NOTE 时间戳是当前时间，离 u64::MAX 还远，nonce 和时间戳不可能一起用完
+            let block = ledger
+                .chain
+                .mine_block(&txs.rlp_bytes())
+                .expect("current time is far below u64::MAX")
+                .clone();
+            ledger.remove_included(&block);
+            block
+        };
+        self.shared.broadcast(&Message::Block(block.clone()), None);
+        block
+    }
+
+    pub fn height(&self) -> u64 {
+        self.shared.ledger.lock().unwrap().chain.latest().index
+    }
+
+    pub fn blocks(&self) -> Vec<Block> {
+        self.shared.ledger.lock().unwrap().chain.blocks().to_vec()
+    }
+
+    pub fn mempool_len(&self) -> usize {
+        self.shared.ledger.lock().unwrap().mempool.len()
+    }
+
+    pub fn has_transaction(&self, hash: &Hash) -> bool {
+        self.shared
+            .ledger
+            .lock()
+            .unwrap()
+            .mempool
+            .contains_key(hash)
+    }
+
+    /// 断开所有连接并停止监听
+    pub fn shutdown(&self) {
+        if !self.shared.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        for peer in self.shared.peers.lock().unwrap().iter() {
+            let _ = peer.writer.lock().unwrap().shutdown(Shutdown::Both);
+        }
+        // accept 阻塞着，自己连自己一次把它唤醒，它看到 running 为 false 就退出
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// 网络是异步的，测试里轮询等待各节点收敛
+#[cfg(test)]
+fn wait_until(what: &str, condition: impl Fn() -> bool) {
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while !condition() {
+        assert!(std::time::Instant::now() < deadline, "timed out: {}", what);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_gossip_transactions_and_blocks() {
+    // a - b - c 连成一条线，a 和 c 之间没有直接连接，消息必须经过 b 转发
+    let (a, b, c) = (
+        Node::start(4).unwrap(),
+        Node::start(4).unwrap(),
+        Node::start(4).unwrap(),
+    );
+    b.connect(a.addr()).unwrap();
+    c.connect(b.addr()).unwrap();
+    wait_until("peers connected", || {
+        a.peer_count() == 1 && b.peer_count() == 2 && c.peer_count() == 1
+    });
+
+    let tx = c.submit_transaction(b"carol -> alice: 5".to_vec());
+    wait_until("tx reached a", || a.has_transaction(&tx));
+    // 重复提交不会再次广播，也不会重复进入交易池
+    c.submit_transaction(b"carol -> alice: 5".to_vec());
+    assert_eq!(a.mempool_len(), 1);
+
+    let block = a.mine();
+    let txs = Vec::<Vec<u8>>::decode_rlp(&block.payload).unwrap();
+    assert_eq!(txs, vec![b"carol -> alice: 5".to_vec()]);
+    wait_until("block reached c", || c.height() == 1);
+    wait_until("mempools drained", || {
+        a.mempool_len() + b.mempool_len() + c.mempool_len() == 0
+    });
+    assert_eq!(c.blocks(), a.blocks());
+}
+
+#[test]
+fn test_sync_lagging_peer_and_fork() {
+    let a = Node::start(4).unwrap();
+    let b = Node::start(4).unwrap();
+    for _ in 0..3 {
+        a.mine();
+    }
+    // b 单独挖出一个包含交易的区块，和 a 在高度 1 就分叉了
+    // NOTE 同一秒内挖出的空区块内容完全相同，不带交易就不会分叉
+    b.submit_transaction(b"only on b".to_vec());
+    b.mine();
+    assert_ne!(a.blocks()[1], b.blocks()[1]);
+
+    b.connect(a.addr()).unwrap();
+    wait_until("b adopted the longer chain", || b.blocks() == a.blocks());
+
+    // 一个全新的节点只连接 b，也能同步到最新高度
+    let d = Node::start(4).unwrap();
+    d.connect(b.addr()).unwrap();
+    wait_until("d synced", || d.height() == 3);
+    assert_eq!(d.blocks(), a.blocks());
+
+    // 之后挖出的区块实时传播
+    a.mine();
+    wait_until("new block reached d", || d.height() == 4);
+}
+
+#[test]
+fn test_handshake_rejected() {
+    let node = Node::start(4).unwrap();
+
+    // 冒充一个创世区块不同的节点连进来
+    let mut stream = TcpStream::connect(node.addr()).unwrap();
+    let ours = match read_message(&mut stream).unwrap() {
+        Message::Version(v) => v,
+        other => panic!("expected version, got {:?}", other),
+    };
+    assert_eq!(ours.height, 0);
+    let fake = Version {
+        genesis: [0xee; 32],
+        ..ours.clone()
+    };
+    write_message(&mut stream, &Message::Version(fake)).unwrap();
+    // 节点拒绝握手并断开，读到的是 EOF 而不是 Verack
+    assert!(read_message(&mut stream).is_err());
+    assert_eq!(node.peer_count(), 0);
+
+    // 反过来，主动连接一个协议版本不同的节点，connect 返回错误
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let future = Version {
+            protocol: PROTOCOL_VERSION + 1,
+            ..ours
+        };
+        write_message(&mut stream, &Message::Version(future)).unwrap();
+        let _ = read_message(&mut stream);
+    });
+    let err = node.connect(addr).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<P2pError>(),
+        Some(P2pError::ProtocolMismatch { ours: 1, theirs: 2 })
+    ));
+    assert_eq!(err.code(), 3003);
+    assert_eq!(err.to_string(), format!("handshake with {}", addr));
+    drop(node);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_cheap_chain_rejected() {
+    let node = Node::start(4).unwrap();
+    node.mine();
+    node.mine();
+    let before = node.blocks();
+
+    // 攻击者按协议完成握手，然后主动推送难度为 0 的长链
+    let mut stream = TcpStream::connect(node.addr()).unwrap();
+    let ours = match read_message(&mut stream).unwrap() {
+        Message::Version(v) => v,
+        other => panic!("expected version, got {:?}", other),
+    };
+    write_message(&mut stream, &Message::Version(ours)).unwrap();
+    assert!(matches!(read_message(&mut stream), Ok(Message::Verack)));
+    write_message(&mut stream, &Message::Verack).unwrap();
+
+    let mut cheap = Blockchain::new(0);
+    for i in 0..20 {
+        cheap.mine_block_at(b"free", i).unwrap();
+    }
+    // 区块更多，但累计工作量不如本地链
+    write_message(&mut stream, &Message::Blocks(cheap.blocks().to_vec())).unwrap();
+    for i in 20..1000 {
+        cheap.mine_block_at(b"free", i).unwrap();
+    }
+    // 累计工作量超过了本地链，但每个区块的难度都不够
+    write_message(&mut stream, &Message::Blocks(cheap.blocks().to_vec())).unwrap();
+
+    // 同一个连接上的消息按顺序处理，收到 GetBlocks 的回复时前面的 Blocks 都已经处理完了
+    write_message(&mut stream, &Message::GetBlocks { from: 0 }).unwrap();
+    let reply = loop {
+        match read_message(&mut stream).unwrap() {
+            Message::Blocks(blocks) => break blocks,
+            // 节点发现对方 "更高" 时会先来要区块，忽略
+            Message::GetBlocks { .. } => continue,
+            other => panic!("expected blocks, got {:?}", other),
+        }
+    };
+    assert_eq!(reply, before);
+    assert_eq!(node.blocks(), before);
+}
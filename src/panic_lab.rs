@@ -0,0 +1,302 @@
+#![allow(dead_code)]
+
+/*
+ * panic 实验室
+ *      test_error.rs 里的 test_panic 只是调用了 panic!，这里看看 panic 发生之后还能做什么:
+ *
+ * panic hook
+ *      panic 发生时，在栈展开（unwind）之前，标准库会先调用一个全局的 hook，默认的 hook 就是打印
+ *      "thread 'main' panicked at src/main.rs:2:5" 的那个。std::panic::set_hook 可以换成自己的，
+ *      例如把崩溃报告（消息、位置、线程、调用栈）写进文件，方便事后排查。
+ *
+ * recover
+ *      Golang 用 defer + recover() 拦截 panic，Rust 对应的是 std::panic::catch_unwind:
+ *      闭包里发生的 panic 会在展开到 catch_unwind 时停下，变成一个 Err。
+ *      NOTE catch_unwind 不是 try/catch，它只适合在边界处兜底（线程池、FFI、插件），
+ *      可预期的错误还是应该用 Result
+ *
+ * UnwindSafe
+ *      闭包在修改数据的中途 panic，数据可能停在一个 "改了一半" 的状态，被捕获之后还继续用就可能出错。
+ *      catch_unwind 要求闭包是 UnwindSafe: 捕获了 &mut T 或者 RefCell 的闭包默认不是，
+ *      需要用 AssertUnwindSafe 包一下，表示 "我确认过，panic 之后这些数据还能用"。
+ *      Mutex 走的是另一条路: 持有锁的线程 panic 后锁会被标记为 poisoned，之后 lock() 返回 Err 提醒你。
+ *
+ * 捕获不了的 panic（进程直接 abort）
+ *      1. 栈展开过程中又发生 panic（例如 Drop 里 panic），不能同时展开两次
+ *      2. panic 越过 extern "C" 函数的边界
+ *      3. 用 panic = "abort" 编译（Cargo.toml 的 [profile] 里设置），根本不展开
+ *      4. std::process::abort()，这本来就不是 panic
+ *      这几种情况 panic hook 仍然会被调用（第 4 种除外），所以崩溃报告依然有用。
+ */
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::panic::{self, PanicHookInfo, UnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    /// UNIX 时间戳（秒）
+    pub time: u64,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+}
+
+impl CrashReport {
+    /// 在 hook 里调用，此时栈还没有展开，捕获到的调用栈就是 panic 发生的地方
+    pub fn capture(info: &PanicHookInfo<'_>) -> CrashReport {
+        CrashReport {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message: payload_message(info.payload()),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            // NOTE force_capture 不受 RUST_BACKTRACE 环境变量影响，总是会捕获
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+
+    /// 以追加的方式写入，一次 write_all 写完一整份报告，多个线程同时 panic 也不会交错
+    pub fn append_to(&self, path: &Path) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== crash report ===")?;
+        writeln!(f, "time: {}", self.time)?;
+        writeln!(f, "thread: {}", self.thread)?;
+        writeln!(f, "message: {}", self.message)?;
+        writeln!(
+            f,
+            "location: {}",
+            self.location.as_deref().unwrap_or("<unknown>")
+        )?;
+        writeln!(f, "backtrace:")?;
+        writeln!(f, "{}", self.backtrace)
+    }
+}
+
+/**
+ * panic! 的参数会被装进 Box<dyn Any + Send>:
+ *      panic!("literal")           -> &'static str
+ *      panic!("{}", x)             -> String
+ *      std::panic::panic_any(42)   -> 任意类型，这里只能显示成 "<non-string payload>"
+ */
+pub fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string payload>".to_string()
+    }
+}
+
+/// 安装崩溃报告 hook: 先把报告写进 path，再交给之前的 hook（默认 hook 会打印到 stderr）
+pub fn install_crash_reporter(path: impl Into<PathBuf>) {
+    let path = path.into();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // NOTE hook 里绝对不能再 panic，那会直接 abort，所以写文件失败也只能忽略
+        let _ = CrashReport::capture(info).append_to(&path);
+        previous(info);
+    }));
+}
+
+/// 恢复成标准库默认的 hook
+pub fn uninstall_crash_reporter() {
+    let _ = panic::take_hook();
+}
+
+/// catch_unwind 捕获到的 panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    pub message: String,
+}
+
+impl fmt::Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for Panic {}
+
+/// Golang 风格的 recover: 把闭包里的 panic 变成 Err
+pub fn recover<R>(f: impl FnOnce() -> R + UnwindSafe) -> Result<R, Panic> {
+    panic::catch_unwind(f).map_err(|payload| Panic {
+        message: payload_message(payload.as_ref()),
+    })
+}
+
+#[test]
+fn test_recover() {
+    use std::panic::AssertUnwindSafe;
+    use std::sync::{Arc, Mutex};
+
+    // 能捕获的 panic: 显式 panic、越界、unwrap None、除以 0 ...
+    assert_eq!(recover(|| 1 + 1), Ok(2));
+    assert_eq!(recover(|| panic!("boom")).unwrap_err().message, "boom");
+    let v: Vec<i32> = (1..=3).collect();
+    let err = recover(|| v[10]).unwrap_err();
+    assert!(err.message.contains("index out of bounds"));
+    let none = std::hint::black_box(None::<i32>);
+    assert!(recover(|| none.unwrap()).is_err());
+    let zero = std::hint::black_box(0);
+    assert_eq!(
+        recover(|| 1 / zero).unwrap_err().message,
+        "attempt to divide by zero"
+    );
+    let payload = recover(|| panic::panic_any(42)).unwrap_err();
+    assert_eq!(payload.message, "<non-string payload>");
+
+    // 其它线程的 panic 不需要 catch_unwind，join 就会返回 Err
+    let handle = std::thread::spawn(|| panic!("in worker"));
+    let payload = handle.join().unwrap_err();
+    assert_eq!(payload_message(payload.as_ref()), "in worker");
+
+    /*
+     * 捕获 &mut Vec 的闭包不是 UnwindSafe，直接传给 recover 会编译失败:
+     * NOTE the type `&mut Vec<i32>` may not be safely transferred across an unwind boundary
+     * 用 AssertUnwindSafe 承诺 panic 之后 balances 仍然可以用，但实际上它停在了 "改了一半" 的状态
+     */
+    let mut balances = vec![100, 100];
+    let result = recover(AssertUnwindSafe(|| {
+        balances[0] -= 30;
+        if balances[0] < 100 {
+            panic!("transfer interrupted");
+        }
+        balances[1] += 30;
+    }));
+    assert!(result.is_err());
+    assert_eq!(balances, [70, 100]);
+
+    // Mutex 在持有锁的线程 panic 之后被标记为 poisoned，数据仍然可以取出来，但调用方必须明确处理
+    let shared = Arc::new(Mutex::new(vec![100, 100]));
+    let worker = Arc::clone(&shared);
+    let _ = std::thread::spawn(move || {
+        let mut guard = worker.lock().unwrap();
+        guard[0] -= 30;
+        panic!("transfer interrupted");
+    })
+    .join();
+    assert!(shared.is_poisoned());
+    let data = shared
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    assert_eq!(*data, [70, 100]);
+}
+
+/// 由 test_crash_report 在子进程里运行，直接运行时什么也不做
+#[test]
+#[ignore]
+fn crash_report_child() {
+    let Ok(path) = std::env::var("PANIC_LAB_REPORT") else {
+        return;
+    };
+    install_crash_reporter(path);
+    let result = std::thread::Builder::new()
+        .name("report-writer".to_string())
+        .spawn(|| panic!("disk full"))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+}
+
+/**
+ * hook 是整个进程共享的，在测试进程里安装会让之后所有测试的 panic 都去写报告，
+ * 所以和 test_uncatchable_panics 一样，在子进程里安装 hook，父进程只检查写出来的文件。
+ */
+#[test]
+fn test_crash_report() {
+    use std::process::Command;
+
+    let path = std::env::temp_dir().join(format!("crash-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["panic_lab::crash_report_child", "--exact", "--ignored"])
+        .env("PANIC_LAB_REPORT", &path)
+        .output()
+        .unwrap()
+        .status;
+    let log = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    assert!(status.success());
+
+    let report = log.expect("crash report not written");
+    println!("{}", report);
+    assert_eq!(report.matches("=== crash report ===").count(), 1);
+    assert!(report.contains("thread: report-writer"));
+    assert!(report.contains("message: disk full"));
+    assert!(report.contains("location: src/panic_lab.rs:"));
+    assert!(report.contains("backtrace:\n"));
+}
+
+/// 由 test_uncatchable_panics 在子进程里运行，直接运行时什么也不做
+#[test]
+#[ignore]
+fn abort_child() {
+    struct PanicOnDrop;
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            panic!("panic in drop");
+        }
+    }
+
+    extern "C" fn callback() {
+        panic!("panic across extern \"C\"");
+    }
+
+    match std::env::var("PANIC_LAB_CASE").as_deref() {
+        Ok("double panic") => {
+            let _ = recover(|| {
+                let _guard = PanicOnDrop;
+                panic!("first panic");
+            });
+        }
+        Ok("extern c") => {
+            let _ = recover(|| callback());
+        }
+        Ok("abort") => std::process::abort(),
+        _ => return,
+    }
+    unreachable!("the process should have aborted");
+}
+
+/**
+ * 捕获不了的 panic 会让整个进程退出，只能在子进程里演示:
+ * 把测试程序自己（current_exe）再运行一次，只运行 abort_child，看它的退出状态。
+ */
+#[test]
+#[cfg(unix)]
+fn test_uncatchable_panics() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    for case in ["double panic", "extern c", "abort"] {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["panic_lab::abort_child", "--exact", "--ignored"])
+            .env("PANIC_LAB_CASE", case)
+            .output()
+            .unwrap();
+        // SIGABRT = 6，recover 没能拦住
+        assert_eq!(output.status.signal(), Some(6), "case {}", case);
+    }
+}
@@ -0,0 +1,381 @@
+#![allow(dead_code)]
+
+/*
+ * 可恢复错误的重试
+ *      test_error.rs 开头说: 文件访问失败 "有可能是因为它正在被占用，是正常的，我们可以通过等待来解决"。
+ *      这里把 "等待" 写成通用的重试逻辑: 失败后等一段时间再试，直到成功、次数用完或者超过总时限。
+ *
+ * 等待多久（Backoff）
+ *      Fixed          每次都等同样的时间
+ *      Exponential    initial, initial * 2, initial * 4 ... 不超过 max，对方越忙等得越久
+ *      Jittered       在 [0, 指数退避的时间] 之间随机取一个值（full jitter），
+ *                     避免很多客户端在同一时刻一起重试，把刚恢复的服务再次压垮
+ *
+ * 哪些错误值得重试
+ *      只有暂时性的错误才重试: Interrupted、WouldBlock、TimedOut ...
+ *      NotFound、PermissionDenied 之类再等也不会变好，应该立刻返回
+ *
+ * 时钟（Clock）
+ *      重试逻辑只通过 Clock 读取时间和等待，测试时换成 FakeClock，sleep 只是把时间往前拨，测试瞬间完成。
+ */
+
+use crate::error::AppError;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub trait Clock {
+    /// 从某个固定起点开始经过的时间，只用来计算时间差
+    fn now(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// 测试用的时钟: sleep 不真的等待，只记录下来并推进时间
+#[derive(Default)]
+pub struct FakeClock {
+    now: Cell<Duration>,
+    sleeps: RefCell<Vec<Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> FakeClock {
+        FakeClock::default()
+    }
+
+    /// 模拟操作本身花掉的时间
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.borrow().clone()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.sleeps.borrow_mut().push(duration);
+        self.advance(duration);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed(Duration),
+    Exponential { initial: Duration, max: Duration },
+    Jittered { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    /// 第 retry 次重试（从 0 开始）之前等待的时间，random 只有 Jittered 会用到
+    fn delay(&self, retry: u32, random: u64) -> Duration {
+        let exponential = |initial: Duration, max: Duration| {
+            // 2^retry 很快就会溢出，超过 max 之后结果都一样，所以先限制指数
+            initial.saturating_mul(1 << retry.min(31)).min(max)
+        };
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => exponential(initial, max),
+            Backoff::Jittered { initial, max } => {
+                let ceiling = exponential(initial, max).as_nanos() as u64;
+                Duration::from_nanos(random % (ceiling + 1))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiveUp {
+    /// 错误不值得重试
+    Permanent,
+    AttemptsExhausted,
+    /// 再等下去就会超过总时限
+    DeadlineExceeded,
+}
+
+/// 放弃重试时返回最后一次的错误，以及放弃的原因
+#[derive(Debug)]
+pub struct RetryError<E> {
+    pub error: E,
+    pub attempts: u32,
+    pub reason: GiveUp,
+}
+
+impl<E> RetryError<E> {
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            GiveUp::Permanent => "not retryable",
+            GiveUp::AttemptsExhausted => "attempts exhausted",
+            GiveUp::DeadlineExceeded => "deadline exceeded",
+        };
+        write!(f, "gave up after {} attempt(s), {}", self.attempts, reason)
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RetryError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// 转换成 AppError 时把重试的信息作为一层 context 保留下来
+impl<E: Into<AppError> + fmt::Display> From<RetryError<E>> for AppError {
+    fn from(err: RetryError<E>) -> AppError {
+        AppError::Context {
+            message: err.to_string(),
+            source: Box::new(err.error.into()),
+        }
+    }
+}
+
+/// 暂时性的 I/O 错误，等一等可能就好了
+pub fn is_transient(err: &io::Error) -> bool {
+    // NOTE Windows 上文件被其它进程占用时返回的是 PermissionDenied，但在 Unix 上它表示真的没有权限，这里不重试
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// 只重试指定的几种错误
+pub fn retry_on(kinds: &[io::ErrorKind]) -> impl Fn(&io::Error) -> bool + '_ {
+    move |err| kinds.contains(&err.kind())
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    backoff: Backoff,
+    max_attempts: u32,
+    max_elapsed: Option<Duration>,
+    seed: u64,
+}
+
+impl RetryPolicy {
+    /// 默认最多尝试 3 次，没有总时限
+    pub fn new(backoff: Backoff) -> RetryPolicy {
+        RetryPolicy {
+            backoff,
+            max_attempts: 3,
+            max_elapsed: None,
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// 包括第一次在内的总尝试次数
+    pub fn max_attempts(mut self, attempts: u32) -> RetryPolicy {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// 从第一次尝试开始计算的总时限，如果下一次等待会超过它就不再重试
+    pub fn max_elapsed(mut self, limit: Duration) -> RetryPolicy {
+        self.max_elapsed = Some(limit);
+        self
+    }
+
+    /// 随机抖动的种子，固定种子可以让测试结果可复现
+    pub fn seed(mut self, seed: u64) -> RetryPolicy {
+        self.seed = seed.max(1);
+        self
+    }
+
+    /**
+     * 执行 op，失败且 should_retry 返回 true 时按退避策略等待后重试。
+     * op 的参数是第几次尝试（从 1 开始），方便记录日志。
+     */
+    pub fn retry<T, E, C: Clock + ?Sized>(
+        &self,
+        clock: &C,
+        should_retry: impl Fn(&E) -> bool,
+        mut op: impl FnMut(u32) -> Result<T, E>,
+    ) -> Result<T, RetryError<E>> {
+        let start = clock.now();
+        let mut random = self.seed;
+        let mut attempt = 1;
+        loop {
+            let error = match op(attempt) {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let reason = if !should_retry(&error) {
+                Some(GiveUp::Permanent)
+            } else if attempt >= self.max_attempts {
+                Some(GiveUp::AttemptsExhausted)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(RetryError {
+                    error,
+                    attempts: attempt,
+                    reason,
+                });
+            }
+
+            // xorshift64，只用来做抖动，不需要密码学强度
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            let delay = self.backoff.delay(attempt - 1, random);
+            if let Some(limit) = self.max_elapsed {
+                if clock.now() - start + delay > limit {
+                    return Err(RetryError {
+                        error,
+                        attempts: attempt,
+                        reason: GiveUp::DeadlineExceeded,
+                    });
+                }
+            }
+            clock.sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+/// 读取整个文件，遇到暂时性错误时按 policy 重试
+pub fn read_text_from_file<C: Clock + ?Sized>(
+    path: impl AsRef<Path>,
+    policy: &RetryPolicy,
+    clock: &C,
+) -> Result<String, RetryError<io::Error>> {
+    policy.retry(clock, is_transient, |_| std::fs::read_to_string(&path))
+}
+
+#[test]
+fn test_retry_backoff() {
+    let ms = Duration::from_millis;
+    let busy = || io::Error::new(io::ErrorKind::WouldBlock, "file is busy");
+
+    // 前两次失败，第三次成功
+    let clock = FakeClock::new();
+    let policy = RetryPolicy::new(Backoff::Fixed(ms(100))).max_attempts(5);
+    let result = policy.retry(&clock, is_transient, |attempt| {
+        if attempt < 3 {
+            Err(busy())
+        } else {
+            Ok(attempt)
+        }
+    });
+    assert_eq!(result.unwrap(), 3);
+    assert_eq!(clock.sleeps(), [ms(100), ms(100)]);
+
+    // 指数退避，封顶 max，次数用完后返回最后一次的错误
+    let clock = FakeClock::new();
+    let policy = RetryPolicy::new(Backoff::Exponential {
+        initial: ms(100),
+        max: ms(500),
+    })
+    .max_attempts(6);
+    let err = policy
+        .retry(&clock, is_transient, |_| Err::<(), _>(busy()))
+        .unwrap_err();
+    assert_eq!((err.attempts, err.reason), (6, GiveUp::AttemptsExhausted));
+    assert_eq!(
+        clock.sleeps(),
+        [ms(100), ms(200), ms(400), ms(500), ms(500)]
+    );
+    assert_eq!(clock.now(), ms(1700));
+
+    // 抖动: 每次等待都在 [0, 指数退避时间] 之内，同一个种子结果相同
+    let jittered = RetryPolicy::new(Backoff::Jittered {
+        initial: ms(100),
+        max: ms(1000),
+    })
+    .max_attempts(8)
+    .seed(42);
+    let run = || {
+        let clock = FakeClock::new();
+        let _ = jittered.retry(&clock, |_: &io::Error| true, |_| Err::<(), _>(busy()));
+        clock.sleeps()
+    };
+    let sleeps = run();
+    assert_eq!(sleeps.len(), 7);
+    for (i, sleep) in sleeps.iter().enumerate() {
+        assert!(*sleep <= ms(100 << i).min(ms(1000)));
+    }
+    assert_eq!(sleeps, run());
+    assert!(sleeps.windows(2).any(|w| w[0] != w[1]));
+}
+
+#[test]
+fn test_retry_give_up() {
+    let ms = Duration::from_millis;
+
+    // NotFound 再等也不会出现，第一次就放弃
+    let clock = FakeClock::new();
+    let policy = RetryPolicy::new(Backoff::Fixed(ms(50))).max_attempts(10);
+    let path = std::env::temp_dir().join(format!("retry-missing-{}", std::process::id()));
+    let err = read_text_from_file(&path, &policy, &clock).unwrap_err();
+    assert_eq!((err.attempts, err.reason), (1, GiveUp::Permanent));
+    assert_eq!(err.error.kind(), io::ErrorKind::NotFound);
+    assert!(clock.sleeps().is_empty());
+
+    // 总时限: 每次操作本身耗时 300ms，第三次失败后再等就会超过 1s
+    let clock = FakeClock::new();
+    let policy = policy.max_elapsed(ms(1000));
+    let err = policy
+        .retry(&clock, retry_on(&[io::ErrorKind::TimedOut]), |_| {
+            clock.advance(ms(300));
+            Err::<(), _>(io::Error::from(io::ErrorKind::TimedOut))
+        })
+        .unwrap_err();
+    assert_eq!((err.attempts, err.reason), (3, GiveUp::DeadlineExceeded));
+    assert_eq!(clock.now(), ms(1000));
+
+    // 转换成 AppError 后，重试信息成为错误链中的一层
+    let err = AppError::from(err);
+    assert_eq!(err.code(), 1004);
+    assert_eq!(
+        err.report(),
+        "error[1004]: gave up after 3 attempt(s), deadline exceeded\n  caused by: timed out"
+    );
+
+    // 文件存在时直接读到内容
+    std::fs::write(&path, "hello").unwrap();
+    let text = read_text_from_file(&path, &policy, &SystemClock::new()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(text, "hello");
+}
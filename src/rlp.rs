@@ -0,0 +1,621 @@
+#![allow(dead_code)]
+
+/*
+ * RLP（Recursive Length Prefix）是以太坊用来序列化交易、区块头、账户的编码格式。
+ * 它只认识两种东西: 字节串和列表（列表里面又是字节串或列表），所以天然是一个递归的枚举:
+ *
+ *      [0x00, 0x7f]        单个字节，小于 0x80 时就是它自己
+ *      [0x80, 0xb7]        0-55 字节的字节串，前缀 0x80 + 长度
+ *      [0xb8, 0xbf]        更长的字节串，前缀 0xb7 + "长度的字节数"，后面跟大端的长度
+ *      [0xc0, 0xf7]        总长 0-55 字节的列表，前缀 0xc0 + 长度
+ *      [0xf8, 0xff]        更长的列表，前缀 0xf7 + "长度的字节数"
+ *
+ * 整数按大端、去掉前导零后当作字节串编码，0 编码为空字节串。
+ *
+ * NOTE 同一个值只允许有一种编码（规范形式），否则同一笔交易可以有多个不同的哈希。
+ * 所以解码时要拒绝: 用 0x81 包装的单字节、用长格式表示的短数据、带前导零的长度和整数。
+ *
+ * NOTE 解码的输入可能来自网络上的其他节点（见 p2p），嵌套层数要有上限，
+ * 否则 [[[[...]]]] 这样的输入会让递归的 decode_item 栈溢出。
+ */
+
+use crate::error::DomainError;
+use crate::evm::word::Word;
+use std::fmt;
+
+/// 列表最多嵌套的层数，以太坊的交易和区块头都只有两三层
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RlpError {
+    /// 输入在读完一个完整的条目之前就结束了
+    UnexpectedEnd,
+    /// 顶层条目之后还有多余的字节
+    TrailingBytes,
+    /// 不是最短的编码形式
+    NonCanonical(&'static str),
+    /// 声明的长度超出了 usize 能表示的范围
+    Oversized,
+    /// 列表嵌套超过了 MAX_DEPTH 层
+    TooDeep,
+    ExpectedBytes,
+    ExpectedList,
+    /// 整数超出了目标类型的范围
+    IntegerOverflow,
+    /// 结构体字段个数和列表长度不一致
+    ListLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidUtf8,
+    /// 定长字节数组的长度不匹配
+    ByteLength {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for RlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RlpError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            RlpError::TrailingBytes => write!(f, "trailing bytes after item"),
+            RlpError::NonCanonical(why) => write!(f, "non-canonical encoding: {}", why),
+            RlpError::Oversized => write!(f, "declared length is too large"),
+            RlpError::TooDeep => write!(f, "lists nested deeper than {} levels", MAX_DEPTH),
+            RlpError::ExpectedBytes => write!(f, "expected a byte string, found a list"),
+            RlpError::ExpectedList => write!(f, "expected a list, found a byte string"),
+            RlpError::IntegerOverflow => write!(f, "integer does not fit the target type"),
+            RlpError::ListLength { expected, found } => {
+                write!(f, "expected a list of {} items, found {}", expected, found)
+            }
+            RlpError::InvalidUtf8 => write!(f, "string is not valid utf-8"),
+            RlpError::ByteLength { expected, found } => {
+                write!(f, "expected {} bytes, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RlpError {}
+
+impl DomainError for RlpError {
+    const CODE: u16 = 2004;
+}
+
+// ---------------------------------------------------------------------------------------------
+// 编码
+
+/// 长度的大端表示，去掉前导零
+fn be_minimal(n: usize) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+fn encode_header(len: usize, short_base: u8, out: &mut Vec<u8>) {
+    if len <= 55 {
+        out.push(short_base + len as u8);
+    } else {
+        let len_bytes = be_minimal(len);
+        // 短格式占用了 base..=base+55，长格式从 base+56 开始，即 base + 55 + 字节数
+        out.push(short_base + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(&len_bytes);
+    }
+}
+
+fn encode_into(item: &Item, out: &mut Vec<u8>) {
+    match item {
+        Item::Bytes(bytes) => {
+            if bytes.len() == 1 && bytes[0] < 0x80 {
+                out.push(bytes[0]);
+            } else {
+                encode_header(bytes.len(), 0x80, out);
+                out.extend_from_slice(bytes);
+            }
+        }
+        Item::List(items) => {
+            // NOTE 列表的前缀需要知道载荷的总长度，所以先把子条目编码到临时缓冲区
+            let mut payload = Vec::new();
+            for item in items {
+                encode_into(item, &mut payload);
+            }
+            encode_header(payload.len(), 0xc0, out);
+            out.extend_from_slice(&payload);
+        }
+    }
+}
+
+pub fn encode(item: &Item) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into(item, &mut out);
+    out
+}
+
+// ---------------------------------------------------------------------------------------------
+// 解码
+
+/// 读取长格式中的长度字段
+fn read_length(data: &[u8], len_of_len: usize) -> Result<usize, RlpError> {
+    let bytes = data.get(..len_of_len).ok_or(RlpError::UnexpectedEnd)?;
+    if bytes[0] == 0 {
+        return Err(RlpError::NonCanonical("length has leading zeros"));
+    }
+    if len_of_len > std::mem::size_of::<usize>() {
+        return Err(RlpError::Oversized);
+    }
+    let len = bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    if len <= 55 {
+        return Err(RlpError::NonCanonical("long form used for short payload"));
+    }
+    Ok(len)
+}
+
+/// 解码一个条目，返回条目和它占用的字节数，depth 是当前所在的列表层数
+fn decode_item(data: &[u8], depth: usize) -> Result<(Item, usize), RlpError> {
+    let prefix = *data.first().ok_or(RlpError::UnexpectedEnd)?;
+    let (is_list, header_len, payload_len) = match prefix {
+        0x00..=0x7f => return Ok((Item::Bytes(vec![prefix]), 1)),
+        0x80..=0xb7 => (false, 1, (prefix - 0x80) as usize),
+        0xb8..=0xbf => {
+            let len_of_len = (prefix - 0xb7) as usize;
+            (false, 1 + len_of_len, read_length(&data[1..], len_of_len)?)
+        }
+        0xc0..=0xf7 => (true, 1, (prefix - 0xc0) as usize),
+        0xf8..=0xff => {
+            let len_of_len = (prefix - 0xf7) as usize;
+            (true, 1 + len_of_len, read_length(&data[1..], len_of_len)?)
+        }
+    };
+
+    let end = header_len
+        .checked_add(payload_len)
+        .ok_or(RlpError::Oversized)?;
+    let payload = data.get(header_len..end).ok_or(RlpError::UnexpectedEnd)?;
+
+    if !is_list {
+        if payload_len == 1 && payload[0] < 0x80 {
+            return Err(RlpError::NonCanonical(
+                "single byte below 0x80 must not be prefixed",
+            ));
+        }
+        return Ok((Item::Bytes(payload.to_vec()), end));
+    }
+
+    if depth >= MAX_DEPTH {
+        return Err(RlpError::TooDeep);
+    }
+    let mut items = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        // NOTE 递归: 列表的载荷就是若干个首尾相接的条目
+        let (item, used) = decode_item(rest, depth + 1)?;
+        items.push(item);
+        rest = &rest[used..];
+    }
+    Ok((Item::List(items), end))
+}
+
+/// 解码一段完整的输入，不允许有多余的字节
+pub fn decode(data: &[u8]) -> Result<Item, RlpError> {
+    let (item, used) = decode_item(data, 0)?;
+    if used != data.len() {
+        return Err(RlpError::TrailingBytes);
+    }
+    Ok(item)
+}
+
+impl Item {
+    pub fn as_bytes(&self) -> Result<&[u8], RlpError> {
+        match self {
+            Item::Bytes(bytes) => Ok(bytes),
+            Item::List(_) => Err(RlpError::ExpectedBytes),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Item], RlpError> {
+        match self {
+            Item::List(items) => Ok(items),
+            Item::Bytes(_) => Err(RlpError::ExpectedList),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// 类型和 Item 之间的转换
+
+pub trait Encodable {
+    fn to_rlp(&self) -> Item;
+
+    fn rlp_bytes(&self) -> Vec<u8> {
+        encode(&self.to_rlp())
+    }
+}
+
+pub trait Decodable: Sized {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError>;
+
+    fn decode_rlp(data: &[u8]) -> Result<Self, RlpError> {
+        Self::from_rlp(&decode(data)?)
+    }
+}
+
+impl Encodable for Item {
+    fn to_rlp(&self) -> Item {
+        self.clone()
+    }
+}
+
+impl Decodable for Item {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        Ok(item.clone())
+    }
+}
+
+/// 整数转成去掉前导零的大端字节
+fn int_to_item(bytes: &[u8]) -> Item {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    Item::Bytes(bytes[start..].to_vec())
+}
+
+/// 规范的整数编码: 不能有前导零，长度不能超过目标类型
+fn int_from_item<const N: usize>(item: &Item) -> Result<[u8; N], RlpError> {
+    let bytes = item.as_bytes()?;
+    if bytes.first() == Some(&0) {
+        return Err(RlpError::NonCanonical("integer has leading zeros"));
+    }
+    if bytes.len() > N {
+        return Err(RlpError::IntegerOverflow);
+    }
+    let mut buf = [0u8; N];
+    buf[N - bytes.len()..].copy_from_slice(bytes);
+    Ok(buf)
+}
+
+// NOTE 故意不给 u8 实现，这样 Vec<u8> 才能表示 "字节串" 而不是 "由整数组成的列表"
+macro_rules! impl_rlp_uint {
+    ($($t:ty),*) => {
+        $(
+            impl Encodable for $t {
+                fn to_rlp(&self) -> Item {
+                    int_to_item(&self.to_be_bytes())
+                }
+            }
+
+            impl Decodable for $t {
+                fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+                    Ok(<$t>::from_be_bytes(int_from_item(item)?))
+                }
+            }
+        )*
+    };
+}
+
+impl_rlp_uint!(u16, u32, u64, u128, usize);
+
+impl Encodable for Word {
+    fn to_rlp(&self) -> Item {
+        int_to_item(&self.to_be_bytes())
+    }
+}
+
+impl Decodable for Word {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        Ok(Word::from_be_bytes(int_from_item(item)?))
+    }
+}
+
+impl Encodable for bool {
+    fn to_rlp(&self) -> Item {
+        (*self as u16).to_rlp()
+    }
+}
+
+impl Decodable for bool {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        match u16::from_rlp(item)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(RlpError::IntegerOverflow),
+        }
+    }
+}
+
+impl Encodable for Vec<u8> {
+    fn to_rlp(&self) -> Item {
+        Item::Bytes(self.clone())
+    }
+}
+
+impl Decodable for Vec<u8> {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        Ok(item.as_bytes()?.to_vec())
+    }
+}
+
+/// 定长字节数组，例如 20 字节的地址、32 字节的哈希
+impl<const N: usize> Encodable for [u8; N] {
+    fn to_rlp(&self) -> Item {
+        Item::Bytes(self.to_vec())
+    }
+}
+
+impl<const N: usize> Decodable for [u8; N] {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        let bytes = item.as_bytes()?;
+        bytes.try_into().map_err(|_| RlpError::ByteLength {
+            expected: N,
+            found: bytes.len(),
+        })
+    }
+}
+
+impl Encodable for str {
+    fn to_rlp(&self) -> Item {
+        Item::Bytes(self.as_bytes().to_vec())
+    }
+}
+
+impl Encodable for String {
+    fn to_rlp(&self) -> Item {
+        self.as_str().to_rlp()
+    }
+}
+
+impl Decodable for String {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        String::from_utf8(item.as_bytes()?.to_vec()).map_err(|_| RlpError::InvalidUtf8)
+    }
+}
+
+impl<T: Encodable> Encodable for Vec<T> {
+    fn to_rlp(&self) -> Item {
+        Item::List(self.iter().map(Encodable::to_rlp).collect())
+    }
+}
+
+impl<T: Decodable> Decodable for Vec<T> {
+    fn from_rlp(item: &Item) -> Result<Self, RlpError> {
+        item.as_list()?.iter().map(T::from_rlp).collect()
+    }
+}
+
+/// "派生" 宏: 把结构体按字段顺序编码成一个列表
+///     rlp_struct!(Transaction { nonce, to, value, data });
+// NOTE 真正的 #[derive] 需要单独的过程宏 crate，声明宏已经足够表达这个模式
+macro_rules! rlp_struct {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::rlp::Encodable for $name {
+            fn to_rlp(&self) -> $crate::rlp::Item {
+                $crate::rlp::Item::List(vec![
+                    $($crate::rlp::Encodable::to_rlp(&self.$field)),*
+                ])
+            }
+        }
+
+        impl $crate::rlp::Decodable for $name {
+            fn from_rlp(item: &$crate::rlp::Item) -> Result<Self, $crate::rlp::RlpError> {
+                let list = item.as_list()?;
+                let expected = [$(stringify!($field)),*].len();
+                if list.len() != expected {
+                    return Err($crate::rlp::RlpError::ListLength {
+                        expected,
+                        found: list.len(),
+                    });
+                }
+                let mut fields = list.iter();
+                Ok($name {
+                    $($field: $crate::rlp::Decodable::from_rlp(fields.next().unwrap())?),*
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use rlp_struct;
+
+#[cfg(test)]
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_ethereum_wiki_vectors() {
+    use crate::crypto::hash::hex;
+
+    let b = |s: &str| Item::Bytes(s.as_bytes().to_vec());
+    let l = Item::List;
+
+    let vectors = [
+        (b("dog"), "83646f67"),
+        (l(vec![b("cat"), b("dog")]), "c88363617483646f67"),
+        (b(""), "80"),
+        (l(vec![]), "c0"),
+        (Item::Bytes(vec![0x00]), "00"),
+        (Item::Bytes(vec![0x0f]), "0f"),
+        (Item::Bytes(vec![0x04, 0x00]), "820400"),
+        // 集合论中 3 的表示 [ [], [[]], [ [], [[]] ] ]
+        (
+            l(vec![
+                l(vec![]),
+                l(vec![l(vec![])]),
+                l(vec![l(vec![]), l(vec![l(vec![])])]),
+            ]),
+            "c7c0c1c0c3c0c1c0",
+        ),
+        (
+            b("Lorem ipsum dolor sit amet, consectetur adipisicing elit"),
+            "b8384c6f72656d20697073756d20646f6c6f722073697420616d65742c20636f6e7365637465747572206164697069736963696e6720656c6974",
+        ),
+    ];
+    for (item, expected) in vectors {
+        assert_eq!(hex(&encode(&item)), expected);
+        assert_eq!(decode(&unhex(expected)), Ok(item));
+    }
+
+    // 整数
+    assert_eq!(hex(&0u64.rlp_bytes()), "80");
+    assert_eq!(hex(&15u64.rlp_bytes()), "0f");
+    assert_eq!(hex(&1024u64.rlp_bytes()), "820400");
+    assert_eq!(hex(&100000u64.rlp_bytes()), "830186a0");
+    assert_eq!(
+        hex(&0x102030405060708090a0b0c0d0e0f2u128.rlp_bytes()),
+        "8f102030405060708090a0b0c0d0e0f2"
+    );
+    assert_eq!(u64::decode_rlp(&unhex("830186a0")), Ok(100000));
+
+    // 超过 55 字节的列表使用长格式
+    let long = vec![String::from("0123456789"); 6];
+    let encoded = long.rlp_bytes();
+    assert_eq!(&encoded[..2], &[0xf8, 66]);
+    assert_eq!(Vec::<String>::decode_rlp(&encoded), Ok(long));
+}
+
+#[test]
+fn test_canonical_checks() {
+    // 单个小字节被 0x81 包装
+    assert!(matches!(
+        decode(&[0x81, 0x05]),
+        Err(RlpError::NonCanonical(_))
+    ));
+    // 短字节串使用了长格式
+    assert!(matches!(
+        decode(&[0xb8, 0x02, 0x61, 0x62]),
+        Err(RlpError::NonCanonical(_))
+    ));
+    // 长度字段有前导零
+    let mut data = vec![0xb9, 0x00, 0x38];
+    data.extend(vec![0x61; 56]);
+    assert!(matches!(decode(&data), Err(RlpError::NonCanonical(_))));
+    // 整数有前导零
+    assert!(matches!(
+        u64::decode_rlp(&[0x82, 0x00, 0x01]),
+        Err(RlpError::NonCanonical(_))
+    ));
+
+    assert_eq!(decode(&[0x83, 0x61]), Err(RlpError::UnexpectedEnd));
+    assert_eq!(decode(&[]), Err(RlpError::UnexpectedEnd));
+    assert_eq!(decode(&[0x80, 0x80]), Err(RlpError::TrailingBytes));
+    // 列表声明的长度超出了实际内容
+    assert_eq!(decode(&[0xc3, 0x80]), Err(RlpError::UnexpectedEnd));
+    // 列表内部的条目越过了列表的边界
+    assert_eq!(
+        decode(&[0xc1, 0x82, 0x61, 0x62]),
+        Err(RlpError::UnexpectedEnd)
+    );
+
+    assert_eq!(
+        u16::decode_rlp(&[0x83, 0x01, 0x00, 0x00]),
+        Err(RlpError::IntegerOverflow)
+    );
+    assert_eq!(u64::decode_rlp(&[0xc0]), Err(RlpError::ExpectedBytes));
+
+    // 长度字段有 9 个字节，超出了 usize
+    let mut data = vec![0xbf];
+    data.extend([0xff; 9]);
+    assert_eq!(decode(&data), Err(RlpError::Oversized));
+
+    // NOTE 每层只要一个字节 0xc1，就能构造出嵌套很深的列表
+    let nested = |depth: usize| {
+        let mut item = Item::List(vec![]);
+        for _ in 1..depth {
+            item = Item::List(vec![item]);
+        }
+        encode(&item)
+    };
+    assert!(decode(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(decode(&nested(MAX_DEPTH + 1)), Err(RlpError::TooDeep));
+    assert_eq!(decode(&nested(2000)), Err(RlpError::TooDeep));
+}
+
+#[test]
+fn test_struct_roundtrip() {
+    #[derive(Debug, PartialEq)]
+    struct Transaction {
+        nonce: u64,
+        to: [u8; 20],
+        value: Word,
+        data: Vec<u8>,
+        memo: String,
+    }
+    rlp_struct!(Transaction {
+        nonce,
+        to,
+        value,
+        data,
+        memo
+    });
+
+    #[derive(Debug, PartialEq)]
+    struct Batch {
+        id: u32,
+        txs: Vec<Transaction>,
+    }
+    rlp_struct!(Batch { id, txs });
+
+    let tx = Transaction {
+        nonce: 9,
+        to: [0x35; 20],
+        value: Word::ONE.shl(200),
+        data: vec![],
+        memo: String::from("转账"),
+    };
+    let encoded = tx.rlp_bytes();
+    assert_eq!(Transaction::decode_rlp(&encoded), Ok(tx));
+
+    let batch = Batch {
+        id: 1,
+        txs: vec![
+            Transaction {
+                nonce: 0,
+                to: [1; 20],
+                value: Word::from_u64(5),
+                data: vec![0xa9, 0x05, 0x9c, 0xbb],
+                memo: String::new(),
+            },
+            Transaction {
+                nonce: 1,
+                to: [2; 20],
+                value: Word::ZERO,
+                data: vec![0; 100],
+                memo: String::from("long data"),
+            },
+        ],
+    };
+    let encoded = batch.rlp_bytes();
+    assert_eq!(Batch::decode_rlp(&encoded), Ok(batch));
+
+    // 字段个数不对
+    let wrong = Item::List(vec![Item::Bytes(vec![1])]);
+    assert_eq!(
+        Batch::from_rlp(&wrong),
+        Err(RlpError::ListLength {
+            expected: 2,
+            found: 1
+        })
+    );
+    // 地址长度不对
+    let wrong = Item::List(vec![
+        0u64.to_rlp(),
+        Item::Bytes(vec![1; 19]),
+        0u64.to_rlp(),
+        Item::Bytes(vec![]),
+        Item::Bytes(vec![]),
+    ]);
+    assert_eq!(
+        Transaction::from_rlp(&wrong),
+        Err(RlpError::ByteLength {
+            expected: 20,
+            found: 19
+        })
+    );
+}
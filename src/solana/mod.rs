@@ -0,0 +1,4 @@
+pub(crate) mod runtime;
+pub(crate) mod scheduler;
+
+// NOTE Solana 的程序（合约）本身无状态，所有状态都放在账户里；交易必须事先声明要读写哪些账户，运行时据此并行执行互不冲突的交易
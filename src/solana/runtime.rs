@@ -0,0 +1,806 @@
+#![allow(dead_code)]
+
+/*
+ * Solana 的账户模型
+ *      每个账户都有一个 owner（某个程序的地址），只有 owner 程序可以修改账户的 data、扣减它的 lamports。
+ *      任何程序都可以给别的账户 "加钱"，但不能 "拿钱"。
+ *      新账户默认属于系统程序（System Program），由它来分配空间并把所有权转交给别的程序。
+ *
+ * 指令必须列出用到的全部账户，并标明是否签名、是否可写:
+ *      可写账户  ~  &mut T   同一时刻只能有一笔交易持有
+ *      只读账户  ~  &T       可以被任意多笔交易同时持有
+ * 这正是借用规则: 一个可变借用，或者任意多个不可变借用，二者不能同时存在。
+ * 同一条指令里把同一个账户传两次，相当于对同一个值同时拿两个 &mut，这里直接拒绝。
+ *
+ * 运行时并不信任程序: 程序拿到的是账户的拷贝，执行完后运行时逐个比较前后的差异，违反规则就整笔交易作废。
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+
+pub type Pubkey = [u8; 32];
+
+pub const SYSTEM_PROGRAM_ID: Pubkey = [0; 32];
+/// 内置程序账户的 owner
+pub const NATIVE_LOADER_ID: Pubkey = [0xff; 32];
+
+/// 每个签名收取的手续费
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+/// 账户元数据的固定开销，按这么多字节计算租金
+const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+/// 每字节每年的租金，余额够付两年就免租
+const LAMPORTS_PER_BYTE_YEAR: u64 = 3480;
+const EXEMPTION_YEARS: u64 = 2;
+/// 单个账户 data 的最大长度，和 Solana 一样是 10 MiB
+// NOTE space 直接来自指令数据，不加限制的话任何交易都能让运行时分配内存失败而崩溃
+pub const MAX_ACCOUNT_DATA_LEN: u64 = 10 * 1024 * 1024;
+
+/// 免租所需的最低余额。余额不足的账户不允许存在（余额为 0 的账户会被回收）。
+/// data 过大导致溢出时返回 None，这样的账户不可能免租
+pub fn minimum_balance(data_len: usize) -> Option<u64> {
+    ACCOUNT_STORAGE_OVERHEAD
+        .checked_add(data_len as u64)?
+        .checked_mul(LAMPORTS_PER_BYTE_YEAR)?
+        .checked_mul(EXEMPTION_YEARS)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Default for Account {
+    fn default() -> Account {
+        Account {
+            lamports: 0,
+            data: Vec::new(),
+            owner: SYSTEM_PROGRAM_ID,
+            executable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    pub fn writable(pubkey: Pubkey, is_signer: bool) -> AccountMeta {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: true,
+        }
+    }
+
+    pub fn readonly(pubkey: Pubkey, is_signer: bool) -> AccountMeta {
+        AccountMeta {
+            pubkey,
+            is_signer,
+            is_writable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+/// 简化的交易: 省略了签名本身（签名校验见 state 模块），signers 视为已经验证过
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    /// 第一个签名者支付手续费
+    pub signers: Vec<Pubkey>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Transaction {
+    pub fn new(signers: Vec<Pubkey>, instructions: Vec<Instruction>) -> Transaction {
+        Transaction {
+            signers,
+            instructions,
+        }
+    }
+
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.signers.first()
+    }
+
+    /// 交易需要锁定的账户: (可写, 只读)。只要有一条指令要写，该账户就按可写锁定
+    pub fn account_locks(&self) -> (Vec<Pubkey>, Vec<Pubkey>) {
+        let mut writable: Vec<Pubkey> = self.fee_payer().into_iter().copied().collect();
+        let mut readonly = Vec::new();
+        for ins in &self.instructions {
+            for meta in &ins.accounts {
+                if meta.is_writable && !writable.contains(&meta.pubkey) {
+                    writable.push(meta.pubkey);
+                }
+            }
+        }
+        for ins in &self.instructions {
+            let keys = ins
+                .accounts
+                .iter()
+                .map(|m| &m.pubkey)
+                .chain(std::iter::once(&ins.program_id));
+            for key in keys {
+                if !writable.contains(key) && !readonly.contains(key) {
+                    readonly.push(*key);
+                }
+            }
+        }
+        (writable, readonly)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionError {
+    UnknownProgram,
+    InvalidInstructionData,
+    /// 同一条指令里重复传入同一个账户
+    DuplicateAccount,
+    NotEnoughAccounts,
+    MissingSigner,
+    InsufficientFunds,
+    AccountAlreadyInUse,
+    ReadonlyModified,
+    ExecutableModified,
+    /// 修改了不属于本程序的账户的 data
+    ExternalDataModified,
+    /// 扣减了不属于本程序的账户的 lamports
+    ExternalLamportSpend,
+    IllegalOwnerChange,
+    /// 指令执行前后 lamports 总量不相等
+    UnbalancedInstruction,
+    /// 申请的 data 长度超过了 MAX_ACCOUNT_DATA_LEN
+    InvalidAccountDataLength,
+    ArithmeticOverflow,
+    Custom(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    MissingFeePayer,
+    InsufficientFundsForFee,
+    InsufficientFundsForRent {
+        account: Pubkey,
+    },
+    InstructionError {
+        index: usize,
+        error: InstructionError,
+    },
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for InstructionError {}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::MissingFeePayer => write!(f, "transaction has no fee payer"),
+            TransactionError::InsufficientFundsForFee => write!(f, "insufficient funds for fee"),
+            TransactionError::InsufficientFundsForRent { account } => {
+                write!(f, "account {:02x?} is not rent exempt", &account[..4])
+            }
+            TransactionError::InstructionError { index, error } => {
+                write!(f, "instruction {} failed: {}", index, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// 程序看到的账户
+#[derive(Debug, Clone)]
+pub struct KeyedAccount {
+    pub key: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub account: Account,
+}
+
+/// 程序是无状态的，需要的一切都从参数里来。Sync 是为了让多个线程同时执行同一个程序
+pub trait Program: Send + Sync {
+    fn process(
+        &self,
+        program_id: &Pubkey,
+        accounts: &mut [KeyedAccount],
+        data: &[u8],
+    ) -> Result<(), InstructionError>;
+}
+
+// ---------------------------------------------------------------------------------------------
+// 系统程序
+
+/// 数据布局和 Solana 的 bincode 编码一致: 4 字节小端的变体编号，后面依次是各字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemInstruction {
+    /// 账户: [付款方(签名, 可写), 新账户(签名, 可写)]
+    CreateAccount {
+        lamports: u64,
+        space: u64,
+        owner: Pubkey,
+    },
+    /// 账户: [付款方(签名, 可写), 收款方(可写)]
+    Transfer { lamports: u64 },
+}
+
+impl SystemInstruction {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                out.extend(0u32.to_le_bytes());
+                out.extend(lamports.to_le_bytes());
+                out.extend(space.to_le_bytes());
+                out.extend(owner);
+            }
+            SystemInstruction::Transfer { lamports } => {
+                out.extend(2u32.to_le_bytes());
+                out.extend(lamports.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<SystemInstruction, InstructionError> {
+        let u64_at = |at: usize| -> Result<u64, InstructionError> {
+            data.get(at..at + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+                .ok_or(InstructionError::InvalidInstructionData)
+        };
+        let tag = data
+            .get(..4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let (ins, len) = match tag {
+            0 => {
+                let owner = data
+                    .get(20..52)
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                let ins = SystemInstruction::CreateAccount {
+                    lamports: u64_at(4)?,
+                    space: u64_at(12)?,
+                    owner: owner.try_into().unwrap(),
+                };
+                (ins, 52)
+            }
+            2 => (
+                SystemInstruction::Transfer {
+                    lamports: u64_at(4)?,
+                },
+                12,
+            ),
+            _ => return Err(InstructionError::InvalidInstructionData),
+        };
+        if data.len() != len {
+            return Err(InstructionError::InvalidInstructionData);
+        }
+        Ok(ins)
+    }
+
+    pub fn create_account(
+        from: Pubkey,
+        new: Pubkey,
+        lamports: u64,
+        space: u64,
+        owner: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::writable(from, true),
+                AccountMeta::writable(new, true),
+            ],
+            data: SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            }
+            .to_bytes(),
+        }
+    }
+
+    pub fn transfer(from: Pubkey, to: Pubkey, lamports: u64) -> Instruction {
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::writable(from, true),
+                AccountMeta::writable(to, false),
+            ],
+            data: SystemInstruction::Transfer { lamports }.to_bytes(),
+        }
+    }
+}
+
+pub struct SystemProgram;
+
+impl Program for SystemProgram {
+    fn process(
+        &self,
+        _program_id: &Pubkey,
+        accounts: &mut [KeyedAccount],
+        data: &[u8],
+    ) -> Result<(), InstructionError> {
+        let [from, to, ..] = accounts else {
+            return Err(InstructionError::NotEnoughAccounts);
+        };
+        if !from.is_signer {
+            return Err(InstructionError::MissingSigner);
+        }
+        let lamports = match SystemInstruction::from_bytes(data)? {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                // NOTE 新账户也要签名，防止别人抢先占用你的地址
+                if !to.is_signer {
+                    return Err(InstructionError::MissingSigner);
+                }
+                if to.account != Account::default() {
+                    return Err(InstructionError::AccountAlreadyInUse);
+                }
+                // 先检查再分配
+                if space > MAX_ACCOUNT_DATA_LEN {
+                    return Err(InstructionError::InvalidAccountDataLength);
+                }
+                to.account.data = vec![0; space as usize];
+                to.account.owner = owner;
+                lamports
+            }
+            SystemInstruction::Transfer { lamports } => lamports,
+        };
+        from.account.lamports = from
+            .account
+            .lamports
+            .checked_sub(lamports)
+            .ok_or(InstructionError::InsufficientFunds)?;
+        to.account.lamports = to
+            .account
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// 运行时
+
+pub struct Runtime {
+    accounts: HashMap<Pubkey, Account>,
+    programs: HashMap<Pubkey, Box<dyn Program>>,
+}
+
+impl Default for Runtime {
+    fn default() -> Runtime {
+        Runtime::new()
+    }
+}
+
+impl Runtime {
+    pub fn new() -> Runtime {
+        let mut runtime = Runtime {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+        };
+        runtime.register_program(SYSTEM_PROGRAM_ID, Box::new(SystemProgram));
+        runtime
+    }
+
+    /// 程序也是账户，只是 executable 为 true，不能被任何交易修改
+    pub fn register_program(&mut self, program_id: Pubkey, program: Box<dyn Program>) {
+        self.accounts.insert(
+            program_id,
+            Account {
+                // NOTE data 为空时不会溢出
+                lamports: minimum_balance(0).unwrap(),
+                data: Vec::new(),
+                owner: NATIVE_LOADER_ID,
+                executable: true,
+            },
+        );
+        self.programs.insert(program_id, program);
+    }
+
+    /// 创世分配，不经过交易
+    pub fn airdrop(&mut self, pubkey: Pubkey, lamports: u64) -> Result<(), InstructionError> {
+        let account = self.accounts.entry(pubkey).or_default();
+        account.lamports = account
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn balance(&self, pubkey: &Pubkey) -> u64 {
+        self.accounts.get(pubkey).map(|a| a.lamports).unwrap_or(0)
+    }
+
+    pub fn process_transaction(&mut self, tx: &Transaction) -> Result<(), TransactionError> {
+        let (writes, result) = self.execute(tx);
+        self.commit(writes);
+        result
+    }
+
+    /// 写回执行结果，余额为 0 的账户被回收
+    pub(crate) fn commit(&mut self, writes: Vec<(Pubkey, Account)>) {
+        for (key, account) in writes {
+            if account.lamports == 0 {
+                self.accounts.remove(&key);
+            } else {
+                self.accounts.insert(key, account);
+            }
+        }
+    }
+
+    /// 只读地执行一笔交易，返回要写回的账户和执行结果。
+    /// 指令失败时只写回扣过手续费的付款账户，其余修改全部丢弃
+    pub(crate) fn execute(
+        &self,
+        tx: &Transaction,
+    ) -> (Vec<(Pubkey, Account)>, Result<(), TransactionError>) {
+        let Some(payer) = tx.fee_payer().copied() else {
+            return (Vec::new(), Err(TransactionError::MissingFeePayer));
+        };
+        let load = |key: &Pubkey| self.accounts.get(key).cloned().unwrap_or_default();
+
+        let fee = LAMPORTS_PER_SIGNATURE * tx.signers.len() as u64;
+        let mut payer_account = load(&payer);
+        if payer_account.owner != SYSTEM_PROGRAM_ID || payer_account.lamports < fee {
+            return (Vec::new(), Err(TransactionError::InsufficientFundsForFee));
+        }
+        payer_account.lamports -= fee;
+
+        let (writable, readonly) = tx.account_locks();
+        let mut working: HashMap<Pubkey, Account> = writable
+            .iter()
+            .chain(&readonly)
+            .map(|key| (*key, load(key)))
+            .collect();
+        working.insert(payer, payer_account.clone());
+
+        let fee_only = vec![(payer, payer_account)];
+        for (index, ins) in tx.instructions.iter().enumerate() {
+            if let Err(error) = self.execute_instruction(tx, ins, &mut working) {
+                return (
+                    fee_only,
+                    Err(TransactionError::InstructionError { index, error }),
+                );
+            }
+        }
+
+        for key in &writable {
+            let account = &working[key];
+            let exempt =
+                minimum_balance(account.data.len()).is_some_and(|min| account.lamports >= min);
+            if account.lamports != 0 && !exempt {
+                return (
+                    fee_only,
+                    Err(TransactionError::InsufficientFundsForRent { account: *key }),
+                );
+            }
+        }
+        let writes = writable
+            .into_iter()
+            .map(|key| {
+                let account = working.remove(&key).unwrap();
+                (key, account)
+            })
+            .collect();
+        (writes, Ok(()))
+    }
+
+    fn execute_instruction(
+        &self,
+        tx: &Transaction,
+        ins: &Instruction,
+        working: &mut HashMap<Pubkey, Account>,
+    ) -> Result<(), InstructionError> {
+        let program = self
+            .programs
+            .get(&ins.program_id)
+            .ok_or(InstructionError::UnknownProgram)?;
+
+        let mut keyed = Vec::with_capacity(ins.accounts.len());
+        for (i, meta) in ins.accounts.iter().enumerate() {
+            if ins.accounts[..i].iter().any(|m| m.pubkey == meta.pubkey) {
+                return Err(InstructionError::DuplicateAccount);
+            }
+            if meta.is_signer && !tx.signers.contains(&meta.pubkey) {
+                return Err(InstructionError::MissingSigner);
+            }
+            keyed.push(KeyedAccount {
+                key: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+                account: working[&meta.pubkey].clone(),
+            });
+        }
+
+        program.process(&ins.program_id, &mut keyed, &ins.data)?;
+
+        // 程序交回账户后逐个检查
+        let (mut before, mut after) = (0u128, 0u128);
+        for ka in &keyed {
+            let pre = &working[&ka.key];
+            let post = &ka.account;
+            before += pre.lamports as u128;
+            after += post.lamports as u128;
+            if pre == post {
+                continue;
+            }
+            if !ka.is_writable {
+                return Err(InstructionError::ReadonlyModified);
+            }
+            if pre.executable || post.executable {
+                return Err(InstructionError::ExecutableModified);
+            }
+            if pre.owner != ins.program_id {
+                if post.owner != pre.owner {
+                    return Err(InstructionError::IllegalOwnerChange);
+                }
+                if post.data != pre.data {
+                    return Err(InstructionError::ExternalDataModified);
+                }
+                if post.lamports < pre.lamports {
+                    return Err(InstructionError::ExternalLamportSpend);
+                }
+            }
+        }
+        if before != after {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+
+        for ka in keyed {
+            working.insert(ka.key, ka.account);
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+
+/// 测试用的计数器程序: data 的前 8 字节是一个小端 u64，每次调用加一。
+/// 它故意不检查账户的 owner，越权的修改由运行时拦下
+#[cfg(test)]
+pub(crate) struct CounterProgram;
+
+#[cfg(test)]
+impl Program for CounterProgram {
+    fn process(
+        &self,
+        _program_id: &Pubkey,
+        accounts: &mut [KeyedAccount],
+        _data: &[u8],
+    ) -> Result<(), InstructionError> {
+        let counter = accounts
+            .first_mut()
+            .ok_or(InstructionError::NotEnoughAccounts)?;
+        let bytes = counter
+            .account
+            .data
+            .get_mut(..8)
+            .ok_or(InstructionError::Custom(1))?;
+        let n = u64::from_le_bytes((&*bytes).try_into().unwrap());
+        bytes.copy_from_slice(&(n + 1).to_le_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) const COUNTER_PROGRAM_ID: Pubkey = [0xc0; 32];
+
+#[cfg(test)]
+pub(crate) fn counter_value(runtime: &Runtime, key: &Pubkey) -> u64 {
+    let data = &runtime.account(key).unwrap().data;
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) fn increment(counter: Pubkey) -> Instruction {
+    Instruction {
+        program_id: COUNTER_PROGRAM_ID,
+        accounts: vec![AccountMeta::writable(counter, false)],
+        data: vec![],
+    }
+}
+
+#[test]
+fn test_system_program() {
+    let (alice, bob, carol) = ([1; 32], [2; 32], [3; 32]);
+    let mut runtime = Runtime::new();
+    runtime.airdrop(alice, 10_000_000).unwrap();
+
+    let transfer = SystemInstruction::transfer(alice, bob, 1_000_000);
+    assert_eq!(
+        SystemInstruction::from_bytes(&transfer.data),
+        Ok(SystemInstruction::Transfer {
+            lamports: 1_000_000
+        })
+    );
+    runtime
+        .process_transaction(&Transaction::new(vec![alice], vec![transfer]))
+        .unwrap();
+    assert_eq!(runtime.balance(&alice), 10_000_000 - 1_000_000 - 5000);
+    assert_eq!(runtime.balance(&bob), 1_000_000);
+
+    // 没有签名就想转走别人的钱
+    let steal = SystemInstruction::transfer(bob, carol, 10);
+    assert_eq!(
+        runtime.process_transaction(&Transaction::new(vec![alice], vec![steal])),
+        Err(TransactionError::InstructionError {
+            index: 0,
+            error: InstructionError::MissingSigner
+        })
+    );
+    // 失败的交易也要付手续费
+    assert_eq!(runtime.balance(&alice), 10_000_000 - 1_000_000 - 2 * 5000);
+    assert_eq!(runtime.balance(&bob), 1_000_000);
+
+    // 转账后余额不足以免租
+    let dust = SystemInstruction::transfer(alice, carol, 10);
+    assert_eq!(
+        runtime.process_transaction(&Transaction::new(vec![alice], vec![dust])),
+        Err(TransactionError::InsufficientFundsForRent { account: carol })
+    );
+    assert_eq!(runtime.account(&carol), None);
+
+    // 转走全部余额后账户被回收
+    let all = SystemInstruction::transfer(bob, carol, 1_000_000 - 5000);
+    runtime
+        .process_transaction(&Transaction::new(vec![bob], vec![all]))
+        .unwrap();
+    assert_eq!(runtime.account(&bob), None);
+    assert_eq!(runtime.balance(&carol), 995_000);
+
+    assert_eq!(
+        runtime.process_transaction(&Transaction::new(vec![bob], vec![])),
+        Err(TransactionError::InsufficientFundsForFee)
+    );
+
+    // 余额溢出时返回错误而不是 panic
+    assert_eq!(
+        runtime.airdrop(carol, u64::MAX),
+        Err(InstructionError::ArithmeticOverflow)
+    );
+    assert_eq!(runtime.balance(&carol), 995_000);
+    let dave = [4; 32];
+    runtime.airdrop(dave, u64::MAX - 10_000).unwrap();
+    let overflow = SystemInstruction::transfer(carol, dave, 100_000);
+    assert_eq!(
+        runtime.process_transaction(&Transaction::new(vec![carol], vec![overflow])),
+        Err(TransactionError::InstructionError {
+            index: 0,
+            error: InstructionError::ArithmeticOverflow
+        })
+    );
+    assert_eq!(minimum_balance(usize::MAX), None);
+}
+
+#[test]
+fn test_ownership_rules() {
+    let (alice, counter, other) = ([1; 32], [7; 32], [8; 32]);
+    let mut runtime = Runtime::new();
+    runtime.register_program(COUNTER_PROGRAM_ID, Box::new(CounterProgram));
+    runtime.airdrop(alice, 100_000_000).unwrap();
+
+    // 创建账户并把所有权交给计数器程序，同一笔交易里接着调用它
+    let rent = minimum_balance(8).unwrap();
+    let tx = Transaction::new(
+        vec![alice, counter],
+        vec![
+            SystemInstruction::create_account(alice, counter, rent, 8, COUNTER_PROGRAM_ID),
+            increment(counter),
+            increment(counter),
+        ],
+    );
+    runtime.process_transaction(&tx).unwrap();
+    assert_eq!(counter_value(&runtime, &counter), 2);
+    assert_eq!(runtime.account(&counter).unwrap().owner, COUNTER_PROGRAM_ID);
+
+    // 不能重复创建
+    let again = Transaction::new(
+        vec![alice, counter],
+        vec![SystemInstruction::create_account(
+            alice,
+            counter,
+            rent,
+            8,
+            COUNTER_PROGRAM_ID,
+        )],
+    );
+    assert!(matches!(
+        runtime.process_transaction(&again),
+        Err(TransactionError::InstructionError {
+            error: InstructionError::AccountAlreadyInUse,
+            ..
+        })
+    ));
+
+    // 计数器程序想改一个属于系统程序的账户
+    let tx = Transaction::new(
+        vec![alice, other],
+        vec![
+            SystemInstruction::create_account(alice, other, rent, 8, SYSTEM_PROGRAM_ID),
+            increment(other),
+        ],
+    );
+    assert_eq!(
+        runtime.process_transaction(&tx),
+        Err(TransactionError::InstructionError {
+            index: 1,
+            error: InstructionError::ExternalDataModified
+        })
+    );
+    // 整笔交易回滚，第一条指令创建的账户也不存在
+    assert_eq!(runtime.account(&other), None);
+
+    // 只读账户被修改
+    let readonly = Instruction {
+        program_id: COUNTER_PROGRAM_ID,
+        accounts: vec![AccountMeta::readonly(counter, false)],
+        data: vec![],
+    };
+    assert!(matches!(
+        runtime.process_transaction(&Transaction::new(vec![alice], vec![readonly])),
+        Err(TransactionError::InstructionError {
+            error: InstructionError::ReadonlyModified,
+            ..
+        })
+    ));
+
+    // 申请的空间过大，在分配内存之前就被拒绝
+    let huge = Transaction::new(
+        vec![alice, other],
+        vec![SystemInstruction::create_account(
+            alice,
+            other,
+            rent,
+            u64::MAX,
+            COUNTER_PROGRAM_ID,
+        )],
+    );
+    assert_eq!(
+        runtime.process_transaction(&huge),
+        Err(TransactionError::InstructionError {
+            index: 0,
+            error: InstructionError::InvalidAccountDataLength
+        })
+    );
+
+    // 同一个账户传两次，相当于两个 &mut
+    let twice = SystemInstruction::transfer(alice, alice, 1);
+    assert!(matches!(
+        runtime.process_transaction(&Transaction::new(vec![alice], vec![twice])),
+        Err(TransactionError::InstructionError {
+            error: InstructionError::DuplicateAccount,
+            ..
+        })
+    ));
+    assert_eq!(counter_value(&runtime, &counter), 2);
+}
@@ -0,0 +1,165 @@
+#![allow(dead_code)]
+
+/*
+ * 并行调度
+ *      交易事先声明了读写哪些账户，调度器不用执行就能知道哪些交易会冲突:
+ *          写 - 写  冲突      (两个 &mut)
+ *          读 - 写  冲突      (& 和 &mut 同时存在)
+ *          读 - 读  不冲突    (任意多个 &)
+ *      把交易分成若干批，同一批内互不冲突，可以放到不同线程上同时执行。
+ *
+ * 为了让并行执行的结果和按顺序逐笔执行完全一致，互相冲突的交易必须保持原来的先后顺序:
+ * 每笔交易放进 "最后一个和它冲突的批次" 的下一批。
+ */
+
+use super::runtime::{Pubkey, Runtime, Transaction, TransactionError};
+use std::collections::HashMap;
+use std::thread;
+
+/// 返回每一批交易在原数组中的下标
+pub fn schedule(txs: &[Transaction]) -> Vec<Vec<usize>> {
+    // 每个账户最后一次被读、被写的批次
+    let mut last_read: HashMap<Pubkey, usize> = HashMap::new();
+    let mut last_write: HashMap<Pubkey, usize> = HashMap::new();
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+
+    for (i, tx) in txs.iter().enumerate() {
+        let (writable, readonly) = tx.account_locks();
+        let after = |map: &HashMap<Pubkey, usize>, key: &Pubkey| map.get(key).map(|b| b + 1);
+        let batch = writable
+            .iter()
+            .flat_map(|key| [after(&last_read, key), after(&last_write, key)])
+            .chain(readonly.iter().map(|key| after(&last_write, key)))
+            .flatten()
+            .max()
+            .unwrap_or(0);
+
+        for key in writable {
+            last_write.insert(key, batch);
+        }
+        for key in readonly {
+            let entry = last_read.entry(key).or_insert(batch);
+            *entry = (*entry).max(batch);
+        }
+        if batch == batches.len() {
+            batches.push(Vec::new());
+        }
+        batches[batch].push(i);
+    }
+    batches
+}
+
+impl Runtime {
+    /// 按批次执行，同一批内的交易各开一个线程。结果的顺序和 txs 一致
+    pub fn process_parallel(&mut self, txs: &[Transaction]) -> Vec<Result<(), TransactionError>> {
+        let mut results = vec![Ok(()); txs.len()];
+        for batch in schedule(txs) {
+            // NOTE 执行阶段只需要 &self，所以多个线程可以共享运行时；写回阶段才需要 &mut self
+            let runtime = &*self;
+            let executed: Vec<_> = thread::scope(|s| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|&i| s.spawn(move || (i, runtime.execute(&txs[i]))))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            for (i, (writes, result)) in executed {
+                self.commit(writes);
+                results[i] = result;
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+use super::runtime::{
+    counter_value, increment, minimum_balance, CounterProgram, SystemInstruction,
+    COUNTER_PROGRAM_ID,
+};
+
+#[test]
+fn test_schedule_respects_conflicts() {
+    let key = |n: u8| -> Pubkey { [n; 32] };
+    let transfer = |from: u8, to: u8| {
+        Transaction::new(
+            vec![key(from)],
+            vec![SystemInstruction::transfer(key(from), key(to), 1)],
+        )
+    };
+    let txs = vec![
+        transfer(1, 2), // 0
+        transfer(3, 4), // 1 与 0 无关
+        transfer(2, 5), // 2 写 2，必须在 0 之后
+        transfer(6, 7), // 3 无关
+        transfer(5, 1), // 4 写 5 和 1，必须在 2 之后
+    ];
+    assert_eq!(schedule(&txs), vec![vec![0, 1, 3], vec![2], vec![4]]);
+
+    // 系统程序账户被所有交易只读地共享，不构成冲突
+    let (writable, readonly) = txs[0].account_locks();
+    assert_eq!(writable, vec![key(1), key(2)]);
+    assert_eq!(readonly, vec![[0; 32]]);
+}
+
+#[test]
+fn test_parallel_matches_sequential() {
+    let payers: Vec<Pubkey> = (1..=8).map(|n| [n; 32]).collect();
+    let counters: Vec<Pubkey> = (0x41..=0x43).map(|n| [n; 32]).collect();
+
+    let setup = || {
+        let mut runtime = Runtime::new();
+        runtime.register_program(COUNTER_PROGRAM_ID, Box::new(CounterProgram));
+        for payer in &payers {
+            runtime.airdrop(*payer, 10_000_000).unwrap();
+        }
+        let rent = minimum_balance(8).unwrap();
+        for counter in &counters {
+            let create =
+                SystemInstruction::create_account(payers[0], *counter, rent, 8, COUNTER_PROGRAM_ID);
+            runtime
+                .process_transaction(&Transaction::new(vec![payers[0], *counter], vec![create]))
+                .unwrap();
+        }
+        runtime
+    };
+
+    // 每个付款方轮流给某个计数器加一，再和搭档（下标异或 1）互相转账；中间夹一笔必定失败的交易
+    let mut txs = Vec::new();
+    for (i, payer) in payers.iter().enumerate() {
+        let counter = counters[i % counters.len()];
+        let next = payers[i ^ 1];
+        txs.push(Transaction::new(vec![*payer], vec![increment(counter)]));
+        txs.push(Transaction::new(
+            vec![*payer],
+            vec![SystemInstruction::transfer(*payer, next, 1000 * i as u64)],
+        ));
+    }
+    txs.push(Transaction::new(
+        vec![payers[0]],
+        vec![SystemInstruction::transfer(payers[1], payers[0], 1)],
+    ));
+
+    let mut sequential = setup();
+    let expected: Vec<_> = txs
+        .iter()
+        .map(|tx| sequential.process_transaction(tx))
+        .collect();
+
+    let mut parallel = setup();
+    let results = parallel.process_parallel(&txs);
+    assert_eq!(results, expected);
+    assert!(results.last().unwrap().is_err());
+    assert!(schedule(&txs).len() < txs.len());
+
+    for key in payers.iter() {
+        assert_eq!(parallel.account(key), sequential.account(key));
+    }
+    for counter in &counters {
+        assert_eq!(
+            counter_value(&parallel, counter),
+            counter_value(&sequential, counter)
+        );
+    }
+    assert_eq!(counter_value(&parallel, &counters[0]), 3);
+}
@@ -0,0 +1,513 @@
+#![allow(dead_code)]
+
+/*
+ * 账户模型的状态机（以太坊风格）
+ *      世界状态 = 地址 -> 账户（余额、nonce、代码、存储）
+ *      交易由发送者签名，nonce 必须严格递增，防止重放。
+ *
+ * 一笔交易的处理分两步:
+ *      1. 校验: 签名、nonce、余额、gas 下限，任何一项不通过则整笔交易被拒绝，状态不变
+ *      2. 执行: 先扣除 nonce 和 gas 预付款，再转账并执行合约代码。
+ *         合约执行失败时只回滚转账和存储修改，nonce 和已消耗的 gas 照样扣除
+ *
+ * 回滚用的是日志（journal）: 每次修改账户前记下旧值，回滚时倒序恢复。
+ * 这和所有权的思路一致: 状态只有一个可变的拥有者，要 "撤销" 只能自己记账，而不是保留多份拷贝。
+ */
+
+use crate::chain::Hash;
+use crate::crypto::ed25519::{PublicKey, SecretKey, Signature};
+use crate::crypto::hash::keccak256;
+use crate::evm::vm::{execute, Context, Storage};
+use crate::evm::word::Word;
+use crate::merkle::MerkleTree;
+use crate::rlp::{rlp_struct, Encodable, Item};
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub type Address = [u8; 20];
+
+/// 每笔交易的固定开销
+pub const TX_BASE_GAS: u64 = 21000;
+
+/// 地址 = 公钥 Keccak-256 哈希的后 20 字节
+pub fn address_of(public_key: &PublicKey) -> Address {
+    let hash = keccak256(public_key.as_bytes());
+    hash[12..].try_into().unwrap()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub balance: Word,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    pub storage: Storage,
+}
+
+impl Account {
+    /// 存储根: 按键排序后的 (key, value) 构成的 Merkle 树
+    fn storage_root(&self) -> Hash {
+        let mut slots: Vec<_> = self.storage.iter().collect();
+        slots.sort();
+        let leaves: Vec<Hash> = slots
+            .into_iter()
+            .map(|(k, v)| keccak256(&Item::List(vec![k.to_rlp(), v.to_rlp()]).rlp_bytes()))
+            .collect();
+        MerkleTree::from_leaves(&leaves).root()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub nonce: u64,
+    pub to: Address,
+    pub value: Word,
+    pub gas_limit: u64,
+    pub gas_price: u64,
+    pub data: Vec<u8>,
+}
+
+rlp_struct!(Transaction {
+    nonce,
+    to,
+    value,
+    gas_limit,
+    gas_price,
+    data
+});
+
+impl Transaction {
+    /// 被签名的内容: 交易 RLP 编码的哈希
+    pub fn signing_hash(&self) -> Hash {
+        keccak256(&self.rlp_bytes())
+    }
+
+    pub fn sign(self, key: &SecretKey) -> SignedTransaction {
+        let signature = key.sign(&self.signing_hash());
+        SignedTransaction {
+            tx: self,
+            public_key: key.public_key(),
+            signature,
+        }
+    }
+
+    /// gas 全部用完时的手续费
+    fn max_fee(&self) -> Word {
+        Word::from_u64(self.gas_limit).wrapping_mul(Word::from_u64(self.gas_price))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedTransaction {
+    pub tx: Transaction,
+    pub public_key: PublicKey,
+    pub signature: Signature,
+}
+
+impl SignedTransaction {
+    /// 验证签名并返回发送者地址
+    pub fn sender(&self) -> Result<Address, StateError> {
+        self.public_key
+            .verify(&self.tx.signing_hash(), &self.signature)
+            .map_err(|_| StateError::InvalidSignature)?;
+        Ok(address_of(&self.public_key))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    InvalidSignature,
+    NonceMismatch { expected: u64, found: u64 },
+    InsufficientBalance { required: Word, available: Word },
+    IntrinsicGas { limit: u64, required: u64 },
+    BalanceOverflow,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidSignature => write!(f, "invalid transaction signature"),
+            StateError::NonceMismatch { expected, found } => {
+                write!(f, "nonce mismatch: expected {}, found {}", expected, found)
+            }
+            StateError::InsufficientBalance {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient balance: required {:?}, available {:?}",
+                required, available
+            ),
+            StateError::IntrinsicGas { limit, required } => {
+                write!(f, "gas limit {} is below the intrinsic {}", limit, required)
+            }
+            StateError::BalanceOverflow => write!(f, "balance overflow"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// 交易收据: 校验通过的交易一定会上链，但合约执行本身可能失败
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub success: bool,
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    // NOTE 用 BTreeMap 保证遍历顺序固定，状态根才是确定的
+    accounts: BTreeMap<Address, Account>,
+    /// 修改前的旧值，None 表示修改前账户不存在
+    journal: Vec<(Address, Option<Account>)>,
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    pub fn balance(&self, address: &Address) -> Word {
+        self.accounts
+            .get(address)
+            .map(|a| a.balance)
+            .unwrap_or_default()
+    }
+
+    pub fn nonce(&self, address: &Address) -> u64 {
+        self.accounts.get(address).map(|a| a.nonce).unwrap_or(0)
+    }
+
+    /// 修改账户前先写日志
+    fn account_mut(&mut self, address: Address) -> &mut Account {
+        let old = self.accounts.get(&address).cloned();
+        self.journal.push((address, old));
+        self.accounts.entry(address).or_default()
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// 倒序撤销 checkpoint 之后的所有修改
+    pub fn revert_to(&mut self, checkpoint: usize) {
+        while self.journal.len() > checkpoint {
+            let (address, old) = self.journal.pop().unwrap();
+            match old {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+    }
+
+    /// 确认修改，丢弃日志
+    pub fn commit(&mut self) {
+        self.journal.clear();
+    }
+
+    /// 创世分配，不经过交易
+    pub fn credit(&mut self, address: Address, amount: Word) -> Result<(), StateError> {
+        let account = self.account_mut(address);
+        let (balance, overflow) = account.balance.overflowing_add(amount);
+        if overflow {
+            return Err(StateError::BalanceOverflow);
+        }
+        account.balance = balance;
+        Ok(())
+    }
+
+    /// 直接部署合约代码（没有实现 CREATE）
+    pub fn deploy(&mut self, address: Address, code: Vec<u8>) {
+        self.account_mut(address).code = code;
+    }
+
+    /// 状态根: 所有账户按地址排序后构成的 Merkle 树的根，任何一个字段变化都会改变它
+    pub fn state_root(&self) -> Hash {
+        let leaves: Vec<Hash> = self
+            .accounts
+            .iter()
+            .map(|(address, account)| {
+                let item = Item::List(vec![
+                    address.to_rlp(),
+                    account.nonce.to_rlp(),
+                    account.balance.to_rlp(),
+                    keccak256(&account.code).to_rlp(),
+                    account.storage_root().to_rlp(),
+                ]);
+                keccak256(&item.rlp_bytes())
+            })
+            .collect();
+        MerkleTree::from_leaves(&leaves).root()
+    }
+
+    fn validate(&self, stx: &SignedTransaction) -> Result<Address, StateError> {
+        let tx = &stx.tx;
+        let sender = stx.sender()?;
+        if tx.gas_limit < TX_BASE_GAS {
+            return Err(StateError::IntrinsicGas {
+                limit: tx.gas_limit,
+                required: TX_BASE_GAS,
+            });
+        }
+        let expected = self.nonce(&sender);
+        if tx.nonce != expected {
+            return Err(StateError::NonceMismatch {
+                expected,
+                found: tx.nonce,
+            });
+        }
+        let available = self.balance(&sender);
+        let (required, overflow) = tx.value.overflowing_add(tx.max_fee());
+        if overflow || required > available {
+            return Err(StateError::InsufficientBalance {
+                required,
+                available,
+            });
+        }
+        Ok(sender)
+    }
+
+    /// 应用一笔交易。返回 Err 时状态完全不变
+    pub fn apply(&mut self, stx: &SignedTransaction) -> Result<Receipt, StateError> {
+        let receipt = self.apply_uncommitted(stx)?;
+        self.commit();
+        Ok(receipt)
+    }
+
+    fn apply_uncommitted(&mut self, stx: &SignedTransaction) -> Result<Receipt, StateError> {
+        let sender = self.validate(stx)?;
+        let tx = &stx.tx;
+        let start = self.checkpoint();
+
+        // 预付全部 gas，nonce 加一，之后无论执行成功与否都不再撤销
+        let payer = self.account_mut(sender);
+        payer.nonce += 1;
+        payer.balance = payer.balance.wrapping_sub(tx.max_fee());
+        let after_fee = self.checkpoint();
+
+        let payer = self.account_mut(sender);
+        payer.balance = payer.balance.wrapping_sub(tx.value);
+        let receiver = self.account_mut(tx.to);
+        let (balance, overflow) = receiver.balance.overflowing_add(tx.value);
+        if overflow {
+            self.revert_to(start);
+            return Err(StateError::BalanceOverflow);
+        }
+        receiver.balance = balance;
+
+        let mut receipt = Receipt {
+            success: true,
+            gas_used: TX_BASE_GAS,
+            output: Vec::new(),
+        };
+        if !receiver.code.is_empty() {
+            let ctx = Context {
+                caller: Word::from_be_slice(&sender),
+                value: tx.value,
+                calldata: tx.data.clone(),
+                gas_limit: tx.gas_limit - TX_BASE_GAS,
+            };
+            let code = receiver.code.clone();
+            // NOTE 解释器只在成功时才把修改写回 storage
+            let outcome = execute(&code, &ctx, &mut receiver.storage);
+            receipt.success = outcome.is_success();
+            receipt.gas_used += outcome.gas_used;
+            receipt.output = outcome.output;
+            if !receipt.success {
+                self.revert_to(after_fee);
+            }
+        }
+
+        // 退还没用完的 gas，已用的部分直接销毁
+        let refund = Word::from_u64(tx.gas_limit - receipt.gas_used)
+            .wrapping_mul(Word::from_u64(tx.gas_price));
+        let payer = self.account_mut(sender);
+        payer.balance = payer.balance.wrapping_add(refund);
+        Ok(receipt)
+    }
+
+    /// 整个区块要么全部生效，要么全部不生效
+    pub fn apply_block(
+        &mut self,
+        txs: &[SignedTransaction],
+    ) -> Result<Vec<Receipt>, (usize, StateError)> {
+        let start = self.checkpoint();
+        let mut receipts = Vec::with_capacity(txs.len());
+        for (i, stx) in txs.iter().enumerate() {
+            match self.apply_uncommitted(stx) {
+                Ok(receipt) => receipts.push(receipt),
+                Err(err) => {
+                    self.revert_to(start);
+                    return Err((i, err));
+                }
+            }
+        }
+        self.commit();
+        Ok(receipts)
+    }
+}
+
+#[cfg(test)]
+fn test_key(n: u8) -> SecretKey {
+    SecretKey::from_seed(&[n; 32])
+}
+
+#[cfg(test)]
+fn transfer(nonce: u64, to: Address, value: u64) -> Transaction {
+    Transaction {
+        nonce,
+        to,
+        value: Word::from_u64(value),
+        gas_limit: TX_BASE_GAS,
+        gas_price: 1,
+        data: vec![],
+    }
+}
+
+#[test]
+fn test_transfer_and_validation() {
+    let alice = test_key(1);
+    let alice_addr = address_of(&alice.public_key());
+    let bob_addr = address_of(&test_key(2).public_key());
+
+    let mut state = State::new();
+    state.credit(alice_addr, Word::from_u64(100_000)).unwrap();
+    state.commit();
+
+    let receipt = state
+        .apply(&transfer(0, bob_addr, 500).sign(&alice))
+        .unwrap();
+    assert!(receipt.success);
+    assert_eq!(receipt.gas_used, TX_BASE_GAS);
+    assert_eq!(
+        state.balance(&alice_addr),
+        Word::from_u64(100_000 - 500 - 21000)
+    );
+    assert_eq!(state.balance(&bob_addr), Word::from_u64(500));
+    assert_eq!(state.nonce(&alice_addr), 1);
+
+    // 以下交易全部被拒绝，状态根不变
+    let root = state.state_root();
+
+    // 重放同一笔交易
+    assert_eq!(
+        state
+            .apply(&transfer(0, bob_addr, 500).sign(&alice))
+            .unwrap_err(),
+        StateError::NonceMismatch {
+            expected: 1,
+            found: 0
+        }
+    );
+    assert!(matches!(
+        state.apply(&transfer(1, bob_addr, 100_000).sign(&alice)),
+        Err(StateError::InsufficientBalance { .. })
+    ));
+    let mut cheap = transfer(1, bob_addr, 1);
+    cheap.gas_limit = 20_000;
+    assert_eq!(
+        state.apply(&cheap.sign(&alice)).unwrap_err(),
+        StateError::IntrinsicGas {
+            limit: 20_000,
+            required: TX_BASE_GAS
+        }
+    );
+    // 签名后篡改金额
+    let mut forged = transfer(1, bob_addr, 1).sign(&alice);
+    forged.tx.value = Word::from_u64(50_000);
+    assert_eq!(
+        state.apply(&forged).unwrap_err(),
+        StateError::InvalidSignature
+    );
+
+    assert_eq!(state.state_root(), root);
+}
+
+#[test]
+fn test_contract_call_rollback() {
+    use crate::evm::opcode::assemble;
+
+    let alice = test_key(1);
+    let alice_addr = address_of(&alice.public_key());
+    let store = [0xc1; 20];
+    let reverter = [0xc2; 20];
+
+    let mut state = State::new();
+    state.credit(alice_addr, Word::from_u64(1_000_000)).unwrap();
+    // 把 calldata 的第一个字写入存储槽 0
+    state.deploy(
+        store,
+        assemble("PUSH0 CALLDATALOAD PUSH0 SSTORE STOP").unwrap(),
+    );
+    // 先写存储再 REVERT
+    state.deploy(
+        reverter,
+        assemble("PUSH1 0x01 PUSH0 SSTORE PUSH0 PUSH0 REVERT").unwrap(),
+    );
+    state.commit();
+
+    let mut call = transfer(0, store, 7);
+    call.gas_limit = 100_000;
+    call.data = Word::from_u64(42).to_be_bytes().to_vec();
+    let receipt = state.apply(&call.sign(&alice)).unwrap();
+    assert!(receipt.success);
+    let contract = state.account(&store).unwrap();
+    assert_eq!(contract.storage.get(&Word::ZERO), Some(&Word::from_u64(42)));
+    assert_eq!(contract.balance, Word::from_u64(7));
+    let spent = 7 + receipt.gas_used;
+    assert_eq!(
+        state.balance(&alice_addr),
+        Word::from_u64(1_000_000 - spent)
+    );
+
+    // 执行失败: 转账和存储被回滚，但 nonce 增加且 gas 照样扣除
+    let mut call = transfer(1, reverter, 9);
+    call.gas_limit = 100_000;
+    let receipt = state.apply(&call.sign(&alice)).unwrap();
+    assert!(!receipt.success);
+    assert!(receipt.gas_used > TX_BASE_GAS);
+    let contract = state.account(&reverter).unwrap();
+    assert!(contract.storage.is_empty());
+    assert!(contract.balance.is_zero());
+    assert_eq!(state.nonce(&alice_addr), 2);
+    assert_eq!(
+        state.balance(&alice_addr),
+        Word::from_u64(1_000_000 - spent - receipt.gas_used)
+    );
+}
+
+#[test]
+fn test_block_is_atomic() {
+    let alice = test_key(1);
+    let alice_addr = address_of(&alice.public_key());
+    let bob = test_key(2);
+    let bob_addr = address_of(&bob.public_key());
+
+    let mut state = State::new();
+    state.credit(alice_addr, Word::from_u64(100_000)).unwrap();
+    state.commit();
+    let root = state.state_root();
+
+    // 第三笔 nonce 跳号，整个区块作废
+    let block = vec![
+        transfer(0, bob_addr, 30_000).sign(&alice),
+        transfer(0, alice_addr, 1).sign(&bob),
+        transfer(2, bob_addr, 1).sign(&alice),
+    ];
+    let (index, err) = state.apply_block(&block).unwrap_err();
+    assert_eq!(index, 2);
+    assert!(matches!(err, StateError::NonceMismatch { .. }));
+    assert_eq!(state.state_root(), root);
+    assert_eq!(state.nonce(&bob_addr), 0);
+
+    // 第二笔依赖第一笔给 bob 的钱
+    let receipts = state.apply_block(&block[..2]).unwrap();
+    assert_eq!(receipts.len(), 2);
+    assert_ne!(state.state_root(), root);
+    assert_eq!(state.balance(&bob_addr), Word::from_u64(30_000 - 1 - 21000));
+}
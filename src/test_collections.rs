@@ -42,6 +42,9 @@ fn test_array() {
 
     let a = &a[..2];
     println!("{:?}", a);
+
+    // NOTE 长度是数组类型的一部分，[i32; 2] 和 [i32; 3] 不是同一个类型
+    // NOTE 用 const 泛型让自己的类型也带上长度，见 linalg/matrix.rs
 }
 
 #[test]
@@ -49,6 +52,7 @@ fn test_slice() {
     // NOTE 这个切片是动词,不是golang中既是名词又是动词, Goalng中的字符串也是可以切片的
     // NOTE 但是无论是golang还是rust,它们的切片都是按照字节进行切分, 如果存在 中文等字符,
     // NOTE 则字符串切片可能会存在乱码问题
+    // NOTE 按字符、按字素簇安全切片，以及中英混排的显示宽度，见 text.rs
 
     // [..]: Goalng,Python
     // [:] : Rust
@@ -107,3 +111,183 @@ fn test_slice() {
     let s2 = &s1[..];
     println!("{s1}, {s2}");
 }
+
+/*
+ * 映射（Map）和集合（Set）
+ *      HashMap<K, V>: 哈希表，查找、插入平均 O(1)，遍历顺序不确定（每次运行都可能不同）
+ *      BTreeMap<K, V>: B 树，查找、插入 O(log n)，按键的顺序遍历，支持范围查询
+ *      HashSet<T> / BTreeSet<T>: 只有键没有值的 Map
+ *
+ * NOTE HashMap 默认使用 SipHash，能抵抗 "精心构造大量冲突的键" 的拒绝服务攻击，代价是比简单的哈希函数慢一些
+ *      自己实现的开放寻址哈希表（可以替换哈希函数、调整装载率）见 open_map.rs
+ */
+#[test]
+fn test_hashmap() {
+    use std::collections::HashMap;
+
+    let mut scores: HashMap<&str, i32> = HashMap::new();
+    scores.insert("alice", 90);
+    scores.insert("bob", 72);
+
+    // insert 返回旧值，键已经存在时会覆盖
+    assert_eq!(scores.insert("bob", 80), Some(72));
+    assert_eq!(scores.insert("carol", 65), None);
+
+    // get 返回 Option<&V>，键不存在时不会 panic；scores["dave"] 则会 panic
+    assert_eq!(scores.get("alice"), Some(&90));
+    assert_eq!(scores.get("dave"), None);
+    assert_eq!(scores["bob"], 80);
+    assert!(scores.contains_key("carol"));
+
+    if let Some(score) = scores.get_mut("carol") {
+        *score += 10;
+    }
+    assert_eq!(scores.remove("carol"), Some(75));
+    assert_eq!(scores.len(), 2);
+
+    // NOTE 遍历顺序不确定，需要稳定输出时先排序
+    let mut names: Vec<_> = scores.keys().copied().collect();
+    names.sort();
+    println!("{:?}", names);
+
+    // 从迭代器收集，键值对来自两个数组
+    let names = ["alice", "bob"];
+    let ages = [30, 25];
+    let ages: HashMap<_, _> = names.into_iter().zip(ages).collect();
+    assert_eq!(ages["bob"], 25);
+}
+
+#[test]
+fn test_entry() {
+    use std::collections::HashMap;
+
+    // entry 返回一个 "槽位"，无论键是否存在都只查找一次
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in "hello 世界 hello".chars().filter(|c| !c.is_whitespace()) {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    assert_eq!(counts[&'l'], 4);
+    assert_eq!(counts[&'世'], 1);
+
+    // and_modify: 已存在时修改，不存在时插入默认值
+    let mut stock: HashMap<&str, u32> = HashMap::new();
+    for item in ["apple", "pear", "apple"] {
+        stock.entry(item).and_modify(|n| *n += 10).or_insert(1);
+    }
+    assert_eq!(stock["apple"], 11);
+    assert_eq!(stock["pear"], 1);
+
+    // or_default + 值是集合: 按首字母分组
+    let mut groups: HashMap<char, Vec<&str>> = HashMap::new();
+    for word in ["rust", "go", "ruby", "python", "gleam"] {
+        groups
+            .entry(word.chars().next().unwrap())
+            .or_default()
+            .push(word);
+    }
+    assert_eq!(groups[&'r'], ["rust", "ruby"]);
+    assert_eq!(groups[&'g'], ["go", "gleam"]);
+
+    // or_insert_with 只在键不存在时才执行闭包，适合开销较大的默认值
+    let mut cache: HashMap<u64, u64> = HashMap::new();
+    let mut computed = 0;
+    for n in [10, 10, 20] {
+        cache.entry(n).or_insert_with(|| {
+            computed += 1;
+            n * n
+        });
+    }
+    assert_eq!(computed, 2);
+}
+
+#[test]
+fn test_hashset() {
+    use std::collections::{BTreeSet, HashSet};
+
+    // 去重
+    let langs = ["rust", "go", "rust", "zig", "go"];
+    let unique: HashSet<&str> = langs.into_iter().collect();
+    assert_eq!(unique.len(), 3);
+
+    // insert 返回是否是新元素
+    let mut seen = HashSet::new();
+    let first_dup = [3, 1, 4, 1, 5].into_iter().find(|n| !seen.insert(*n));
+    assert_eq!(first_dup, Some(1));
+
+    // 集合运算: 两段文本共有的词、各自独有的词
+    let a: HashSet<&str> = "the quick brown fox".split(' ').collect();
+    let b: HashSet<&str> = "the lazy brown dog".split(' ').collect();
+    // NOTE HashSet 的结果无序，收集进 BTreeSet 之后就是有序的，方便比较和打印
+    let common: BTreeSet<_> = a.intersection(&b).copied().collect();
+    let only_a: BTreeSet<_> = a.difference(&b).copied().collect();
+    let either: BTreeSet<_> = a.symmetric_difference(&b).copied().collect();
+    assert_eq!(common, BTreeSet::from(["brown", "the"]));
+    assert_eq!(only_a, BTreeSet::from(["fox", "quick"]));
+    assert_eq!(either.len(), 4);
+    assert_eq!(a.union(&b).count(), 6);
+    assert!(common.iter().all(|w| a.contains(w) && b.contains(w)));
+}
+
+/*
+ * HashMap 和 BTreeMap 的取舍
+ *      需要按键排序输出、范围查询、取最大最小值 -> BTreeMap
+ *      只需要按键查找，数据量大 -> HashMap 通常更快
+ *      键类型只实现了 Ord 没有实现 Hash（或者反过来）时，只能选另一个
+ */
+#[test]
+fn test_btreemap() {
+    use std::collections::{BTreeMap, HashMap};
+    use std::time::Instant;
+
+    let mut prices = BTreeMap::new();
+    for (day, price) in [(5, 103), (1, 100), (3, 98), (2, 101), (4, 99)] {
+        prices.insert(day, price);
+    }
+    // 无论插入顺序如何，遍历总是按键升序
+    assert_eq!(prices.keys().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    assert_eq!(prices.first_key_value(), Some((&1, &100)));
+    assert_eq!(prices.last_key_value(), Some((&5, &103)));
+    // 范围查询: 第 2 到第 4 天
+    let mid: Vec<_> = prices.range(2..=4).map(|(_, p)| *p).collect();
+    assert_eq!(mid, [101, 98, 99]);
+    // 第 3 天之前（不含）最近的一条记录
+    assert_eq!(prices.range(..3).next_back(), Some((&2, &101)));
+
+    // 同样的数据放进 HashMap，遍历顺序和插入顺序、键的大小都无关
+    let hashed: HashMap<_, _> = prices.iter().map(|(k, v)| (*k, *v)).collect();
+    println!("HashMap 顺序: {:?}", hashed.keys().collect::<Vec<_>>());
+    println!("BTreeMap 顺序: {:?}", prices.keys().collect::<Vec<_>>());
+
+    // 粗略的性能对比，只打印不断言，debug 构建下的数字仅供参考
+    const N: u64 = 50_000;
+    let keys: Vec<u64> = (0..N)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+
+    let start = Instant::now();
+    let mut hash = HashMap::new();
+    for &k in &keys {
+        hash.insert(k, k);
+    }
+    let hits = keys.iter().filter(|k| hash.contains_key(k)).count();
+    let hash_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut tree = BTreeMap::new();
+    for &k in &keys {
+        tree.insert(k, k);
+    }
+    let tree_hits = keys.iter().filter(|k| tree.contains_key(k)).count();
+    let tree_time = start.elapsed();
+
+    assert_eq!(hits, tree_hits);
+    println!(
+        "{} 次插入 + 查找: HashMap {:?}, BTreeMap {:?}",
+        N, hash_time, tree_time
+    );
+
+    // 完整的例子: 中英混排文本的词频统计，见 wordfreq.rs
+    let mut freq = crate::wordfreq::WordFreq::new();
+    freq.add_text("map 和 set 是最常用的集合, map map");
+    assert_eq!(freq.top_words(1), vec![("map".to_string(), 3)]);
+}
@@ -2,6 +2,7 @@
  * Rust 有一套独特的处理异常情况的机制，它并不像其它语言中的 try 机制那样简单。
  * 首先，程序中一般会出现两种错误：可恢复错误和不可恢复错误。
  * 可恢复错误的典型案例是文件访问错误，如果访问一个文件失败，有可能是因为它正在被占用，是正常的，我们可以通过等待来解决。
+ * NOTE 按退避策略等待后重试的实现见 retry.rs
  * 但还有一种错误是由编程中无法解决的逻辑错误导致的，例如访问数组末尾以外的位置。
  * 大多数编程语言不区分这两种错误，并用 Exception （异常）类来表示错误。
  * 在 Rust 中没有 Exception。
@@ -15,6 +16,8 @@ fn test_panic() {
 
     // RUST_BACKTRACE=full, 告诉编译器运行时,如何打印堆栈信息
     // note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+
+    // NOTE 自定义 panic hook 写崩溃报告、用 catch_unwind 实现 Golang 的 recover，见 panic_lab.rs
 }
 
 #[test]
@@ -39,10 +42,22 @@ fn test_result() {
     // NOTE 如果想使一个可恢复错误按不可恢复错误处理，Result 类提供了两个办法：
     // unwrap() 和 expect(message: &str) ：
     // 相当于在 Result 为 Err 时调用 panic! 宏。两者的区别在于 expect 能够向 panic! 宏发送一段指定的错误信息。
-    let f1 = File::open("hello.txt").unwrap();
-    println!("{}", f1.metadata().is_err());
-    let f2 = File::open("hello.txt").expect("Failed to open.");
-    println!("{}", f2.metadata().is_err())
+    // NOTE 这里读的是内存里的文件（见 vfs.rs），不依赖当前目录下是否真的有 hello.txt，否则新检出的仓库里这里就会 panic
+    use crate::vfs::{FileSystem, MemFs};
+    use std::path::Path;
+    let fs = MemFs::new().with_file("hello.txt", "Hello, Rust!");
+    let f1 = fs.read_to_string(Path::new("hello.txt")).unwrap();
+    println!("{}", f1);
+    let f2 = fs
+        .metadata(Path::new("hello.txt"))
+        .expect("Failed to open.");
+    assert_eq!(f2.len, f1.len() as u64);
+
+    // 文件不存在时 unwrap 就会 panic，这正是 "把可恢复错误当作不可恢复错误处理"
+    let missing = std::panic::catch_unwind(|| {
+        fs.read_to_string(Path::new("missing.txt")).unwrap();
+    });
+    assert!(missing.is_err());
 }
 
 #[test]
@@ -82,6 +97,8 @@ fn test_err_pass() {
     /*
       NOTE ? 符的实际作用是将 Result 类非异常的值直接取出，如果有异常就将异常 Result 返回出去。
     * 所以，? 符仅用于返回值类型为 Result<T, E> 的函数，其中 E 类型必须和 ? 所处理的 Result 的 E 类型一致。
+    * 准确地说是 E 必须实现 From<?处理的错误类型>，? 会自动调用 From::from 转换，
+    * 多种错误汇总成一个枚举、附加上下文、打印错误链，见 error.rs
     */
 
     let r = g(10000);
@@ -102,26 +119,44 @@ fn test_kind() {
      * 但是这样需要判断 Result 的 Err 类型，获取 Err 类型的函数是 kind()。
      */
 
-    use std::fs::File;
+    use crate::vfs::{FileSystem, MemFs};
     use std::io;
     use std::io::Read;
-    fn read_text_from_file(path: &str) -> Result<String, io::Error> {
-        let mut f = File::open(path)?;
+    use std::path::Path;
+    // 接收 &dyn FileSystem 而不是直接调用 File::open，测试时可以换成内存里的文件（见 vfs.rs）
+    fn read_text_from_file(fs: &dyn FileSystem, path: &str) -> Result<String, io::Error> {
+        let mut f = fs.open(Path::new(path))?;
         let mut s = String::new();
         f.read_to_string(&mut s)?;
         Ok(s)
     }
 
-    let str_file = read_text_from_file("hello.txt");
-    match str_file {
-        Ok(s) => println!("{}", s),
-        Err(e) => match e.kind() {
-            io::ErrorKind::NotFound => {
-                println!("No such file");
-            }
-            _ => {
-                println!("Cannot read the file");
-            }
-        },
+    let fs = MemFs::new()
+        .with_file("hello.txt", "Hello, Rust!")
+        .with_unreadable("secret.txt", "top secret");
+
+    for path in ["hello.txt", "missing.txt", "secret.txt"] {
+        let str_file = read_text_from_file(&fs, path);
+        let kind = str_file.as_ref().err().map(io::Error::kind);
+        match str_file {
+            Ok(s) => println!("{}", s),
+            Err(e) => match e.kind() {
+                io::ErrorKind::NotFound => {
+                    println!("No such file");
+                }
+                io::ErrorKind::PermissionDenied => {
+                    println!("Permission denied");
+                }
+                _ => {
+                    println!("Cannot read the file");
+                }
+            },
+        }
+        let expected = match path {
+            "hello.txt" => None,
+            "missing.txt" => Some(io::ErrorKind::NotFound),
+            _ => Some(io::ErrorKind::PermissionDenied),
+        };
+        assert_eq!(kind, expected);
     }
 }
@@ -45,3 +45,304 @@ fn test_func() {
     }
     show(4);
 }
+
+/*
+ * 闭包的三个 trait，按 "闭包体怎么使用捕获的变量" 区分:
+ *      Fn      只读捕获的变量（&T），可以调用任意多次，也可以同时被多处共享调用
+ *      FnMut   修改捕获的变量（&mut T），可以调用多次，但调用时需要 mut 绑定，不能共享
+ *      FnOnce  把捕获的变量移走（T），只能调用一次
+ * 三者是包含关系: Fn 一定是 FnMut，FnMut 一定是 FnOnce。
+ * 所以参数要求 FnOnce 的地方什么闭包都能传，要求 Fn 的地方限制最多。
+ *
+ * NOTE move 关键字只决定 "怎么捕获"（所有权移进闭包），不决定实现哪个 trait，
+ *      move || println!("{}", s) 仍然是 Fn，因为闭包体只读 s
+ */
+
+// NOTE 下面的辅助函数只在测试里用到，和测试一起放进 cfg(test) 模块
+#[cfg(test)]
+mod closures {
+    fn call_fn(f: impl Fn() -> String) -> (String, String) {
+        (f(), f())
+    }
+
+    fn call_fn_mut(mut f: impl FnMut() -> i32) -> (i32, i32) {
+        (f(), f())
+    }
+
+    fn call_fn_once(f: impl FnOnce() -> Vec<i32>) -> Vec<i32> {
+        f()
+        // f(); // NOTE error: use of moved value: `f`
+    }
+
+    /// compose(f, g)(x) == g(f(x))，先 f 后 g，和管道的顺序一致
+    fn compose<A, B, C>(f: impl Fn(A) -> B, g: impl Fn(B) -> C) -> impl Fn(A) -> C {
+        move |x| g(f(x))
+    }
+
+    /// 把多个 Fn(T) -> T 串成一个，空列表就是恒等函数
+    fn pipeline<T>(fs: Vec<Box<dyn Fn(T) -> T>>) -> impl Fn(T) -> T {
+        move |x| fs.iter().fold(x, |acc, f| f(acc))
+    }
+
+    /**
+     * 柯里化: f(a, b) -> f(a)(b)
+     * NOTE 返回的内层闭包要捕获 a 和 f，每次调用外层都要生成一个新的闭包，
+     *      类型写不出来（impl Fn 不能嵌套在 Fn 的返回值里），只能装箱成 Box<dyn Fn>
+     */
+    fn curry<A, B, C>(f: impl Fn(A, B) -> C + Clone + 'static) -> impl Fn(A) -> Box<dyn Fn(B) -> C>
+    where
+        A: Clone + 'static,
+    {
+        move |a| {
+            let f = f.clone();
+            Box::new(move |b| f(a.clone(), b))
+        }
+    }
+
+    fn uncurry<A, B, C, G: Fn(B) -> C>(f: impl Fn(A) -> G) -> impl Fn(A, B) -> C {
+        move |a, b| f(a)(b)
+    }
+
+    /// 偏函数: 固定第一个参数
+    fn partial<A: Clone, B, C>(f: impl Fn(A, B) -> C, a: A) -> impl Fn(B) -> C {
+        move |b| f(a.clone(), b)
+    }
+
+    /**
+     * 记忆化: 用 HashMap 缓存 参数 -> 结果，同样的参数只真正计算一次。
+     * f 的类型是 fn(&mut Memo, A) -> R（函数指针而不是闭包），这样递归时可以把 memo 自己传进去，
+     *      斐波那契 fib(n) = memo.get(n - 1) + memo.get(n - 2)
+     * 如果 f 是捕获了 memo 的闭包，就会同时持有 memo 的 &mut 和 &，借用检查不通过。
+     */
+    struct Memo<A, R> {
+        f: fn(&mut Memo<A, R>, A) -> R,
+        cache: std::collections::HashMap<A, R>,
+        calls: usize,
+    }
+
+    impl<A: std::hash::Hash + Eq + Clone, R: Clone> Memo<A, R> {
+        fn new(f: fn(&mut Memo<A, R>, A) -> R) -> Memo<A, R> {
+            Memo {
+                f,
+                cache: std::collections::HashMap::new(),
+                calls: 0,
+            }
+        }
+
+        fn get(&mut self, arg: A) -> R {
+            if let Some(r) = self.cache.get(&arg) {
+                return r.clone();
+            }
+            self.calls += 1;
+            let r = (self.f)(self, arg.clone());
+            self.cache.insert(arg, r.clone());
+            r
+        }
+    }
+
+    /// 不需要递归时，任意 FnMut 都可以包一层缓存
+    fn memoize<A, R>(mut f: impl FnMut(A) -> R) -> impl FnMut(A) -> R
+    where
+        A: std::hash::Hash + Eq + Clone,
+        R: Clone,
+    {
+        let mut cache = std::collections::HashMap::new();
+        move |arg: A| cache.entry(arg.clone()).or_insert_with(|| f(arg)).clone()
+    }
+
+    /**
+     * 事件回调注册表: 事件名 -> 一组装箱的闭包
+     *      on    注册 FnMut 回调，可以反复触发，返回 id 用于 off 注销
+     *      once  注册 FnOnce 回调，触发一次后自动移除
+     * 不同闭包的类型各不相同，要放进同一个 Vec 只能用 trait 对象 Box<dyn FnMut>。
+     * NOTE 'a 让回调可以借用外部的局部变量（比如一个计数器），而不必是 'static
+     */
+    enum Callback<'a> {
+        Many(Box<dyn FnMut(&str) + 'a>),
+        Once(Box<dyn FnOnce(&str) + 'a>),
+    }
+
+    #[derive(Default)]
+    struct EventBus<'a> {
+        handlers: std::collections::HashMap<String, Vec<(usize, Callback<'a>)>>,
+        next_id: usize,
+    }
+
+    impl<'a> EventBus<'a> {
+        fn new() -> EventBus<'a> {
+            EventBus::default()
+        }
+
+        fn register(&mut self, event: &str, callback: Callback<'a>) -> usize {
+            self.next_id += 1;
+            self.handlers
+                .entry(event.to_string())
+                .or_default()
+                .push((self.next_id, callback));
+            self.next_id
+        }
+
+        fn on(&mut self, event: &str, f: impl FnMut(&str) + 'a) -> usize {
+            self.register(event, Callback::Many(Box::new(f)))
+        }
+
+        fn once(&mut self, event: &str, f: impl FnOnce(&str) + 'a) -> usize {
+            self.register(event, Callback::Once(Box::new(f)))
+        }
+
+        fn off(&mut self, id: usize) -> bool {
+            for list in self.handlers.values_mut() {
+                if let Some(i) = list.iter().position(|(h, _)| *h == id) {
+                    list.remove(i);
+                    return true;
+                }
+            }
+            false
+        }
+
+        /// 按注册顺序调用，返回被调用的回调个数
+        fn emit(&mut self, event: &str, payload: &str) -> usize {
+            let Some(list) = self.handlers.get_mut(event) else {
+                return 0;
+            };
+            let count = list.len();
+            // NOTE FnOnce 调用时要拿走所有权，所以先把整个列表取出来，再把 Many 放回去
+            for (id, callback) in std::mem::take(list) {
+                match callback {
+                    Callback::Many(mut f) => {
+                        f(payload);
+                        list.push((id, Callback::Many(f)));
+                    }
+                    Callback::Once(f) => f(payload),
+                }
+            }
+            count
+        }
+    }
+
+    #[test]
+    fn test_closure_traits() {
+        // Fn: 只读捕获
+        let greeting = String::from("hi");
+        let greet = || format!("{} there", greeting);
+        assert_eq!(call_fn(greet), ("hi there".into(), "hi there".into()));
+        assert_eq!(greeting, "hi"); // 只是借用，greeting 还能用
+
+        // FnMut: 修改捕获的计数器
+        let mut count = 0;
+        let counter = || {
+            count += 1;
+            count
+        };
+        // call_fn(counter); // NOTE error: expected a closure that implements `Fn`, this closure implements `FnMut`
+        assert_eq!(call_fn_mut(counter), (1, 2));
+        assert_eq!(count, 2);
+
+        // FnOnce: 闭包体把 v 移走了
+        let v = vec![1, 2, 3];
+        let consume = move || {
+            let mut v = v;
+            v.push(4);
+            v
+        };
+        // call_fn_mut(consume); // NOTE error: closure is `FnOnce` because it moves the variable `v` out of its environment
+        assert_eq!(call_fn_once(consume), [1, 2, 3, 4]);
+
+        // Fn 也可以传给要求 FnMut 和 FnOnce 的参数
+        let n = 7;
+        assert_eq!(call_fn_mut(|| n), (7, 7));
+        assert_eq!(call_fn_once(|| vec![n]), [7]);
+
+        // 普通函数和不捕获变量的闭包都实现了全部三个 trait，还能转成函数指针 fn
+        fn hello() -> String {
+            "hello".to_string()
+        }
+        assert_eq!(call_fn(hello).0, "hello");
+        let double: fn(i32) -> i32 = |x| x * 2;
+        assert_eq!([1, 2].map(double), [2, 4]);
+
+        // move 不改变 trait: s 被移进闭包，但闭包体只读，所以仍是 Fn
+        let s = String::from("moved");
+        let show = move || s.clone();
+        assert_eq!(call_fn(&show), ("moved".into(), "moved".into()));
+        // NOTE &F 在 F: Fn 时也实现了 Fn，所以传引用后 show 还能继续用
+        assert_eq!(show(), "moved");
+    }
+
+    #[test]
+    fn test_higher_order() {
+        let add_one = |x: i32| x + 1;
+        let to_string = |x: i32| format!("<{}>", x);
+        let f = compose(compose(add_one, |x| x * 10), to_string);
+        assert_eq!(f(2), "<30>");
+
+        let p = pipeline(vec![
+            Box::new(|x| x + 1),
+            Box::new(|x| x * x),
+            Box::new(|x| x - 1),
+        ]);
+        assert_eq!(p(3), 15);
+        assert_eq!(pipeline::<i32>(vec![])(5), 5);
+
+        let add = curry(|a: i32, b: i32| a + b);
+        let add5 = add(5);
+        assert_eq!((add5(1), add5(2), add(1)(1)), (6, 7, 2));
+        let join = curry(|a: String, b: &str| a + b);
+        assert_eq!(join("foo".to_string())("bar"), "foobar");
+        assert_eq!(uncurry(add)(3, 4), 7);
+
+        let pow = |base: u64, exp: u32| base.pow(exp);
+        let pow2 = partial(pow, 2);
+        assert_eq!((1..=4).map(&pow2).collect::<Vec<_>>(), [2, 4, 8, 16]);
+
+        // 递归的记忆化: 不缓存时 fib(80) 要调用上万亿次
+        let mut fib = Memo::new(|memo, n: u64| {
+            if n < 2 {
+                n
+            } else {
+                memo.get(n - 1) + memo.get(n - 2)
+            }
+        });
+        assert_eq!(fib.get(80), 23_416_728_348_467_685);
+        assert_eq!(fib.calls, 81);
+        fib.get(50);
+        assert_eq!(fib.calls, 81);
+
+        let mut slow_calls = 0;
+        let mut square = memoize(|x: (i32, i32)| {
+            slow_calls += 1;
+            x.0 * x.1
+        });
+        assert_eq!(
+            (square((3, 4)), square((3, 4)), square((4, 3))),
+            (12, 12, 12)
+        );
+        drop(square);
+        assert_eq!(slow_calls, 2);
+    }
+
+    #[test]
+    fn test_event_bus() {
+        let mut log = Vec::new();
+        let mut clicks = 0;
+        {
+            let mut bus = EventBus::new();
+            let id = bus.on("click", |p| clicks += p.len());
+            bus.on("click", |p| println!("clicked {}", p));
+            bus.once("close", |p| log.push(format!("closing {}", p)));
+
+            assert_eq!(bus.emit("click", "ok"), 2);
+            assert_eq!(bus.emit("click", "cancel"), 2);
+            assert_eq!(bus.emit("close", "window"), 1);
+            assert_eq!(bus.emit("close", "window"), 0); // once 已经移除
+            assert_eq!(bus.emit("unknown", ""), 0);
+
+            assert!(bus.off(id));
+            assert!(!bus.off(id));
+            assert_eq!(bus.emit("click", "ignored"), 1);
+            // NOTE bus 还借用着 clicks 和 log，离开作用域后才能读它们
+        }
+        assert_eq!(clicks, 8);
+        assert_eq!(log, ["closing window"]);
+    }
+}
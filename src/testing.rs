@@ -0,0 +1,31 @@
+/*
+ * 测试辅助
+ *      多个模块的随机化测试共用的工具，只在 cargo test 时编译。
+ */
+
+/// 测试用的伪随机数生成器（xorshift64），种子固定，失败时可以复现
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> XorShift {
+        XorShift(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// [0, n)，n 为 0 时返回 0
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+}
@@ -0,0 +1,311 @@
+#![allow(dead_code)]
+
+/*
+ * 面向中日韩（CJK）文本的字符串工具
+ *
+ * Rust 的 String 是 UTF-8 编码的字节序列，&s[a..b] 按字节切分:
+ *      "中" 占 3 个字节，&"中文"[0..2] 切在了字符中间，直接 panic。
+ * 而 "一个字符" 在不同场合有三种含义:
+ *      1. 字节（byte）: s.len()，UTF-8 中 1~4 个字节表示一个码点
+ *      2. 码点（char）: s.chars().count()，一个 Unicode 标量值
+ *      3. 字素簇（grapheme cluster）: 用户眼中的 "一个字"，
+ *         例如 "é" 可以是 e + 组合重音符两个码点，👨‍👩‍👧 是用零宽连接符连起来的 5 个码点
+ *
+ * 终端里的显示宽度又是另一回事: 汉字、假名、全角符号占 2 列，ASCII 占 1 列，组合符号占 0 列。
+ * 对齐表格、截断日志时要按显示宽度算，而不是按字节或字符个数。
+ *
+ * NOTE 字素簇的切分和宽度表都是 Unicode 标准（UAX #29、UAX #11）的简化子集，覆盖常见的 CJK、组合符号和 emoji，
+ * 完整实现需要几千行的数据表，生产环境应该使用 unicode-segmentation、unicode-width 这类 crate
+ */
+
+use std::borrow::Cow;
+use std::ops::{Bound, RangeBounds};
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const ELLIPSIS: char = '…';
+
+/// 组合符号和变体选择符: 附着在前一个字符上，自身不占宽度
+fn is_combining(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'     // 组合用附加符号
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{3099}'..='\u{309A}'   // 日文的浊音、半浊音组合符
+        | '\u{FE00}'..='\u{FE0F}'   // 变体选择符，例如让 ❤ 显示成 emoji 的 U+FE0F
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}' // emoji 肤色修饰符
+        | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// 成对出现表示国旗，例如 🇨🇳 = C + N
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// 终端中的显示宽度: 0、1 或 2 列
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || c == ZERO_WIDTH_JOINER || c == '\u{200B}' || is_combining(c) {
+        return 0;
+    }
+    let wide = matches!(c,
+        '\u{1100}'..='\u{115F}'     // 谚文字母
+        | '\u{2E80}'..='\u{303E}'   // 部首、CJK 符号和标点
+        | '\u{3041}'..='\u{33FF}'   // 平假名、片假名、注音等
+        | '\u{3400}'..='\u{4DBF}'   // CJK 扩展 A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK 统一汉字
+        | '\u{A000}'..='\u{A4CF}'   // 彝文
+        | '\u{AC00}'..='\u{D7A3}'   // 谚文音节
+        | '\u{F900}'..='\u{FAFF}'   // CJK 兼容汉字
+        | '\u{FE30}'..='\u{FE4F}'   // CJK 兼容形式
+        | '\u{FF00}'..='\u{FF60}'   // 全角 ASCII
+        | '\u{FFE0}'..='\u{FFE6}'   // 全角符号
+        | '\u{1F1E6}'..='\u{1F1FF}' // 区域指示符（国旗）
+        | '\u{1F300}'..='\u{1F64F}' // emoji
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{20000}'..='\u{2FFFD}' // CJK 扩展 B 以后
+        | '\u{30000}'..='\u{3FFFD}');
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+/// 整个字符串的显示宽度，按字素簇计算，emoji 序列不会被重复计数
+pub fn display_width(s: &str) -> usize {
+    graphemes(s).map(grapheme_width).sum()
+}
+
+/// 一个字素簇的宽度取其中最宽的码点: "e + 重音符" 是 1，"👨‍👩‍👧" 是 2
+fn grapheme_width(g: &str) -> usize {
+    g.chars().map(char_width).max().unwrap_or(0)
+}
+
+/// 按字素簇遍历
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices().peekable();
+        let (_, first) = chars.next()?;
+        let mut prev = first;
+        let mut end = self.rest.len();
+        // 一对区域指示符组成一个国旗，第三个开始新的一簇
+        let mut regional_pair = is_regional_indicator(first);
+        while let Some(&(i, c)) = chars.peek() {
+            let joins = (prev == '\r' && c == '\n')
+                || is_combining(c)
+                || c == ZERO_WIDTH_JOINER
+                || prev == ZERO_WIDTH_JOINER
+                || (regional_pair && is_regional_indicator(c));
+            if !joins {
+                end = i;
+                break;
+            }
+            regional_pair = false;
+            prev = c;
+            chars.next();
+        }
+        let (grapheme, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(grapheme)
+    }
+}
+
+/// 把 "第几个单位" 的范围换算成字节范围，starts 是每个单位的起始字节
+fn slice_units(
+    s: &str,
+    starts: impl Iterator<Item = usize>,
+    range: impl RangeBounds<usize>,
+) -> Option<&str> {
+    // 末尾补上字符串长度，这样 "最后一个单位之后" 也是合法的边界
+    let boundaries: Vec<usize> = starts.chain(std::iter::once(s.len())).collect();
+    let units = boundaries.len() - 1;
+    let from = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let to = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1)?,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => units,
+    };
+    if from > to || to > units {
+        return None;
+    }
+    Some(&s[boundaries[from]..boundaries[to]])
+}
+
+/// 按码点下标切片，越界时返回 None 而不是 panic
+pub fn char_slice(s: &str, range: impl RangeBounds<usize>) -> Option<&str> {
+    slice_units(s, s.char_indices().map(|(i, _)| i), range)
+}
+
+/// 按字素簇下标切片
+pub fn grapheme_slice(s: &str, range: impl RangeBounds<usize>) -> Option<&str> {
+    let starts = graphemes(s).scan(0, |offset, g| {
+        let start = *offset;
+        *offset += g.len();
+        Some(start)
+    });
+    slice_units(s, starts, range)
+}
+
+pub fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// 从第 start 个码点开始取 len 个，超出部分自动截掉，永远不会 panic
+pub fn substr(s: &str, start: usize, len: usize) -> &str {
+    let mut indices = s
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(s.len()));
+    let Some(begin) = indices.nth(start) else {
+        return "";
+    };
+    let end = match len {
+        0 => begin,
+        _ => indices.nth(len - 1).unwrap_or(s.len()),
+    };
+    &s[begin..end]
+}
+
+/// 把不在字符边界上的字节下标向前挪到最近的边界，用于必须按字节限制长度的场合（例如协议字段）
+pub fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    (0..=index)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// 截断到不超过 max_width 列，被截断时末尾加 "…"（占 1 列），不会切开字素簇
+pub fn truncate(s: &str, max_width: usize) -> Cow<'_, str> {
+    if display_width(s) <= max_width {
+        return Cow::Borrowed(s);
+    }
+    let budget = match max_width.checked_sub(1) {
+        Some(budget) => budget,
+        None => return Cow::Borrowed(""),
+    };
+    let mut width = 0;
+    let mut end = 0;
+    for g in graphemes(s) {
+        let w = grapheme_width(g);
+        if width + w > budget {
+            break;
+        }
+        width += w;
+        end += g.len();
+    }
+    Cow::Owned(format!("{}{}", &s[..end], ELLIPSIS))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// 用空格补齐到 width 列。format! 的 {:<10} 按字符个数补齐，遇到汉字就对不齐了
+pub fn pad(s: &str, width: usize, align: Align) -> String {
+    let fill = width.saturating_sub(display_width(s));
+    let (left, right) = match align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), s, " ".repeat(right))
+}
+
+#[test]
+fn test_char_slicing() {
+    let s = "Hello, 世界!";
+    // &s[0..9] 会切在 "世" 的中间而 panic
+    assert!(!s.is_char_boundary(9));
+    assert_eq!(char_slice(s, 7..9), Some("世界"));
+    assert_eq!(char_slice(s, 7..), Some("世界!"));
+    assert_eq!(char_slice(s, ..=7), Some("Hello, 世"));
+    assert_eq!(char_slice(s, 10..10), Some(""));
+    assert_eq!(char_slice(s, 11..11), None);
+    assert_eq!(char_slice(s, 8..20), None);
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = char_slice(s, 5..3);
+    assert_eq!(reversed, None);
+
+    assert_eq!(substr("中文字符串", 2, 2), "字符");
+    assert_eq!(substr("中文字符串", 3, 100), "符串");
+    assert_eq!(substr("中文字符串", 100, 1), "");
+    assert_eq!(substr("中文", 0, 0), "");
+    assert_eq!(char_len("中文abc"), 5);
+    assert_eq!(floor_char_boundary("中文", 4), 3);
+    assert_eq!(floor_char_boundary("中文", 100), 6);
+}
+
+#[test]
+fn test_graphemes() {
+    // e + 组合重音符、一家三口（ZWJ 序列）、中国国旗（两个区域指示符）、带肤色的点赞
+    let s = "e\u{301}👨\u{200D}👩\u{200D}👧🇨🇳👍🏽中\r\n";
+    let clusters: Vec<&str> = graphemes(s).collect();
+    assert_eq!(
+        clusters,
+        vec![
+            "e\u{301}",
+            "👨\u{200D}👩\u{200D}👧",
+            "🇨🇳",
+            "👍🏽",
+            "中",
+            "\r\n"
+        ]
+    );
+    assert_eq!(char_len(s), 14);
+    assert_eq!(grapheme_slice(s, 1..3), Some("👨\u{200D}👩\u{200D}👧🇨🇳"));
+    assert_eq!(grapheme_slice(s, 4..), Some("中\r\n"));
+    assert_eq!(grapheme_slice(s, 7..), None);
+    // 三个区域指示符: 前两个组成国旗，第三个单独一簇
+    assert_eq!(graphemes("🇯🇵🇰").count(), 2);
+}
+
+#[test]
+fn test_width_truncate_pad() {
+    assert_eq!(display_width("abc"), 3);
+    assert_eq!(display_width("中文"), 4);
+    assert_eq!(display_width("ｆｕｌｌ"), 8);
+    assert_eq!(display_width("e\u{301}"), 1);
+    assert_eq!(display_width("👨\u{200D}👩\u{200D}👧"), 2);
+    assert_eq!(display_width("こんにちは世界"), 14);
+
+    assert_eq!(truncate("short", 10), "short");
+    assert_eq!(truncate("区块链技术入门", 9), "区块链技…");
+    // 剩下的 1 列放不下一个汉字，宁可少一列也不切开字符
+    assert_eq!(truncate("区块链技术入门", 8), "区块链…");
+    assert_eq!(truncate("abc", 0), "");
+    assert!(matches!(truncate("abc", 3), Cow::Borrowed(_)));
+
+    assert_eq!(pad("中文", 6, Align::Left), "中文  ");
+    assert_eq!(pad("中文", 6, Align::Right), "  中文");
+    assert_eq!(pad("中", 5, Align::Center), " 中  ");
+    assert_eq!(pad("超出宽度", 4, Align::Left), "超出宽度");
+
+    // 中英混排的表格，每一列都对齐到相同的显示宽度
+    let rows = [("名称", "Alice"), ("balance", "余额不足")];
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|(k, v)| format!("|{}|{}|", pad(k, 8, Align::Left), pad(v, 10, Align::Right)))
+        .collect();
+    assert!(lines.iter().all(|l| display_width(l) == 21));
+}
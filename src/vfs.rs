@@ -0,0 +1,276 @@
+#![allow(dead_code)]
+
+/*
+ * 虚拟文件系统
+ *      test_error.rs 里的例子直接读当前目录下的 hello.txt，结果取决于从哪个目录运行、文件在不在，
+ *      新检出的仓库里没有这个文件，测试就 panic 了。
+ *
+ *      把 "读写文件" 抽象成 FileSystem trait，有两种实现:
+ *          RealFs    真实的磁盘，所有相对路径都相对于创建时指定的根目录，而不是进程的当前目录
+ *          MemFs     内存里的文件，可以预先放好文件、标记为不可读或只读，
+ *                    这样 NotFound、PermissionDenied、读取成功都能稳定地复现
+ *
+ *      需要读写文件的函数接收 &dyn FileSystem，正式运行时传 RealFs，测试时传 MemFs。
+ */
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    pub len: u64,
+    pub readonly: bool,
+}
+
+pub trait FileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>>;
+
+    /// 文件不存在时创建，存在时覆盖
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.open(path)?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// 内容不是合法的 UTF-8 时返回 InvalidData，和 std::fs::read_to_string 一样
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let mut s = String::new();
+        self.open(path)?.read_to_string(&mut s)?;
+        Ok(s)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+}
+
+pub struct RealFs {
+    root: PathBuf,
+}
+
+impl RealFs {
+    /// NOTE Path::join 遇到绝对路径会直接替换掉 root，所以绝对路径仍然指向原来的位置
+    pub fn new(root: impl Into<PathBuf>) -> RealFs {
+        RealFs { root: root.into() }
+    }
+
+    /// 以仓库根目录（Cargo.toml 所在的目录）为根，不管 cargo test 是从哪个目录运行的
+    pub fn project() -> RealFs {
+        RealFs::new(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl FileSystem for RealFs {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(fs::File::open(self.resolve(path))?))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(self.resolve(path), contents)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let meta = fs::metadata(self.resolve(path))?;
+        Ok(Metadata {
+            len: meta.len(),
+            readonly: meta.permissions().readonly(),
+        })
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(self.resolve(path))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MemFile {
+    contents: Vec<u8>,
+    readable: bool,
+    readonly: bool,
+}
+
+/// 内存文件系统，只有文件没有目录，路径按原样比较（不会把 a/../b 化简成 b）
+#[derive(Debug, Default)]
+pub struct MemFs {
+    files: Mutex<BTreeMap<PathBuf, MemFile>>,
+}
+
+impl MemFs {
+    pub fn new() -> MemFs {
+        MemFs::default()
+    }
+
+    fn insert(
+        self,
+        path: impl Into<PathBuf>,
+        contents: &[u8],
+        readable: bool,
+        readonly: bool,
+    ) -> MemFs {
+        self.files.lock().unwrap().insert(
+            path.into(),
+            MemFile {
+                contents: contents.to_vec(),
+                readable,
+                readonly,
+            },
+        );
+        self
+    }
+
+    pub fn with_file(self, path: impl Into<PathBuf>, contents: impl AsRef<[u8]>) -> MemFs {
+        self.insert(path, contents.as_ref(), true, false)
+    }
+
+    /// 存在但没有读权限，读取时返回 PermissionDenied
+    pub fn with_unreadable(self, path: impl Into<PathBuf>, contents: impl AsRef<[u8]>) -> MemFs {
+        self.insert(path, contents.as_ref(), false, false)
+    }
+
+    /// 可以读，写入和删除时返回 PermissionDenied
+    pub fn with_readonly(self, path: impl Into<PathBuf>, contents: impl AsRef<[u8]>) -> MemFs {
+        self.insert(path, contents.as_ref(), true, true)
+    }
+
+    /// 所有文件的路径，按字典序
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{}: No such file or directory", path.display()),
+    )
+}
+
+fn permission_denied(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("{}: Permission denied", path.display()),
+    )
+}
+
+impl FileSystem for MemFs {
+    /// 返回内容的一份拷贝，之后对文件的修改不影响已经打开的 reader
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let files = self.files.lock().unwrap();
+        let file = files.get(path).ok_or_else(|| not_found(path))?;
+        if !file.readable {
+            return Err(permission_denied(path));
+        }
+        Ok(Box::new(Cursor::new(file.contents.clone())))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        match files.get_mut(path) {
+            Some(file) if file.readonly => Err(permission_denied(path)),
+            Some(file) => {
+                file.contents = contents.to_vec();
+                Ok(())
+            }
+            None => {
+                files.insert(
+                    path.to_path_buf(),
+                    MemFile {
+                        contents: contents.to_vec(),
+                        readable: true,
+                        readonly: false,
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let files = self.files.lock().unwrap();
+        let file = files.get(path).ok_or_else(|| not_found(path))?;
+        Ok(Metadata {
+            len: file.contents.len() as u64,
+            readonly: file.readonly,
+        })
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        match files.get(path) {
+            None => Err(not_found(path)),
+            Some(file) if file.readonly => Err(permission_denied(path)),
+            Some(_) => {
+                files.remove(path);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn test_vfs() {
+    // 同一段逻辑分别在真实磁盘和内存里运行，行为应该一致
+    fn roundtrip(fs: &dyn FileSystem) {
+        let path = Path::new("notes.txt");
+        let err = fs.read_to_string(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(!fs.exists(path));
+
+        fs.write(path, "第一行\n".as_bytes()).unwrap();
+        assert_eq!(fs.read_to_string(path).unwrap(), "第一行\n");
+        assert_eq!(fs.metadata(path).unwrap().len, 10);
+
+        fs.write(path, &[0xff, 0xfe]).unwrap();
+        assert_eq!(fs.read(path).unwrap(), [0xff, 0xfe]);
+        let err = fs.read_to_string(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs.remove_file(path).unwrap();
+        assert_eq!(
+            fs.remove_file(path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    let dir = std::env::temp_dir().join(format!("vfs-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    roundtrip(&RealFs::new(&dir));
+    fs::remove_dir_all(&dir).unwrap();
+    roundtrip(&MemFs::new());
+
+    // NOTE 在真实磁盘上复现 PermissionDenied 并不可靠: 以 root 运行时权限检查会被跳过
+    let fs = MemFs::new()
+        .with_unreadable("secret.txt", "top secret")
+        .with_readonly("config.toml", "port = 8080");
+    let secret = Path::new("secret.txt");
+    let config = Path::new("config.toml");
+    assert_eq!(
+        fs.open(secret).err().unwrap().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+    assert_eq!(fs.metadata(secret).unwrap().len, 10);
+    assert_eq!(fs.read_to_string(config).unwrap(), "port = 8080");
+    assert!(fs.metadata(config).unwrap().readonly);
+    assert_eq!(
+        fs.write(config, b"port = 1").unwrap_err().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+    assert_eq!(fs.paths(), [config, secret]);
+}
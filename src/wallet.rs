@@ -0,0 +1,467 @@
+#![allow(dead_code)]
+
+/*
+ * 钱包与加密的密钥文件（keystore）
+ *      私钥就是资产本身，不能明文放在磁盘上。这里参考以太坊 keystore 的做法:
+ *
+ *      1. 口令 + 随机盐 --PBKDF2--> 64 字节，前 32 字节是加密密钥，后 32 字节是 MAC 密钥
+ *      2. 用 ChaCha20 加密 32 字节的私钥种子，nonce 随机生成
+ *      3. MAC = Keccak-256(MAC 密钥 || 密文)
+ *
+ * 解锁时先校验 MAC 再解密: 口令错了，派生出的 MAC 密钥就不同，MAC 对不上，
+ * 这样就能明确地告诉用户 "口令错误"，而不是解密出一个错误的私钥。
+ *
+ * 每个账户一个文件，文件名是地址的十六进制，内容是 RLP 编码的 KeyFile。
+ * 公开的接口返回 error::Result: io::Error 带上出错的文件路径作为 context 向上传递，
+ * 口令错误、文件损坏这类钱包自己的错误用 WalletError 表示，调用方用 downcast_ref::<WalletError>() 取出来匹配。
+ */
+
+use crate::crypto::chacha20;
+use crate::crypto::ed25519::SecretKey;
+use crate::crypto::hash::{hex, keccak256};
+use crate::crypto::kdf::pbkdf2_hmac_sha256;
+use crate::error::{Context, DomainError, Result};
+use crate::rlp::{rlp_struct, Decodable, Encodable, RlpError};
+use crate::state::{address_of, Address, SignedTransaction, Transaction};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const KEYSTORE_VERSION: u16 = 1;
+
+/// PBKDF2 的默认迭代次数，越大暴力破解越慢，解锁也越慢
+pub const DEFAULT_ITERATIONS: u32 = 100_000;
+
+/// 迭代次数的上限。迭代次数记录在密钥文件里，文件损坏或者被人故意改大，
+/// 解锁就会卡在 PBKDF2 里几个小时，所以超过上限的文件按损坏处理
+pub const MAX_ITERATIONS: u32 = 10_000_000;
+
+const EXTENSION: &str = "key";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletError {
+    /// 文件内容无法解码
+    Malformed(RlpError),
+    UnsupportedVersion(u16),
+    /// MAC 校验失败，几乎总是口令错误（也可能是密文被篡改，两者无法区分）
+    WrongPassword,
+    /// 解密出的私钥和文件记录的地址不一致
+    AddressMismatch,
+    NotFound(Address),
+    AlreadyExists(Address),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // 解码错误的细节由 source() 给出
+            WalletError::Malformed(_) => write!(f, "corrupt key file"),
+            WalletError::UnsupportedVersion(v) => write!(f, "unsupported key file version {}", v),
+            WalletError::WrongPassword => write!(f, "wrong password"),
+            WalletError::AddressMismatch => {
+                write!(f, "corrupt key file: address does not match key")
+            }
+            WalletError::NotFound(address) => write!(f, "no key for address 0x{}", hex(address)),
+            WalletError::AlreadyExists(address) => {
+                write!(f, "key for address 0x{} already exists", hex(address))
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalletError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalletError::Malformed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl DomainError for WalletError {
+    const CODE: u16 = 3002;
+}
+
+impl From<RlpError> for WalletError {
+    fn from(err: RlpError) -> WalletError {
+        WalletError::Malformed(err)
+    }
+}
+
+/// 磁盘上的密钥文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyFile {
+    version: u16,
+    address: Address,
+    salt: [u8; 16],
+    iterations: u32,
+    nonce: [u8; 12],
+    ciphertext: [u8; 32],
+    mac: [u8; 32],
+}
+
+rlp_struct!(KeyFile {
+    version,
+    address,
+    salt,
+    iterations,
+    nonce,
+    ciphertext,
+    mac
+});
+
+/// 口令 -> (加密密钥, MAC 密钥)
+fn derive_keys(password: &str, salt: &[u8], iterations: u32) -> ([u8; 32], [u8; 32]) {
+    let mut derived = [0u8; 64];
+    pbkdf2_hmac_sha256(password.as_bytes(), salt, iterations, &mut derived);
+    (
+        derived[..32].try_into().unwrap(),
+        derived[32..].try_into().unwrap(),
+    )
+}
+
+fn compute_mac(mac_key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    keccak256(&[&mac_key[..], ciphertext].concat())
+}
+
+/// 比较时不提前返回，耗时和第一个不同字节的位置无关
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 从操作系统的熵池读取随机数
+// NOTE /dev/urandom 只在类 Unix 系统上存在
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .context("reading /dev/urandom")?;
+    Ok(buf)
+}
+
+impl KeyFile {
+    fn encrypt(key: &SecretKey, password: &str, iterations: u32) -> Result<KeyFile> {
+        let salt = random_bytes()?;
+        let nonce = random_bytes()?;
+        let (enc_key, mac_key) = derive_keys(password, &salt, iterations);
+        let mut ciphertext = *key.seed();
+        chacha20::apply_keystream(&enc_key, 0, &nonce, &mut ciphertext);
+        Ok(KeyFile {
+            version: KEYSTORE_VERSION,
+            address: address_of(&key.public_key()),
+            salt,
+            iterations,
+            nonce,
+            mac: compute_mac(&mac_key, &ciphertext),
+            ciphertext,
+        })
+    }
+
+    fn decrypt(&self, password: &str) -> Result<SecretKey, WalletError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(WalletError::UnsupportedVersion(self.version));
+        }
+        if !(1..=MAX_ITERATIONS).contains(&self.iterations) {
+            return Err(RlpError::NonCanonical("kdf iterations out of range").into());
+        }
+        let (enc_key, mac_key) = derive_keys(password, &self.salt, self.iterations);
+        if !constant_time_eq(&compute_mac(&mac_key, &self.ciphertext), &self.mac) {
+            return Err(WalletError::WrongPassword);
+        }
+        let mut seed = self.ciphertext;
+        chacha20::apply_keystream(&enc_key, 0, &self.nonce, &mut seed);
+        let key = SecretKey::from_seed(&seed);
+        if address_of(&key.public_key()) != self.address {
+            return Err(WalletError::AddressMismatch);
+        }
+        Ok(key)
+    }
+}
+
+/// 创建一个新文件并写入，文件已存在时失败。unix 上权限是 0600，只有文件的主人能读写
+fn write_private(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// 文件名 "<40 位十六进制地址>.key" -> 地址
+fn parse_file_name(path: &Path) -> Option<Address> {
+    if path.extension()? != EXTENSION {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    if stem.len() != 40 {
+        return None;
+    }
+    let mut address = [0u8; 20];
+    for (i, b) in address.iter_mut().enumerate() {
+        *b = u8::from_str_radix(stem.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(address)
+}
+
+/// 一个目录就是一个钥匙串
+pub struct Keystore {
+    dir: PathBuf,
+    iterations: u32,
+}
+
+impl Keystore {
+    /// 打开钥匙串目录，不存在时自动创建
+    pub fn open(dir: impl AsRef<Path>) -> Result<Keystore> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).with_context(|| format!("creating keystore {}", dir.display()))?;
+        Ok(Keystore {
+            dir: dir.to_path_buf(),
+            iterations: DEFAULT_ITERATIONS,
+        })
+    }
+
+    /// 新建密钥时使用的迭代次数；已有的文件各自记录了自己的迭代次数，不受影响
+    pub fn with_iterations(mut self, iterations: u32) -> Keystore {
+        assert!(
+            (1..=MAX_ITERATIONS).contains(&iterations),
+            "PBKDF2 iterations must be in 1..={}",
+            MAX_ITERATIONS
+        );
+        self.iterations = iterations;
+        self
+    }
+
+    fn path_of(&self, address: &Address) -> PathBuf {
+        self.dir.join(format!("{}.{}", hex(address), EXTENSION))
+    }
+
+    /// 生成新的随机私钥并保存，返回地址
+    pub fn generate(&self, password: &str) -> Result<Address> {
+        let key = SecretKey::from_seed(&random_bytes()?);
+        self.import(&key, password)
+    }
+
+    /// 保存一个已有的私钥。同一个地址不会被覆盖，以免旧口令加密的备份被悄悄替换
+    pub fn import(&self, key: &SecretKey, password: &str) -> Result<Address> {
+        let file = KeyFile::encrypt(key, password, self.iterations)?;
+        let path = self.path_of(&file.address);
+        let context = || format!("writing key file {}", path.display());
+        // 先写一个名字随机的临时文件，写完再硬链接到正式的文件名，写到一半崩溃也不会留下损坏的密钥文件。
+        // NOTE 不用 rename: 它会悄悄覆盖已有的文件；hard_link 在目标存在时失败，
+        // "检查是否存在" 和 "创建" 是同一个原子操作，不会有先检查后创建之间的竞争
+        let tmp = path.with_extension(format!("{}.tmp", hex(&random_bytes::<8>()?)));
+        write_private(&tmp, &file.rlp_bytes()).with_context(context)?;
+        let linked = fs::hard_link(&tmp, &path);
+        let _ = fs::remove_file(&tmp);
+        match linked {
+            Ok(()) => Ok(file.address),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                Err(WalletError::AlreadyExists(file.address).into())
+            }
+            Err(err) => Err(err).with_context(context),
+        }
+    }
+
+    /// 按地址排序列出所有账户，目录里的其它文件被忽略
+    pub fn list(&self) -> Result<Vec<Address>> {
+        let context = || format!("listing keystore {}", self.dir.display());
+        let mut addresses = Vec::new();
+        for entry in fs::read_dir(&self.dir).with_context(context)? {
+            if let Some(address) = parse_file_name(&entry.with_context(context)?.path()) {
+                addresses.push(address);
+            }
+        }
+        addresses.sort();
+        Ok(addresses)
+    }
+
+    fn load(&self, address: &Address) -> Result<KeyFile> {
+        let path = self.path_of(address);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(WalletError::NotFound(*address).into())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading key file {}", path.display()))
+            }
+        };
+        // NOTE 直接 ? 会把 RlpError 转换成 AppError 的 2004，这里要的是 WalletError::Malformed
+        let file = KeyFile::decode_rlp(&bytes).map_err(WalletError::from)?;
+        // 文件被改名或者拷错了位置
+        if file.address != *address {
+            return Err(WalletError::AddressMismatch.into());
+        }
+        Ok(file)
+    }
+
+    /// 用口令解密出私钥
+    pub fn unlock(&self, address: &Address, password: &str) -> Result<SecretKey> {
+        Ok(self.load(address)?.decrypt(password)?)
+    }
+
+    pub fn sign_transaction(
+        &self,
+        address: &Address,
+        password: &str,
+        tx: Transaction,
+    ) -> Result<SignedTransaction> {
+        Ok(tx.sign(&self.unlock(address, password)?))
+    }
+
+    /// 导出明文私钥种子（十六进制），用于迁移到其它钱包
+    pub fn export(&self, address: &Address, password: &str) -> Result<String> {
+        Ok(hex(self.unlock(address, password)?.seed()))
+    }
+
+    pub fn delete(&self, address: &Address, password: &str) -> Result<()> {
+        // NOTE 删除前要求口令，防止误删别人的密钥
+        self.unlock(address, password)?;
+        let path = self.path_of(address);
+        fs::remove_file(&path).with_context(|| format!("deleting key file {}", path.display()))
+    }
+}
+
+/// 每个测试用自己的目录，测试并行运行时互不干扰
+#[cfg(test)]
+fn test_keystore(name: &str) -> Keystore {
+    let dir = std::env::temp_dir().join(format!("wallet-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    // NOTE 测试里把迭代次数调低，否则 debug 构建下每次解锁都要等上好几秒
+    Keystore::open(dir).unwrap().with_iterations(1000)
+}
+
+#[test]
+fn test_keystore_roundtrip() {
+    use crate::evm::word::Word;
+    use crate::state::State;
+
+    let keystore = test_keystore("roundtrip");
+    let alice = keystore.generate("alice password").unwrap();
+    let seed = [7u8; 32];
+    let bob = keystore
+        .import(&SecretKey::from_seed(&seed), "bob password")
+        .unwrap();
+    let mut expected = vec![alice, bob];
+    expected.sort();
+    assert_eq!(keystore.list().unwrap(), expected);
+
+    // 只有主人能读写密钥文件，临时文件没有留下
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(keystore.path_of(&bob))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    assert_eq!(fs::read_dir(&keystore.dir).unwrap().count(), 2);
+
+    // 磁盘上只有密文
+    let raw = fs::read(keystore.path_of(&bob)).unwrap();
+    assert!(!raw.windows(32).any(|w| w == seed));
+    assert_eq!(keystore.export(&bob, "bob password").unwrap(), hex(&seed));
+
+    // 用钥匙串签名的交易可以直接被状态机执行
+    let mut state = State::new();
+    state.credit(alice, Word::from_u64(1_000_000)).unwrap();
+    let tx = Transaction {
+        nonce: 0,
+        to: bob,
+        value: Word::from_u64(500),
+        gas_limit: 21000,
+        gas_price: 1,
+        data: Vec::new(),
+    };
+    let stx = keystore
+        .sign_transaction(&alice, "alice password", tx)
+        .unwrap();
+    assert_eq!(stx.sender(), Ok(alice));
+    assert!(state.apply(&stx).unwrap().success);
+    assert_eq!(state.balance(&bob), Word::from_u64(500));
+
+    let err = keystore
+        .import(&SecretKey::from_seed(&seed), "other")
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<WalletError>(),
+        Some(WalletError::AlreadyExists(a)) if *a == bob
+    ));
+    keystore.delete(&alice, "alice password").unwrap();
+    assert_eq!(keystore.list().unwrap(), vec![bob]);
+    fs::remove_dir_all(&keystore.dir).unwrap();
+}
+
+#[test]
+fn test_keystore_errors() {
+    let keystore = test_keystore("errors");
+    let address = keystore.generate("correct horse").unwrap();
+    let path = keystore.path_of(&address);
+    let original = fs::read(&path).unwrap();
+
+    let err = keystore.unlock(&address, "battery staple").unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<WalletError>(),
+        Some(WalletError::WrongPassword)
+    ));
+    assert_eq!(err.to_string(), "wrong password");
+    assert_eq!(err.code(), 3002);
+    let unlock_err = |address: &Address| {
+        let err = keystore.unlock(address, "correct horse").unwrap_err();
+        err.downcast_ref::<WalletError>().cloned()
+    };
+    assert_eq!(unlock_err(&[0; 20]), Some(WalletError::NotFound([0; 20])));
+
+    // 截断的文件
+    fs::write(&path, &original[..original.len() / 2]).unwrap();
+    let err = keystore.unlock(&address, "correct horse").unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<WalletError>(),
+        Some(WalletError::Malformed(_))
+    ));
+    assert!(err.to_string().starts_with("corrupt key file"));
+
+    // 篡改密文: MAC 对不上
+    let mut file = KeyFile::decode_rlp(&original).unwrap();
+    file.ciphertext[0] ^= 1;
+    fs::write(&path, file.rlp_bytes()).unwrap();
+    assert_eq!(unlock_err(&address), Some(WalletError::WrongPassword));
+
+    let mut file = KeyFile::decode_rlp(&original).unwrap();
+    file.version = 2;
+    fs::write(&path, file.rlp_bytes()).unwrap();
+    assert_eq!(
+        unlock_err(&address),
+        Some(WalletError::UnsupportedVersion(2))
+    );
+
+    // 迭代次数被改得很大: 直接按损坏处理，而不是卡在 PBKDF2 里
+    let mut file = KeyFile::decode_rlp(&original).unwrap();
+    file.iterations = u32::MAX;
+    fs::write(&path, file.rlp_bytes()).unwrap();
+    assert_eq!(
+        unlock_err(&address),
+        Some(WalletError::Malformed(RlpError::NonCanonical(
+            "kdf iterations out of range"
+        )))
+    );
+
+    // 文件被改名成另一个地址
+    let other = [0xab; 20];
+    fs::write(keystore.path_of(&other), &original).unwrap();
+    assert_eq!(unlock_err(&other), Some(WalletError::AddressMismatch));
+
+    // 无关的文件不会出现在列表里
+    fs::write(keystore.dir.join("README.txt"), "not a key").unwrap();
+    fs::write(&path, &original).unwrap();
+    assert_eq!(keystore.list().unwrap().len(), 2);
+    assert!(keystore.unlock(&address, "correct horse").is_ok());
+    fs::remove_dir_all(&keystore.dir).unwrap();
+}
@@ -0,0 +1,232 @@
+#![allow(dead_code)]
+
+/*
+ * 中英混排文本的词频统计
+ *      英文按单词统计（忽略大小写，保留 don't 这样的撇号），
+ *      中文没有空格分词，这里按单个汉字（以及假名）统计字频。
+ *
+ * 用到的集合:
+ *      HashMap                 计数，entry API 一次查找完成 "没有就插入 0，然后加 1"
+ *      HashSet<String>         停用词表，O(1) 判断一个词是否要忽略
+ *      BinaryHeap              top-k: 只维护 k 个元素的小顶堆，不必把所有词排序
+ *      BTreeMap<&str, usize>   按字典序输出完整的词表
+ */
+
+use crate::error::{Context, Result};
+use crate::text::{display_width, pad, Align};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// 汉字和日文假名，每个字单独计数
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3041}'..='\u{30FF}'     // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}'   // CJK 扩展 A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK 统一汉字
+        | '\u{F900}'..='\u{FAFF}'   // CJK 兼容汉字
+        | '\u{20000}'..='\u{2FFFD}')
+}
+
+/// 出现次数最多的 k 个，次数相同时按键的升序，保证输出稳定
+pub fn top_k<K: Ord + Hash + Clone>(counts: &HashMap<K, usize>, k: usize) -> Vec<(K, usize)> {
+    // 堆顶是目前 "最差" 的元素: 次数最少，次数相同时键最大
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (key, &count) in counts {
+        heap.push(Reverse((count, Reverse(key))));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    // into_sorted_vec 是升序，对 Reverse 来说就是从最好到最差
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((count, Reverse(key)))| (key.clone(), count))
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct WordFreq {
+    words: HashMap<String, usize>,
+    chars: HashMap<char, usize>,
+    stopwords: HashSet<String>,
+}
+
+impl WordFreq {
+    pub fn new() -> WordFreq {
+        WordFreq::default()
+    }
+
+    /// 忽略这些词（不区分大小写），例如 the、a、of
+    pub fn with_stopwords<'a>(mut self, words: impl IntoIterator<Item = &'a str>) -> WordFreq {
+        self.stopwords
+            .extend(words.into_iter().map(str::to_lowercase));
+        self
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        let mut word = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            // 撇号夹在两个字母之间才算单词的一部分: don't 是一个词，'quoted' 两边的引号不是
+            let apostrophe = c == '\''
+                && !word.is_empty()
+                && chars
+                    .peek()
+                    .is_some_and(|n| n.is_alphanumeric() && !is_cjk(*n));
+            if (c.is_alphanumeric() && !is_cjk(c)) || apostrophe {
+                word.extend(c.to_lowercase());
+                continue;
+            }
+            self.finish_word(&mut word);
+            if is_cjk(c) {
+                *self.chars.entry(c).or_insert(0) += 1;
+            }
+        }
+        self.finish_word(&mut word);
+    }
+
+    fn finish_word(&mut self, word: &mut String) {
+        if word.is_empty() {
+            return;
+        }
+        if !self.stopwords.contains(word.as_str()) {
+            // NOTE 这里没有用 entry: entry 的参数必须是拥有所有权的 String，每个单词都要分配一次；
+            // 先用 &str 查找，只有第一次出现的单词才需要分配
+            match self.words.get_mut(word.as_str()) {
+                Some(count) => *count += 1,
+                None => {
+                    self.words.insert(word.clone(), 1);
+                }
+            }
+        }
+        word.clear();
+    }
+
+    /// 按行读取文件，不会一次性把整个文件读进内存
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let context = || format!("reading {}", path.display());
+        let reader = BufReader::new(File::open(path).with_context(context)?);
+        for line in reader.lines() {
+            self.add_text(&line.with_context(context)?);
+        }
+        Ok(())
+    }
+
+    pub fn word_count(&self, word: &str) -> usize {
+        self.words.get(&word.to_lowercase()).copied().unwrap_or(0)
+    }
+
+    pub fn char_count(&self, c: char) -> usize {
+        self.chars.get(&c).copied().unwrap_or(0)
+    }
+
+    /// 单词总数（含重复）
+    pub fn total_words(&self) -> usize {
+        self.words.values().sum()
+    }
+
+    pub fn total_chars(&self) -> usize {
+        self.chars.values().sum()
+    }
+
+    /// 不同单词的集合
+    pub fn vocabulary(&self) -> HashSet<&str> {
+        self.words.keys().map(String::as_str).collect()
+    }
+
+    pub fn top_words(&self, k: usize) -> Vec<(String, usize)> {
+        top_k(&self.words, k)
+    }
+
+    pub fn top_chars(&self, k: usize) -> Vec<(char, usize)> {
+        top_k(&self.chars, k)
+    }
+
+    /// 按字典序排列的完整词表
+    pub fn sorted_words(&self) -> BTreeMap<&str, usize> {
+        self.words.iter().map(|(w, c)| (w.as_str(), *c)).collect()
+    }
+
+    /// 两列对齐的 top-k 报告，单词和汉字并排
+    pub fn report(&self, k: usize) -> String {
+        let words = self.top_words(k);
+        let chars = self.top_chars(k);
+        let left_width = words
+            .iter()
+            .map(|(w, c)| display_width(w) + c.to_string().len() + 1)
+            .chain(std::iter::once(display_width("单词")))
+            .max()
+            .unwrap_or(0);
+
+        let mut out = format!("{}  {}\n", pad("单词", left_width, Align::Left), "汉字");
+        for i in 0..words.len().max(chars.len()) {
+            let left = words
+                .get(i)
+                .map(|(w, c)| format!("{} {}", w, c))
+                .unwrap_or_default();
+            let right = chars
+                .get(i)
+                .map(|(ch, c)| format!("{} {}", ch, c))
+                .unwrap_or_default();
+            let line = format!("{}  {}", pad(&left, left_width, Align::Left), right);
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[test]
+fn test_word_frequency() {
+    let mut freq = WordFreq::new().with_stopwords(["the", "a", "of"]);
+    freq.add_text("The Rust book: Rust's ownership is the heart of Rust. 所有权是 Rust 的核心，");
+    freq.add_text("我们 don't panic, we return Result. 错误处理也是 Rust 的核心。");
+
+    assert_eq!(freq.word_count("rust"), 4);
+    assert_eq!(freq.word_count("RUST"), 4);
+    assert_eq!(freq.word_count("rust's"), 1);
+    assert_eq!(freq.word_count("don't"), 1);
+    assert_eq!(freq.word_count("the"), 0);
+    assert_eq!(freq.char_count('核'), 2);
+    assert_eq!(freq.char_count('，'), 0);
+    assert_eq!(freq.total_chars(), 18);
+
+    assert_eq!(
+        freq.top_words(3),
+        vec![
+            ("rust".to_string(), 4),
+            ("book".to_string(), 1),
+            ("don't".to_string(), 1),
+        ]
+    );
+    assert_eq!(freq.top_chars(2), vec![('心', 2), ('是', 2)]);
+    assert_eq!(freq.top_words(100).len(), freq.vocabulary().len());
+    assert!(freq.sorted_words().keys().is_sorted());
+
+    let report = freq.report(2);
+    println!("{}", report);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[1], "rust 4  心 2");
+    // 中文表头和英文内容按显示宽度对齐
+    assert_eq!(display_width(lines[0]), display_width("rust 4  汉字"));
+}
+
+#[test]
+fn test_word_frequency_file() {
+    let path = std::env::temp_dir().join(format!("wordfreq-{}.txt", std::process::id()));
+    std::fs::write(&path, "hello world\n你好，世界\nhello 世界\n").unwrap();
+    let mut freq = WordFreq::new();
+    freq.add_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(freq.top_words(1), vec![("hello".to_string(), 2)]);
+    assert_eq!(freq.top_chars(2), vec![('世', 2), ('界', 2)]);
+
+    let err = freq.add_file(&path).unwrap_err();
+    assert_eq!(err.io_kind(), Some(std::io::ErrorKind::NotFound));
+    assert_eq!(err.to_string(), format!("reading {}", path.display()));
+}